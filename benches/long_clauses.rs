@@ -0,0 +1,58 @@
+//! Solves random formulas dominated by long clauses, which makes the search for replacement
+//! watched literals the hot path
+//!
+//! Run with `cargo bench --bench long_clauses`.
+
+use std::time::Instant;
+
+use satsolver::cnf::{Clause, Cnf, Var};
+use satsolver::rng::{random_cnf, Rng};
+use satsolver::satsolve::Solver;
+
+const NUM_VARS: Var = 400;
+const LONG_CLAUSES: usize = 1000;
+const LONG_CLAUSE_LEN: usize = 300;
+/// The short clauses are random 3-clauses over the first variables, forcing some search
+const SHORT_CLAUSE_VARS: Var = 30;
+const SHORT_CLAUSES: usize = 120;
+
+/// The chance of a literal of a long clause to be negative is `1 / NEGATIVE_ODDS`
+const NEGATIVE_ODDS: u64 = 20;
+
+fn random_formula(seed: u64) -> Cnf {
+    let mut rng = Rng::new(seed);
+    let mut cnf = random_cnf(&mut rng, SHORT_CLAUSE_VARS, SHORT_CLAUSES, 3..=3);
+    // mostly positive, so deciding variables false first slowly falsifies the long clauses
+    let long = random_cnf(
+        &mut rng,
+        NUM_VARS,
+        LONG_CLAUSES,
+        LONG_CLAUSE_LEN..=LONG_CLAUSE_LEN,
+    );
+    for clause in long.clauses {
+        let literals = clause
+            .literals()
+            .map(|(var, _)| (var, rng.below(NEGATIVE_ODDS) != 0))
+            .collect::<Vec<_>>();
+        cnf.clauses.push(Clause::from_literals(&literals));
+    }
+    cnf
+}
+
+fn main() {
+    let formulas = (0..10).map(random_formula).collect::<Vec<_>>();
+
+    let start = Instant::now();
+    let mut satisfiable = 0;
+    for cnf in formulas {
+        if Solver::new(cnf).solve().is_sat() {
+            satisfiable += 1;
+        }
+    }
+    let elapsed = start.elapsed();
+
+    eprintln!(
+        "long_clauses: solved 10 formulas ({} satisfiable) in {:?}",
+        satisfiable, elapsed
+    );
+}
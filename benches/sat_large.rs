@@ -0,0 +1,93 @@
+//! Solves the instance of the `test_sat_large` test over and over, a search dominated by
+//! reading, extending and cloning assignments
+//!
+//! Run with `cargo bench --bench sat_large`.
+
+use std::time::Instant;
+
+use satsolver::input::parse_dimacs;
+use satsolver::satsolve::is_satisfiable;
+
+const RUNS: usize = 20_000;
+
+const INSTANCE: &str = "\
+p cnf 19 68
+-2 -8 -10 11 -12 -13 -14 17 -19 0
+-1 -2 -3 -4 -5 -6 7 -8 9 10 -11 -12 -13 14 -15 -16 17 -18 -19 0
+-15 0
+-1 -2 -6 -9 10 14 -15 -16 17 0
+1 2 -3 -4 5 -6 -7 9 -10 -11 12 -13 -14 -15 16 17 -18 -19 0
+-1 2 -4 -6 -7 8 -9 12 13 15 -16 -19 0
+1 -2 3 4 -5 6 7 -8 9 -10 12 13 14 15 -17 18 19 0
+1 -2 -3 -4 5 6 -7 -8 -9 10 11 12 13 14 -15 -16 -17 -18 19 0
+1 15 18 0
+-9 -12 17 0
+-1 9 19 0
+-6 9 17 0
+1 -11 14 0
+2 5 -7 -10 14 0
+2 3 4 -5 -6 -7 -8 -9 10 14 -15 16 17 19 0
+2 -3 4 8 9 12 15 -17 0
+-1 -2 3 4 5 6 -7 8 -9 10 11 12 -13 -14 15 16 17 18 -19 0
+-1 -2 -3 4 -5 7 8 -9 10 -12 -13 -15 18 19 0
+-13 -16 18 0
+-5 14 0
+-1 2 3 -4 -5 6 -7 8 9 10 11 -12 13 14 15 16 17 -18 0
+1 2 -3 4 5 6 -7 8 10 11 -12 -13 14 15 18 -19 0
+1 4 -7 10 -11 12 -18 19 0
+-1 -6 -8 11 -14 -18 0
+-1 5 -6 -7 8 -12 13 14 15 16 -19 0
+1 2 3 -4 5 -8 9 -11 -12 13 -14 -15 16 -17 -18 -19 0
+-2 5 6 -8 11 14 -17 -18 19 0
+-1 2 -3 4 5 -6 -7 8 9 11 -13 14 15 16 18 -19 0
+1 2 4 5 -6 8 -9 14 15 -16 17 0
+-4 9 -11 -14 17 -18 0
+-3 -8 9 15 -18 0
+1 -2 4 -5 -6 7 8 -11 12 13 14 15 16 -17 19 0
+-2 3 -4 5 -6 -7 8 -10 11 -12 -13 14 15 -18 -19 0
+1 2 -3 4 5 6 -7 8 9 10 -11 -12 -13 -14 15 -16 18 -19 0
+3 4 5 -7 9 10 -12 -13 15 -16 19 0
+-1 2 4 -5 6 -7 8 -9 -11 12 -13 16 17 -18 -19 0
+7 -18 0
+1 2 3 -4 5 6 -7 -9 -10 11 -13 14 15 -16 -17 -18 19 0
+1 -2 5 9 12 14 -15 0
+8 16 0
+2 -4 -6 -7 8 9 10 13 14 16 17 -19 0
+1 3 6 10 -13 16 -18 0
+1 2 -3 4 -5 -6 7 -8 9 10 -11 -12 -13 14 -15 -16 -17 -18 0
+1 2 3 -4 5 6 7 8 -9 10 -11 12 13 14 -15 16 17 18 0
+2 6 -10 -11 13 14 15 19 0
+-1 2 3 4 -5 -6 7 8 10 11 -12 13 14 15 16 18 19 0
+1 -5 -6 7 8 -9 -10 -11 12 -13 -14 15 16 17 -18 -19 0
+-2 3 -4 -5 -6 -7 10 13 -14 -18 19 0
+-1 -2 3 5 6 -7 -8 -9 10 -12 -13 -14 15 16 18 -19 0
+1 3 -4 5 -8 -9 11 -12 13 -14 15 -16 -17 19 0
+-4 -12 19 0
+-3 -11 12 0
+1 -7 -19 0
+-1 10 -11 15 18 0
+-1 -3 4 -5 7 -8 11 -12 14 15 16 18 0
+1 -2 3 8 -10 11 -14 16 0
+2 7 -8 10 15 17 0
+-1 5 7 -8 -9 12 -15 18 0
+-7 18 0
+-1 -2 -3 -4 -5 -6 -7 -8 -10 -12 15 16 17 19 0
+11 0
+-2 -4 6 8 -12 -16 -18 0
+-1 2 3 -4 5 6 7 -9 -10 11 12 16 -17 19 0
+7 8 -12 0
+-2 -11 15 0
+2 -3 5 6 -9 -10 11 -14 15 17 -18 19 0
+1 -2 4 -5 -6 7 -8 -9 10 -12 14 -15 16 -17 18 19 0
+-1 2 -3 4 5 -6 -7 8 -9 10 11 12 -13 14 -15 16 -17 18 19 0
+";
+
+fn main() {
+    let cnf = parse_dimacs(INSTANCE).unwrap();
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        assert!(is_satisfiable(&cnf).0);
+    }
+    eprintln!("sat_large: solved {} times in {:?}", RUNS, start.elapsed());
+}
@@ -0,0 +1,40 @@
+//! Solves a batch of tiny random formulas, where allocating the solver state for every
+//! formula is a noticeable part of the work
+//!
+//! Run with `cargo bench --bench tiny_batch`.
+
+use std::time::Instant;
+
+use satsolver::cnf::{Cnf, Var};
+use satsolver::rng::{random_cnf, Rng};
+use satsolver::satsolve::{is_satisfiable, is_satisfiable_with_scratch, SolverScratch};
+
+const FORMULAS: u64 = 20_000;
+const NUM_VARS: Var = 8;
+const CLAUSES: usize = 30;
+
+fn random_formula(seed: u64) -> Cnf {
+    random_cnf(&mut Rng::new(seed), NUM_VARS, CLAUSES, 3..=3)
+}
+
+fn main() {
+    let formulas = (0..FORMULAS).map(random_formula).collect::<Vec<_>>();
+
+    let start = Instant::now();
+    let fresh = formulas.iter().filter(|cnf| is_satisfiable(cnf).0).count();
+    let fresh_elapsed = start.elapsed();
+
+    let start = Instant::now();
+    let mut scratch = SolverScratch::default();
+    let reused = formulas
+        .iter()
+        .filter(|cnf| is_satisfiable_with_scratch(cnf, &mut scratch).0)
+        .count();
+    let reused_elapsed = start.elapsed();
+
+    assert_eq!(fresh, reused);
+    eprintln!(
+        "tiny_batch: solved {} formulas ({} satisfiable) in {:?} without and {:?} with scratch",
+        FORMULAS, fresh, fresh_elapsed, reused_elapsed
+    );
+}
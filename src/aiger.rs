@@ -0,0 +1,358 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+
+use crate::cnf::{negate, Clause, Cnf, LiteralTpl, Var};
+
+/// An error encountered while parsing an ASCII AIGER file
+///
+/// Line numbers start at 1.
+#[derive(Debug, PartialEq, Eq)]
+pub enum AigerError {
+    /// The `aag M I L O A` header is missing or malformed, or the maximal variable `M` is
+    /// smaller than the `I + L + A` variables it has to cover
+    InvalidHeader,
+    /// A line does not contain the expected literals or a literal is out of range
+    InvalidLine { line: usize, text: String },
+    /// The file ends before all inputs, latches, outputs and gates were read
+    UnexpectedEnd,
+    /// A variable is defined twice, e.g. as an input and as the output of a gate
+    Redefined { line: usize, var: u32 },
+}
+
+impl Display for AigerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AigerError::InvalidHeader => write!(f, "line 1: expected header 'aag M I L O A'"),
+            AigerError::InvalidLine { line, text } => {
+                write!(f, "line {}: invalid line '{}'", line, text)
+            }
+            AigerError::UnexpectedEnd => write!(f, "unexpected end of file"),
+            AigerError::Redefined { line, var } => {
+                write!(f, "line {}: variable {} is defined twice", line, var)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AigerError {}
+
+/// A combinational circuit translated into a formula
+///
+/// AIGER variable `v` is represented by the formula variable `v`. The formula only
+/// constrains every AND gate output to the conjunction of its inputs, the outputs of the
+/// circuit are not asserted. To ask whether an output can become true, solve under the
+/// assumption of its literal or add it as a unit clause.
+///
+/// Latches are cut: their current state is an unconstrained variable like an input and
+/// their next state function is ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aig {
+    pub cnf: Cnf,
+    pub inputs: Vec<Var>,
+    pub latches: Vec<Var>,
+    /// The literals of the outputs in the formula
+    pub outputs: Vec<LiteralTpl>,
+    max_var: Var,
+    /// Variable which is forced to false, used for the AIGER constants 0 and 1
+    constant: Option<Var>,
+}
+
+impl Aig {
+    /// Maps an AIGER literal `2 * v + negated` to a literal of the formula
+    ///
+    /// The constants 0 (false) and 1 (true) are only representable if the circuit uses
+    /// them, otherwise this function returns None for them.
+    pub fn literal(&self, aiger_lit: u32) -> Option<LiteralTpl> {
+        let var = match aiger_lit / 2 {
+            0 => self.constant?,
+            var => var,
+        };
+        Some((var, aiger_lit & 1 == 0))
+    }
+}
+
+/// Parses a combinational circuit in the ASCII AIGER format (`aag`)
+///
+/// The AND gates are encoded into clauses by the Tseitin transformation. Symbol table and
+/// comment section following the gates are ignored.
+pub fn parse_aiger(input: &str) -> Result<Aig, AigerError> {
+    let mut lines = input.lines().enumerate().map(|(idx, line)| (idx + 1, line));
+
+    let header = lines
+        .next()
+        .map(|(_, line)| line.split_ascii_whitespace().collect::<Vec<_>>())
+        .ok_or(AigerError::InvalidHeader)?;
+    let counts = match header.split_first() {
+        Some((&"aag", counts)) if counts.len() == 5 => counts
+            .iter()
+            .map(|count| count.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| AigerError::InvalidHeader)?,
+        _ => return Err(AigerError::InvalidHeader),
+    };
+    let (max_var, num_inputs, num_latches, num_outputs, num_ands) =
+        (counts[0], counts[1], counts[2], counts[3], counts[4]);
+    let num_defined = u64::from(num_inputs) + u64::from(num_latches) + u64::from(num_ands);
+    if max_var >= Var::MAX / 2 || u64::from(max_var) < num_defined {
+        return Err(AigerError::InvalidHeader);
+    }
+
+    // the header is not trusted for allocations, `M` may be far larger than the file
+    let mut parser = Parser {
+        lines,
+        max_var,
+        defined: HashSet::new(),
+    };
+    let mut aig = Aig {
+        cnf: Cnf::new(),
+        inputs: Vec::new(),
+        latches: Vec::new(),
+        outputs: Vec::new(),
+        max_var,
+        constant: None,
+    };
+
+    for _ in 0..num_inputs {
+        let (line, lits) = parser.next_line(1..=1)?;
+        aig.inputs.push(parser.define(line, lits[0])?);
+    }
+    for _ in 0..num_latches {
+        // current state, next state and an optional reset value
+        let (line, lits) = parser.next_line(2..=3)?;
+        aig.latches.push(parser.define(line, lits[0])?);
+    }
+    let mut output_lits = Vec::new();
+    for _ in 0..num_outputs {
+        let (_, lits) = parser.next_line(1..=1)?;
+        output_lits.push(lits[0]);
+    }
+    for _ in 0..num_ands {
+        let (line, lits) = parser.next_line(3..=3)?;
+        let gate = parser.define(line, lits[0])?;
+        let (lhs, rhs) = (aig.map_literal(lits[1]), aig.map_literal(lits[2]));
+        aig.cnf.clauses.extend(and_gate(gate, lhs, rhs));
+    }
+
+    for lit in output_lits {
+        let lit = aig.map_literal(lit);
+        aig.outputs.push(lit);
+    }
+
+    if let Some(constant) = aig.constant {
+        aig.cnf
+            .clauses
+            .push(Clause::from_literals(&[(constant, false)]));
+    }
+
+    Ok(aig)
+}
+
+impl Aig {
+    /// Like [Aig::literal], but allocates the constant variable if it is needed
+    fn map_literal(&mut self, aiger_lit: u32) -> LiteralTpl {
+        if aiger_lit < 2 && self.constant.is_none() {
+            self.constant = Some(self.max_var + 1);
+        }
+        self.literal(aiger_lit)
+            .expect("constant variable is allocated")
+    }
+}
+
+struct Parser<'a, I: Iterator<Item = (usize, &'a str)>> {
+    lines: I,
+    max_var: u32,
+    /// The variables which are already an input, latch or gate output
+    defined: HashSet<Var>,
+}
+
+impl<'a, I: Iterator<Item = (usize, &'a str)>> Parser<'a, I> {
+    /// Reads the next line, which has to consist of `count` literals
+    fn next_line(&mut self, count: RangeInclusive<usize>) -> Result<(usize, Vec<u32>), AigerError> {
+        let (line, text) = self.lines.next().ok_or(AigerError::UnexpectedEnd)?;
+        let invalid = || AigerError::InvalidLine {
+            line,
+            text: text.to_string(),
+        };
+
+        let lits = text
+            .split_ascii_whitespace()
+            .map(|token| token.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+
+        if !count.contains(&lits.len()) || lits.iter().any(|&lit| lit / 2 > self.max_var) {
+            return Err(invalid());
+        }
+        Ok((line, lits))
+    }
+
+    /// Marks the variable of a defining literal, which has to be positive and not constant
+    fn define(&mut self, line: usize, aiger_lit: u32) -> Result<Var, AigerError> {
+        let var = aiger_lit / 2;
+        if var == 0 || aiger_lit & 1 == 1 {
+            return Err(AigerError::InvalidLine {
+                line,
+                text: aiger_lit.to_string(),
+            });
+        }
+        if !self.defined.insert(var) {
+            return Err(AigerError::Redefined { line, var });
+        }
+        Ok(var)
+    }
+}
+
+/// Encodes `gate <-> lhs & rhs`
+fn and_gate(gate: Var, lhs: LiteralTpl, rhs: LiteralTpl) -> Vec<Clause> {
+    let gate_lits = [
+        vec![(gate, false), lhs],
+        vec![(gate, false), rhs],
+        vec![(gate, true), negate(lhs), negate(rhs)],
+    ];
+
+    gate_lits
+        .iter()
+        .filter_map(|lits| {
+            let mut lits = lits.clone();
+            lits.sort_unstable();
+            lits.dedup();
+            // a clause containing a literal and its negation is always satisfied
+            let tautology = lits.windows(2).any(|pair| pair[0].0 == pair[1].0);
+            if tautology {
+                None
+            } else {
+                Some(Clause::from_literals(&lits))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::satsolve::Solver;
+
+    /// Solves the circuit for every input combination and compares the first output
+    fn check_function(aig: &Aig, function: impl Fn(&[bool]) -> bool) {
+        let mut solver = Solver::new(aig.cnf.clone());
+        for bits in 0..1u32 << aig.inputs.len() {
+            let values = (0..aig.inputs.len())
+                .map(|i| bits & (1 << i) != 0)
+                .collect::<Vec<_>>();
+            let mut assumptions = aig
+                .inputs
+                .iter()
+                .zip(&values)
+                .map(|(&var, &val)| (var, val))
+                .collect::<Vec<_>>();
+            assumptions.push(aig.outputs[0]);
+
+            assert_eq!(
+                solver.solve_under_assumptions(&assumptions).is_sat(),
+                function(&values),
+                "inputs {:?}",
+                values
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_aiger_and() {
+        let aig = parse_aiger("aag 3 2 0 1 1\n2\n4\n6\n6 2 4\n").unwrap();
+        assert_eq!(aig.inputs, vec![1, 2]);
+        assert_eq!(aig.outputs, vec![(3, true)]);
+        assert_eq!(aig.cnf.clauses.len(), 3);
+        assert_eq!(aig.literal(7), Some((3, false)));
+        assert_eq!(aig.literal(0), None);
+        check_function(&aig, |v| v[0] && v[1]);
+    }
+
+    #[test]
+    fn test_parse_aiger_xor() {
+        // output is the negation of (!(a & b) & !(!a & !b))
+        let input = "aag 5 2 0 1 3\n2\n4\n11\n6 2 4\n8 3 5\n10 7 9\ni0 a\nc\ncomment\n";
+        let aig = parse_aiger(input).unwrap();
+        check_function(&aig, |v| v[0] == v[1]);
+    }
+
+    #[test]
+    fn test_parse_aiger_constants() {
+        let aig = parse_aiger("aag 2 1 0 2 1\n2\n0\n4\n4 2 1\n").unwrap();
+        assert_eq!(aig.literal(0), Some((3, true)));
+        assert_eq!(aig.literal(1), Some((3, false)));
+        // the constant false output is never true
+        assert!(!Solver::new(aig.cnf.clone())
+            .solve_under_assumptions(&[aig.outputs[0]])
+            .is_sat());
+
+        // gate 2 is input 1 and true
+        let and_true = Aig {
+            outputs: vec![aig.outputs[1]],
+            ..aig
+        };
+        check_function(&and_true, |v| v[0]);
+    }
+
+    #[test]
+    fn test_parse_aiger_latches() {
+        let aig = parse_aiger("aag 3 1 1 1 1\n2\n4 6 0\n6\n6 2 4\n").unwrap();
+        assert_eq!(aig.inputs, vec![1]);
+        assert_eq!(aig.latches, vec![2]);
+    }
+
+    #[test]
+    fn test_parse_aiger_errors() {
+        assert_eq!(parse_aiger(""), Err(AigerError::InvalidHeader));
+        assert_eq!(
+            parse_aiger("aig 1 1 0 0 0\n2\n"),
+            Err(AigerError::InvalidHeader)
+        );
+        assert_eq!(
+            parse_aiger("aag 1 1 0 0\n2\n"),
+            Err(AigerError::InvalidHeader)
+        );
+        assert_eq!(
+            parse_aiger("aag 2 2 0 0 0\n2\n"),
+            Err(AigerError::UnexpectedEnd)
+        );
+        assert_eq!(
+            parse_aiger("aag 1 1 0 0 0\n3\n"),
+            Err(AigerError::InvalidLine {
+                line: 2,
+                text: "3".to_string()
+            })
+        );
+        assert_eq!(
+            parse_aiger("aag 1 0 0 1 0\n4\n"),
+            Err(AigerError::InvalidLine {
+                line: 2,
+                text: "4".to_string()
+            })
+        );
+        assert_eq!(
+            parse_aiger("aag 2 1 0 0 1\n2\n2 0 1\n"),
+            Err(AigerError::Redefined { line: 3, var: 1 })
+        );
+        // fewer variables than inputs and gates
+        assert_eq!(
+            parse_aiger("aag 1 1 0 0 1\n2\n2 0 1\n"),
+            Err(AigerError::InvalidHeader)
+        );
+        assert_eq!(
+            parse_aiger("aag 1 4294967295 4294967295 0 4294967295\n"),
+            Err(AigerError::InvalidHeader)
+        );
+    }
+
+    #[test]
+    fn test_parse_aiger_large_header() {
+        // nothing is allocated for the variables the file does not use
+        let aig = parse_aiger("aag 2000000000 0 0 0 0\n").unwrap();
+        assert!(aig.cnf.clauses.is_empty());
+        assert_eq!(
+            parse_aiger("aag 2000000000 2000000000 0 0 0\n2\n"),
+            Err(AigerError::UnexpectedEnd)
+        );
+    }
+}
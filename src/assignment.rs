@@ -1,24 +1,42 @@
-use std::collections::HashMap;
 use std::fmt::Debug;
 
 use crate::cnf::{LiteralTpl, Var};
+
+/// The values of the assigned variables, indexed by the variable
+///
+/// The vector only grows up to the highest assigned variable, so its last entry is always
+/// assigned and two assignments are equal iff they assign the same values. It takes memory
+/// proportional to the highest variable, which is cheap for the usual dense numbering.
 #[derive(Clone, PartialEq, Default)]
-pub struct Assignment(HashMap<Var, bool>);
+pub struct Assignment(Vec<Option<bool>>);
 
 impl Assignment {
     pub fn new() -> Assignment {
-        Assignment(HashMap::new())
+        Assignment(Vec::new())
     }
 
     pub fn new_with(var: Var, val: bool) -> Assignment {
-        let mut it = Assignment(HashMap::new());
+        let mut it = Assignment::new();
         it.change(var, val);
         it
     }
 
+    /// Creates the assignment of a DIMACS model line, e.g. `[1, -2, 0]` assigns 1 to true
+    /// and 2 to false
+    ///
+    /// Zeros are ignored, so the terminating zero may be included. If a variable occurs
+    /// several times, its last literal wins.
+    pub fn from_dimacs(lits: &[i32]) -> Assignment {
+        Assignment::new().with_all(
+            lits.iter()
+                .filter(|&&lit| lit != 0)
+                .map(|&lit| (lit.unsigned_abs(), lit > 0)),
+        )
+    }
+
     /// Gets the value (true or false) that is assigned to this variable or None if it is unassigned
     pub fn get(&self, var: Var) -> Option<bool> {
-        self.0.get(&var).map(|it| *it)
+        self.0.get(var as usize).copied().flatten()
     }
 
     /// Gets whether this literal is valid, invalid or unassigned
@@ -27,19 +45,20 @@ impl Assignment {
     /// if it is invalid (its variable set to false), this function returns Some(false).
     /// If the literal's variable is unassigned, this function returns None
     pub fn get_lit(&self, lit: LiteralTpl) -> Option<bool> {
-        match self.0.get(&lit.0) {
-            Some(&val) => Some(val == lit.1),
-            None => None,
-        }
+        self.get(lit.0).map(|val| val == lit.1)
     }
 
     /// Checks wether this assignment satisfies the given literal
     pub fn satisfies(&self, lit: LiteralTpl) -> bool {
-        self.0.get(&lit.0).map(|&val| val == lit.1).unwrap_or(false)
+        self.get(lit.0) == Some(lit.1)
     }
 
     pub fn change(&mut self, var: Var, val: bool) {
-        self.0.insert(var, val);
+        let idx = var as usize;
+        if idx >= self.0.len() {
+            self.0.resize(idx + 1, None);
+        }
+        self.0[idx] = Some(val);
     }
 
     pub fn with(&self, var: Var, val: bool) -> Assignment {
@@ -50,38 +69,302 @@ impl Assignment {
 
     pub fn with_all(&self, map: impl Iterator<Item = LiteralTpl>) -> Assignment {
         let mut this = self.clone();
-        this.0.extend(map);
+        for (var, val) in map {
+            this.change(var, val);
+        }
         this
     }
 
     pub fn highest_assigned_var(&self) -> Option<Var> {
-        self.0.keys().copied().max()
+        self.0.len().checked_sub(1).map(|var| var as Var)
+    }
+
+    /// Returns the assigned variables and their values in ascending order of the variables
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.0.iter().enumerate())
+    }
+
+    /// Checks that no variable is assigned different values in this and the other assignment
+    ///
+    /// Variables assigned in only one of them are ignored, so a partial model agrees with
+    /// every assignment extending it.
+    pub fn agrees_with(&self, other: &Assignment) -> bool {
+        self.iter().all(|(var, val)| other.get(var) != Some(!val))
+    }
+
+    /// Returns the assignment of the given variables only
+    pub fn restrict_to(&self, vars: &[Var]) -> Assignment {
+        Assignment::new().with_all(
+            vars.iter()
+                .filter_map(|&var| self.get(var).map(|val| (var, val))),
+        )
+    }
+
+    /// Packs the values of the variables `1..=num_vars` into a [ModelBits]
+    ///
+    /// Assigned variables above `num_vars` are not part of the result.
+    pub fn to_bitset(&self, num_vars: Var) -> ModelBits {
+        let mut bits = ModelBits::new(num_vars);
+        for (var, val) in self.iter().filter(|&(var, _)| var >= 1 && var <= num_vars) {
+            let (word, mask) = ModelBits::position(var);
+            bits.assigned[word] |= mask;
+            if val {
+                bits.values[word] |= mask;
+            }
+        }
+        bits
+    }
+}
+
+/// A packed form of an [Assignment] over the variables `1..=num_vars`
+///
+/// Variable `v` is stored at bit `(v - 1) % 64` of the word `(v - 1) / 64` in two bitsets:
+/// * `assigned` has the bit set iff the variable is assigned
+/// * `values` has the bit set iff the variable is assigned true
+///
+/// The bits of unassigned variables are always cleared in `values`, so two `ModelBits` are
+/// equal iff they assign the same values to the same variables. Bits beyond `num_vars` in
+/// the last word are cleared as well.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ModelBits {
+    num_vars: Var,
+    values: Vec<u64>,
+    assigned: Vec<u64>,
+}
+
+impl ModelBits {
+    fn new(num_vars: Var) -> Self {
+        let words = (num_vars as usize).div_ceil(64);
+        ModelBits {
+            num_vars,
+            values: vec![0; words],
+            assigned: vec![0; words],
+        }
+    }
+
+    /// Returns the word index and bit mask of the given variable
+    fn position(var: Var) -> (usize, u64) {
+        let idx = var as usize - 1;
+        (idx / 64, 1 << (idx % 64))
+    }
+
+    pub fn num_vars(&self) -> Var {
+        self.num_vars
+    }
+
+    /// The bitset of variables assigned true
+    pub fn values(&self) -> &[u64] {
+        &self.values
+    }
+
+    /// The bitset of assigned variables
+    pub fn assigned(&self) -> &[u64] {
+        &self.assigned
+    }
+
+    /// Gets the value assigned to this variable or None if it is unassigned or out of range
+    pub fn get(&self, var: Var) -> Option<bool> {
+        if var == 0 || var > self.num_vars {
+            return None;
+        }
+        let (word, mask) = Self::position(var);
+        if self.assigned[word] & mask == 0 {
+            None
+        } else {
+            Some(self.values[word] & mask != 0)
+        }
+    }
+
+    /// Checks whether every variable assigned in `other` is assigned the same value in `self`
+    ///
+    /// Both must cover the same number of variables.
+    pub fn contains(&self, other: &ModelBits) -> bool {
+        assert_eq!(
+            self.num_vars, other.num_vars,
+            "Cannot compare models of different size"
+        );
+        self.assigned
+            .iter()
+            .zip(&self.values)
+            .zip(other.assigned.iter().zip(&other.values))
+            .all(|((&assigned, &values), (&other_assigned, &other_values))| {
+                other_assigned & !assigned == 0 && (values ^ other_values) & other_assigned == 0
+            })
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (Var, bool)> + '_ {
-        self.0.iter().map(|(k, v)| (*k, *v))
+    pub fn to_assignment(&self) -> Assignment {
+        let mut assignment = Assignment::new();
+        for var in 1..=self.num_vars {
+            if let Some(val) = self.get(var) {
+                assignment.change(var, val);
+            }
+        }
+        assignment
+    }
+}
+
+impl<'a> IntoIterator for &'a Assignment {
+    type Item = LiteralTpl;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl IntoIterator for Assignment {
+    type Item = LiteralTpl;
+    type IntoIter = IntoIter;
+
+    /// Yields the assigned variables and their values like [Assignment::iter]
+    fn into_iter(self) -> IntoIter {
+        IntoIter(self.0.into_iter().enumerate())
+    }
+}
+
+/// Iterator over the assigned variables of an [Assignment] and their values, see
+/// [Assignment::iter]
+pub struct Iter<'a>(std::iter::Enumerate<std::slice::Iter<'a, Option<bool>>>);
+
+impl Iterator for Iter<'_> {
+    type Item = LiteralTpl;
+
+    fn next(&mut self) -> Option<LiteralTpl> {
+        self.0
+            .find_map(|(var, val)| val.map(|val| (var as Var, val)))
+    }
+}
+
+/// Owning iterator over the assigned variables of an [Assignment] and their values
+pub struct IntoIter(std::iter::Enumerate<std::vec::IntoIter<Option<bool>>>);
+
+impl Iterator for IntoIter {
+    type Item = LiteralTpl;
+
+    fn next(&mut self) -> Option<LiteralTpl> {
+        self.0
+            .find_map(|(var, val)| val.map(|val| (var as Var, val)))
     }
 }
 
 impl Debug for Assignment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let assignment_str = {
-            let mut values = self
-                .0
-                .iter()
-                .map(|(&var, &val)| (var, val))
-                .collect::<Vec<(u32, bool)>>();
-
-            values.sort_by_key(|&(var, _val)| var);
-
-            values
-                .iter()
-                .map(|&(var, val)| format!("{:02} => {}", var, if val { 1 } else { 0 }))
-                .collect::<Vec<String>>()
-                .join("; ")
-        };
+        let assignment_str = self
+            .iter()
+            .map(|(var, val)| format!("{:02} => {}", var, if val { 1 } else { 0 }))
+            .collect::<Vec<String>>()
+            .join("; ");
 
         write!(f, "α{{{}}}", assignment_str)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_bitset() {
+        let a = Assignment::new()
+            .with(1, true)
+            .with(2, false)
+            .with(64, true)
+            .with(65, true)
+            .with(70, false)
+            .with(100, true);
+        let bits = a.to_bitset(70);
+
+        assert_eq!(bits.values(), &[1 << 63 | 1, 1]);
+        assert_eq!(bits.assigned(), &[1 << 63 | 0b11, 1 << 5 | 1]);
+        assert_eq!(bits.get(2), Some(false));
+        assert_eq!(bits.get(3), None);
+        assert_eq!(bits.get(65), Some(true));
+        assert_eq!(bits.get(100), None);
+
+        // variable 100 is out of range
+        assert_eq!(
+            bits.to_assignment(),
+            Assignment::new()
+                .with(1, true)
+                .with(2, false)
+                .with(64, true)
+                .with(65, true)
+                .with(70, false)
+        );
+    }
+
+    #[test]
+    fn test_from_dimacs() {
+        let a = Assignment::from_dimacs(&[1, -2, 5, 0]);
+        assert_eq!(
+            a,
+            Assignment::new().with(1, true).with(2, false).with(5, true)
+        );
+        assert_eq!(Assignment::from_dimacs(&[3, -3]).get(3), Some(false));
+        assert_eq!(Assignment::from_dimacs(&[0]), Assignment::new());
+    }
+
+    #[test]
+    fn test_dense_storage() {
+        let a = Assignment::new().with(5, true).with(2, false);
+        assert_eq!(a.get(5), Some(true));
+        assert_eq!(a.get(3), None);
+        assert_eq!(a.get(100), None);
+        assert_eq!(a.get_lit((2, false)), Some(true));
+        assert!(!a.satisfies((4, true)));
+        assert_eq!(a.highest_assigned_var(), Some(5));
+        assert_eq!(Assignment::new().highest_assigned_var(), None);
+        assert_eq!(a.iter().collect::<Vec<_>>(), vec![(2, false), (5, true)]);
+
+        // the order of the assignments does not matter
+        assert_eq!(a, Assignment::new().with(2, false).with(5, true));
+        assert_eq!(a.with(5, false).with(5, true), a);
+    }
+
+    #[test]
+    fn test_iter() {
+        let a = Assignment::new()
+            .with(3, true)
+            .with(1, false)
+            .with(70, true);
+        let expected = vec![(1, false), (3, true), (70, true)];
+        assert_eq!(a.iter().collect::<Vec<_>>(), expected);
+        assert_eq!((&a).into_iter().collect::<Vec<_>>(), expected);
+        assert_eq!(a.clone().into_iter().collect::<Vec<_>>(), expected);
+
+        let mut copied = Assignment::new();
+        for (var, val) in &a {
+            copied.change(var, val);
+        }
+        assert_eq!(copied, a);
+        assert_eq!(Assignment::new().into_iter().next(), None);
+    }
+
+    #[test]
+    fn test_agrees_with() {
+        let model = Assignment::new().with(1, true).with(2, false);
+        let extended = model.with(3, true);
+        assert!(model.agrees_with(&extended));
+        assert!(extended.agrees_with(&model));
+        assert!(model.agrees_with(&Assignment::new()));
+        assert!(!model.agrees_with(&extended.with(2, true)));
+
+        assert_eq!(extended.restrict_to(&[1, 2, 4]), model);
+        assert_ne!(extended, model);
+    }
+
+    #[test]
+    fn test_bitset_contains() {
+        let full = Assignment::new().with(1, true).with(2, false).with(3, true);
+        let partial = Assignment::new().with(1, true).with(3, true);
+        let other = Assignment::new().with(2, true);
+
+        assert!(full.to_bitset(3).contains(&partial.to_bitset(3)));
+        assert!(!partial.to_bitset(3).contains(&full.to_bitset(3)));
+        assert!(!full.to_bitset(3).contains(&other.to_bitset(3)));
+        assert!(full.to_bitset(3).contains(&full.to_bitset(3)));
+        assert_eq!(full.to_bitset(3), full.clone().to_bitset(3));
+        assert_ne!(full.to_bitset(3), partial.to_bitset(3));
+    }
+}
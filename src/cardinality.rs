@@ -0,0 +1,306 @@
+use crate::cnf::{negate, Clause, LiteralTpl, Var};
+
+/// Encodes that at least one of the given variables is true
+///
+/// This is the clause of all variables, it is empty and thus unsatisfiable if `vars` is.
+pub fn at_least_one(vars: &[Var]) -> Clause {
+    let literals = vars.iter().map(|&var| (var, true)).collect::<Vec<_>>();
+    Clause::from_literals(&literals)
+}
+
+/// Encodes that at most one of the given variables is true
+///
+/// Uses one binary clause per pair of variables and no auxiliary variables.
+pub fn at_most_one(vars: &[Var]) -> Vec<Clause> {
+    let mut clauses = Vec::new();
+    for (idx, &first) in vars.iter().enumerate() {
+        for &second in &vars[idx + 1..] {
+            clauses.push(Clause::from_literals(&[(first, false), (second, false)]));
+        }
+    }
+    clauses
+}
+
+/// Encodes that exactly one of the given variables is true
+pub fn exactly_one(vars: &[Var]) -> Vec<Clause> {
+    let mut clauses = at_most_one(vars);
+    clauses.push(at_least_one(vars));
+    clauses
+}
+
+/// Encodes a sequential counter over the given variables
+///
+/// Returns the clauses of the counter and its outputs. The output at index `j` is forced
+/// to true whenever at least `j + 1` of the variables are true, so assuming the negation
+/// of `outputs[k]` restricts the variables to at most `k` true ones.
+/// As the bound is given by an assumption, it can be changed between incremental solves
+/// without touching the clauses.
+///
+/// Auxiliary variables are allocated starting at `next_fresh`, which is increased accordingly.
+pub fn counter(vars: &[Var], next_fresh: &mut Var) -> (Vec<Clause>, Vec<Var>) {
+    let mut clauses = Vec::new();
+
+    // partial_sums[j] holds the variable "at least j + 1 of the variables so far are true"
+    let mut partial_sums: Vec<Var> = Vec::new();
+
+    for &var in vars {
+        let mut new_sums = Vec::with_capacity(partial_sums.len() + 1);
+
+        for j in 0..=partial_sums.len() {
+            let sum = fresh_var(next_fresh);
+
+            if j == 0 {
+                // var -> sum_0
+                clauses.push(Clause::from_literals(&[(var, false), (sum, true)]));
+            } else {
+                // var & prev_sum_(j-1) -> sum_j
+                clauses.push(Clause::from_literals(&[
+                    (var, false),
+                    (partial_sums[j - 1], false),
+                    (sum, true),
+                ]));
+            }
+
+            if j < partial_sums.len() {
+                // prev_sum_j -> sum_j
+                clauses.push(Clause::from_literals(&[
+                    (partial_sums[j], false),
+                    (sum, true),
+                ]));
+            }
+
+            new_sums.push(sum);
+        }
+
+        partial_sums = new_sums;
+    }
+
+    (clauses, partial_sums)
+}
+
+/// Encodes that at most `k` of the given variables are true
+///
+/// Uses a sequential counter like [counter], but it only counts up to `k` and defines its
+/// partial sums in both directions, so every assignment of `vars` extends to at most one
+/// model of the clauses. The clauses are empty if `k` is at least the number of variables.
+///
+/// Auxiliary variables are allocated starting at `next_fresh`, which is increased accordingly.
+pub fn at_most_k(vars: &[Var], k: usize, next_fresh: &mut Var) -> Vec<Clause> {
+    let literals = vars.iter().map(|&var| (var, true)).collect::<Vec<_>>();
+    at_most_k_literals(&literals, k, next_fresh)
+}
+
+/// Encodes that at least `k` of the given variables are true, like [at_most_k] restricting
+/// the false ones to at most `vars.len() - k`
+///
+/// If `k` exceeds the number of variables, the result is the empty clause.
+pub fn at_least_k(vars: &[Var], k: usize, next_fresh: &mut Var) -> Vec<Clause> {
+    if k > vars.len() {
+        return vec![Clause::new()];
+    }
+    let literals = vars.iter().map(|&var| (var, false)).collect::<Vec<_>>();
+    at_most_k_literals(&literals, vars.len() - k, next_fresh)
+}
+
+/// Encodes that exactly `k` of the given variables are true, combining [at_most_k] and
+/// [at_least_k]
+pub fn exactly_k(vars: &[Var], k: usize, next_fresh: &mut Var) -> Vec<Clause> {
+    let mut clauses = at_most_k(vars, k, next_fresh);
+    clauses.append(&mut at_least_k(vars, k, next_fresh));
+    clauses
+}
+
+fn at_most_k_literals(literals: &[LiteralTpl], k: usize, next_fresh: &mut Var) -> Vec<Clause> {
+    if k >= literals.len() {
+        return Vec::new();
+    }
+    if k == 0 {
+        return literals
+            .iter()
+            .map(|&lit| Clause::from_literals(&[negate(lit)]))
+            .collect();
+    }
+
+    let mut clauses = Vec::new();
+
+    // partial_sums[j] holds the variable "at least j + 1 of the literals so far are true"
+    let mut partial_sums: Vec<Var> = Vec::new();
+
+    for (idx, &lit) in literals.iter().enumerate() {
+        if partial_sums.len() == k {
+            // lit & prev_sum_(k-1) would make k + 1 true literals
+            clauses.push(Clause::from_literals(&[
+                negate(lit),
+                (partial_sums[k - 1], false),
+            ]));
+        }
+        if idx == literals.len() - 1 {
+            break;
+        }
+
+        let mut new_sums = Vec::with_capacity(k);
+        for j in 0..(partial_sums.len() + 1).min(k) {
+            let sum = fresh_var(next_fresh);
+            // sum_j <-> prev_sum_j | (lit & prev_sum_(j-1)), where a missing prev_sum_j is
+            // false and a missing prev_sum_(j-1) is true
+            let prev = partial_sums.get(j).map(|&prev| (prev, true));
+            let lower = j.checked_sub(1).map(|lower| (partial_sums[lower], true));
+
+            if let Some(prev) = prev {
+                clauses.push(Clause::from_literals(&[negate(prev), (sum, true)]));
+            }
+            let mut implied = vec![negate(lit), (sum, true)];
+            implied.extend(lower.map(negate));
+            clauses.push(Clause::from_literals(&implied));
+
+            let mut implying = vec![(sum, false), lit];
+            implying.extend(prev);
+            clauses.push(Clause::from_literals(&implying));
+            if let Some(lower) = lower {
+                let mut implying = vec![(sum, false), lower];
+                implying.extend(prev);
+                clauses.push(Clause::from_literals(&implying));
+            }
+
+            new_sums.push(sum);
+        }
+
+        partial_sums = new_sums;
+    }
+
+    clauses
+}
+
+fn fresh_var(next_fresh: &mut Var) -> Var {
+    let var = *next_fresh;
+    *next_fresh += 1;
+    var
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::Cnf;
+    use crate::satsolve::{count_models, Solver};
+
+    /// Checks for every assignment of `vars` whether `clauses` allow it
+    fn check_all_assignments(vars: &[Var], clauses: Vec<Clause>, allowed: impl Fn(usize) -> bool) {
+        let mut solver = Solver::new(Cnf::new_with(clauses));
+
+        for bits in 0..(1u32 << vars.len()) {
+            let fixed = vars
+                .iter()
+                .enumerate()
+                .map(|(idx, &var)| (var, bits & (1 << idx) != 0))
+                .collect::<Vec<_>>();
+            let num_true = bits.count_ones() as usize;
+
+            assert_eq!(
+                solver.solve_under_assumptions(&fixed).is_sat(),
+                allowed(num_true),
+                "{:?}",
+                fixed
+            );
+        }
+    }
+
+    #[test]
+    fn test_at_least_one() {
+        for len in 0..=4 {
+            let vars = (1..=len).collect::<Vec<Var>>();
+            check_all_assignments(&vars, vec![at_least_one(&vars)], |num_true| num_true >= 1);
+        }
+    }
+
+    #[test]
+    fn test_at_most_one() {
+        for len in 0..=4 {
+            let vars = (1..=len).collect::<Vec<Var>>();
+            check_all_assignments(&vars, at_most_one(&vars), |num_true| num_true <= 1);
+        }
+    }
+
+    #[test]
+    fn test_exactly_one() {
+        for len in 0..=4 {
+            let vars = (1..=len).collect::<Vec<Var>>();
+            check_all_assignments(&vars, exactly_one(&vars), |num_true| num_true == 1);
+        }
+    }
+
+    #[test]
+    fn test_counter_bounds() {
+        let vars = [1, 2, 3, 4];
+        let mut next_fresh = 5;
+        let (clauses, outputs) = counter(&vars, &mut next_fresh);
+        assert_eq!(outputs.len(), vars.len());
+
+        let mut solver = Solver::new(Cnf::new_with(clauses));
+
+        for bits in 0..(1u32 << vars.len()) {
+            let fixed = vars
+                .iter()
+                .enumerate()
+                .map(|(idx, &var)| (var, bits & (1 << idx) != 0))
+                .collect::<Vec<_>>();
+            let num_true = bits.count_ones() as usize;
+
+            for (k, &output) in outputs.iter().enumerate() {
+                let mut assumptions = fixed.clone();
+                assumptions.push((output, false));
+                assert_eq!(
+                    solver.solve_under_assumptions(&assumptions).is_sat(),
+                    num_true <= k,
+                    "{:?} with at most {}",
+                    fixed,
+                    k
+                );
+            }
+        }
+    }
+
+    fn binomial(n: usize, k: usize) -> u128 {
+        (0..k).fold(1, |result, i| result * (n - i) as u128 / (i + 1) as u128)
+    }
+
+    /// Checks for every bound up to the number of variables that the models of the encoding
+    /// are exactly the assignments of `vars` with an allowed number of true variables
+    fn check_model_counts(
+        encode: impl Fn(&[Var], usize, &mut Var) -> Vec<Clause>,
+        allowed: impl Fn(usize, usize) -> bool,
+    ) {
+        for len in 1..=5 {
+            let vars = (1..=len).collect::<Vec<Var>>();
+            for k in 0..=len as usize + 1 {
+                let mut next_fresh = len + 1;
+                let cnf = Cnf::new_with(encode(&vars, k, &mut next_fresh));
+                let expected = (0..=len as usize)
+                    .filter(|&num_true| allowed(num_true, k))
+                    .map(|num_true| binomial(len as usize, num_true))
+                    .sum::<u128>();
+
+                if cnf.clauses.is_empty() {
+                    assert_eq!(expected, 1 << len, "{} of {}", k, len);
+                } else {
+                    assert!(cnf.highest_var() < next_fresh);
+                    assert_eq!(count_models(&cnf), expected, "{} of {}", k, len);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_at_most_k() {
+        check_model_counts(at_most_k, |num_true, k| num_true <= k);
+    }
+
+    #[test]
+    fn test_at_least_k() {
+        check_model_counts(at_least_k, |num_true, k| num_true >= k);
+    }
+
+    #[test]
+    fn test_exactly_k() {
+        check_model_counts(exactly_k, |num_true, k| num_true == k);
+    }
+}
@@ -1,50 +1,837 @@
-use std::fmt::Debug;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{Debug, Display};
 
 use crate::assignment::Assignment;
+use crate::input::ParseError;
+use crate::satsolve::{SolveResult, Solver};
 
 pub type Var = u32;
 
 /// A literal is a positive or negated form of a variable
 pub type LiteralTpl = (Var, bool);
 
+/// Returns the negated form of the given literal
+pub fn negate(lit: LiteralTpl) -> LiteralTpl {
+    (lit.0, !lit.1)
+}
+
+/// A property of a formula the solver relies on, which is violated
+#[derive(Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The clause contains the variable 0, variables start at 1
+    ZeroVariable { clause_idx: usize },
+    /// The clause contains the variable both positive and negative
+    Tautology { clause_idx: usize, var: Var },
+    /// The variable does not occur although a higher one does
+    Gap { var: Var },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::ZeroVariable { clause_idx } => {
+                write!(f, "clause {} contains variable 0", clause_idx)
+            }
+            ValidationError::Tautology { clause_idx, var } => write!(
+                f,
+                "clause {} contains variable {} positive and negative",
+                clause_idx, var
+            ),
+            ValidationError::Gap { var } => write!(f, "variable {} does not occur", var),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Whether a formula has a model, as decided by [Cnf::quick_decide]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Satisfiability {
+    Satisfiable,
+    Unsatisfiable,
+}
+
 /// A collection of [clauses] in logical conjunction
 /// 
 /// [clauses]: Clause
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
 pub struct Cnf {
     pub clauses: Vec<Clause>,
+    /// Parity constraints in conjunction with the clauses
+    ///
+    /// The solvers propagate them by Gaussian elimination instead of encoding them into
+    /// clauses. The transformations of the formula, e.g. [Cnf::to_3cnf] or
+    /// [Cnf::components], as well as its DIMACS representation only cover the clauses.
+    /// Serializing a formula with parity constraints fails.
+    pub xors: Vec<XorClause>,
 }
 
 impl Cnf {
     pub fn new() -> Self {
         Cnf {
             clauses: Vec::new(),
+            xors: Vec::new(),
         }
     }
 
     pub fn new_with(clauses: Vec<Clause>) -> Self {
-        Cnf { clauses }
+        Cnf {
+            clauses,
+            xors: Vec::new(),
+        }
+    }
+
+    /// Creates a formula from clauses given as DIMACS literals, e.g. `[1, -2, 3]` for
+    /// `x1 ∨ ¬x2 ∨ x3`
+    ///
+    /// Like in [parse_dimacs](crate::input::parse_dimacs), a clause may be terminated by `0`,
+    /// duplicate literals are dropped and tautological clauses are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [ParseError::ZeroLiteral] if a `0` is not the last literal of its clause,
+    /// the line of the error is the number of the clause starting at 1.
+    pub fn from_dimacs_iter(
+        clauses: impl IntoIterator<Item = impl AsRef<[i32]>>,
+    ) -> Result<Cnf, ParseError> {
+        let mut cnf = Cnf::new();
+        for (clause_idx, clause) in clauses.into_iter().enumerate() {
+            let literals = match clause.as_ref() {
+                [literals @ .., 0] => literals,
+                literals => literals,
+            };
+
+            let mut tuples = Vec::with_capacity(literals.len());
+            for &literal in literals {
+                if literal == 0 {
+                    return Err(ParseError::ZeroLiteral {
+                        line: clause_idx + 1,
+                    });
+                }
+                tuples.push((literal.unsigned_abs(), literal > 0));
+            }
+
+            if !tuples.iter().any(|&lit| tuples.contains(&negate(lit))) {
+                cnf.clauses.push(Clause::from_literals(&tuples));
+            }
+        }
+        Ok(cnf)
+    }
+
+    /// Writes this formula in the DIMACS CNF format
+    ///
+    /// The header declares [highest_var](Cnf::highest_var) variables, every clause is
+    /// written on its own line and an empty clause as a lone `0`. The result is parsed back
+    /// into an equal formula by [parse_dimacs](crate::input::parse_dimacs), unless the
+    /// formula contains tautological clauses, which the parser drops.
+    pub fn to_dimacs(&self) -> String {
+        let mut dimacs = format!("p cnf {} {}\n", self.highest_var(), self.clauses.len());
+        for clause in &self.clauses {
+            for (var, positive) in clause.literals() {
+                let sign = if positive { "" } else { "-" };
+                dimacs.push_str(&format!("{}{} ", sign, var));
+            }
+            dimacs.push_str("0\n");
+        }
+        dimacs
     }
 
     pub fn highest_var(&self) -> Var {
-        fn highest_var_in_clause(slc: &[Var]) -> Var {
-            slc.iter().fold(0, |cur, var| cur.max(*var))
+        // the literals of a clause are sorted, so its last one has the highest variable
+        let highest = self
+            .clauses
+            .iter()
+            .filter_map(|clause| clause.literals.last())
+            .fold(0, |cur, &(var, _)| cur.max(var));
+        self.xors
+            .iter()
+            .flat_map(|xor| xor.vars.iter())
+            .fold(highest, |cur, &var| cur.max(var))
+    }
+
+    /// Returns the distinct variables occurring in this formula in ascending order
+    pub fn vars(&self) -> impl Iterator<Item = Var> {
+        self.clauses
+            .iter()
+            .flat_map(|clause| clause.literals().map(|(var, _)| var))
+            .chain(self.xors.iter().flat_map(|xor| xor.vars.iter().copied()))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+    }
+
+    /// Returns the number of distinct variables occurring in this formula
+    ///
+    /// In contrast to [highest_var](Cnf::highest_var) this does not count the gaps
+    /// of a sparse numbering.
+    pub fn num_vars(&self) -> usize {
+        self.vars().count()
+    }
+
+    /// Counts in how many clauses each variable occurs positively and negatively
+    ///
+    /// Maps every occurring variable to `(positive occurrences, negative occurrences)`.
+    pub fn polarity_counts(&self) -> HashMap<Var, (usize, usize)> {
+        let mut counts: HashMap<Var, (usize, usize)> = HashMap::new();
+        for clause in &self.clauses {
+            for var in clause.positives() {
+                counts.entry(var).or_default().0 += 1;
+            }
+            for var in clause.negatives() {
+                counts.entry(var).or_default().1 += 1;
+            }
+        }
+        counts
+    }
+
+    /// Removes the tautological clauses, which every assignment satisfies
+    ///
+    /// Returns the number of removed clauses.
+    pub fn remove_tautologies(&mut self) -> usize {
+        let len = self.clauses.len();
+        self.clauses.retain(|clause| !clause.is_tautology());
+        len - self.clauses.len()
+    }
+
+    /// Removes the clauses subsumed by another clause, including all but the first of
+    /// duplicate clauses
+    ///
+    /// Returns the number of removed clauses, the order of the remaining ones is kept.
+    ///
+    /// The clauses are checked from the shortest to the longest against the kept ones. Each
+    /// kept clause is indexed under its first literal only, which every clause it subsumes
+    /// contains as well, so a clause is only compared to the kept clauses indexed under one
+    /// of its literals. This is still quadratic in the worst case, e.g. if most clauses share
+    /// a literal, but far from it for typical formulas.
+    pub fn remove_subsumed(&mut self) -> usize {
+        let mut order = (0..self.clauses.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&idx| self.clauses[idx].len());
+
+        let mut keep = vec![false; self.clauses.len()];
+        let mut kept_by_literal: HashMap<LiteralTpl, Vec<usize>> = HashMap::new();
+        for idx in order {
+            let clause = &self.clauses[idx];
+            let subsumed = clause
+                .literals()
+                .filter_map(|lit| kept_by_literal.get(&lit))
+                .flatten()
+                .any(|&kept| self.clauses[kept].subsumes(clause));
+            if !subsumed {
+                keep[idx] = true;
+                if let Some(first) = clause.literals().next() {
+                    kept_by_literal.entry(first).or_default().push(idx);
+                }
+            }
         }
 
-        self.clauses.iter().fold(0, |cur, clause| {
-            cur.max(highest_var_in_clause(&clause.positive))
-                .max(highest_var_in_clause(&clause.negative))
-        })
+        // the empty clause is indexed under no literal, but subsumes every other clause
+        if let Some(empty) = self.clauses.iter().position(Clause::is_empty) {
+            keep.iter_mut().for_each(|kept| *kept = false);
+            keep[empty] = true;
+        }
+
+        let len = self.clauses.len();
+        let mut keep = keep.into_iter();
+        self.clauses.retain(|_| keep.next().unwrap());
+        len - self.clauses.len()
+    }
+
+    /// Removes the clauses containing a pure literal, a literal whose variable occurs with
+    /// this polarity only, and returns the assignment making these literals true
+    ///
+    /// Removing clauses can make further literals pure, so this repeats until no pure literal
+    /// is left. The remaining formula does not contain the assigned variables, it is
+    /// satisfiable iff the original one is, and each of its models extended by the returned
+    /// assignment is a model of the original formula. The variables of the parity
+    /// constraints are never pure.
+    pub fn eliminate_pure_literals(&mut self) -> Assignment {
+        let xor_vars = self
+            .xors
+            .iter()
+            .flat_map(|xor| xor.vars.iter().copied())
+            .collect::<HashSet<_>>();
+        let mut pure = Assignment::new();
+        loop {
+            let found = self
+                .polarity_counts()
+                .into_iter()
+                .filter(|(var, _)| !xor_vars.contains(var))
+                .filter_map(|(var, counts)| match counts {
+                    (_, 0) => Some((var, true)),
+                    (0, _) => Some((var, false)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            if found.is_empty() {
+                return pure;
+            }
+
+            for (var, val) in found {
+                pure.change(var, val);
+            }
+            self.clauses.retain(|clause| !clause.is_satisfied(&pure));
+        }
     }
 
     pub fn is_satisfied(&self, assignment: &Assignment) -> bool {
         self.clauses.iter().all(|cls| cls.is_satisfied(assignment))
+            && self.xors.iter().all(|xor| xor.is_satisfied(assignment))
+    }
+
+    /// Returns the index of the first clause the given assignment does not satisfy
+    ///
+    /// `None` iff all clauses are satisfied, the parity constraints are not checked. A
+    /// clause with an unassigned variable and otherwise only false literals counts as not
+    /// satisfied.
+    pub fn first_violated_clause(&self, assignment: &Assignment) -> Option<usize> {
+        self.clauses
+            .iter()
+            .position(|cls| !cls.is_satisfied(assignment))
+    }
+
+    /// Checks a model, e.g. one returned by a solver, against every clause
+    ///
+    /// Returns the index of the first clause it does not satisfy as the error, like
+    /// [first_violated_clause](Cnf::first_violated_clause).
+    pub fn verify(&self, assignment: &Assignment) -> Result<(), usize> {
+        match self.first_violated_clause(assignment) {
+            Some(clause_idx) => Err(clause_idx),
+            None => Ok(()),
+        }
+    }
+
+    /// Turns a model into a minimal one, setting as many of its true variables to false as
+    /// possible
+    ///
+    /// The result is a model whose true variables are a subset of those of the given one,
+    /// such that no model has a strict subset of them. True variables are flipped greedily
+    /// one at a time first. Since flipping several at once may still lead to a smaller
+    /// model, the solver then searches for one, until there is none.
+    ///
+    /// The variables of the formula unassigned in the given model are false in the result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given assignment does not satisfy this formula
+    pub fn minimize_model(&self, model: &Assignment) -> Assignment {
+        assert!(self.is_satisfied(model), "Assignment is not a model");
+        let vars = self.vars().collect::<Vec<_>>();
+        let mut model = model.with_all(
+            vars.iter()
+                .filter(|&&var| model.get(var).is_none())
+                .map(|&var| (var, false)),
+        );
+
+        loop {
+            for &var in &vars {
+                if model.get(var) == Some(true) {
+                    let flipped = model.with(var, false);
+                    if self.is_satisfied(&flipped) {
+                        model = flipped;
+                    }
+                }
+            }
+
+            // a smaller model keeps the false variables and flips at least one true variable
+            let (true_vars, false_vars): (Vec<Var>, Vec<Var>) =
+                vars.iter().partition(|&&var| model.get(var) == Some(true));
+            let mut smaller = self.clone();
+            for &var in &false_vars {
+                smaller.clauses.push(Clause::from_literals(&[(var, false)]));
+            }
+            let flip_one = true_vars
+                .iter()
+                .map(|&var| (var, false))
+                .collect::<Vec<_>>();
+            smaller.clauses.push(Clause::from_literals(&flip_one));
+
+            match Solver::new(smaller).solve() {
+                SolveResult::Sat(smaller_model) => {
+                    for var in true_vars {
+                        model.change(var, smaller_model.get(var) == Some(true));
+                    }
+                }
+                SolveResult::Unsat => return model,
+                SolveResult::Unknown => {
+                    unreachable!("a solver without a limit always decides the formula")
+                }
+            }
+        }
+    }
+
+    /// Checks that no clause contains the variable 0 or a variable with both signs
+    ///
+    /// The parsers rule out both, a formula built otherwise can be cleaned from tautologies
+    /// with [remove_tautologies](Cnf::remove_tautologies).
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for (clause_idx, clause) in self.clauses.iter().enumerate() {
+            if clause.get(0).is_some() {
+                return Err(ValidationError::ZeroVariable { clause_idx });
+            }
+            if let Some(var) = clause
+                .positives()
+                .find(|&var| clause.contains_literal((var, false)))
+            {
+                return Err(ValidationError::Tautology { clause_idx, var });
+            }
+        }
+        Ok(())
+    }
+
+    /// Like [validate](Cnf::validate), but additionally checks that every variable
+    /// from 1 up to the highest one occurs
+    pub fn validate_dense(&self) -> Result<(), ValidationError> {
+        self.validate()?;
+
+        match (1..).zip(self.vars()).find(|&(expected, var)| expected != var) {
+            Some((missing, _)) => Err(ValidationError::Gap { var: missing }),
+            None => Ok(()),
+        }
+    }
+
+    /// Decides the formula if one of the cheap rules applies, without searching
+    ///
+    /// The formula is
+    /// * unsatisfiable if it contains an empty clause or two unit clauses with
+    ///   opposite literals
+    /// * satisfiable if it is monotone, i.e. no variable occurs both positive and negative,
+    ///   and has no parity constraints. This includes the empty formula and formulas
+    ///   consisting of unit clauses only.
+    ///
+    /// Returns None if none of them applies and a real search is needed.
+    pub fn quick_decide(&self) -> Option<Satisfiability> {
+        if self.clauses.iter().any(|clause| clause.is_empty()) {
+            return Some(Satisfiability::Unsatisfiable);
+        }
+
+        let mut units = HashMap::new();
+        for clause in &self.clauses {
+            let mut literals = clause.literals();
+            if let (Some((var, val)), None) = (literals.next(), literals.next()) {
+                if *units.entry(var).or_insert(val) != val {
+                    return Some(Satisfiability::Unsatisfiable);
+                }
+            }
+        }
+
+        let monotone = self
+            .polarity_counts()
+            .values()
+            .all(|&(positive, negative)| positive == 0 || negative == 0);
+        if monotone && self.xors.is_empty() {
+            return Some(Satisfiability::Satisfiable);
+        }
+
+        None
+    }
+
+    /// Returns whether every clause has exactly three literals
+    pub fn is_3cnf(&self) -> bool {
+        self.clauses.iter().all(|clause| clause.len() == 3)
+    }
+
+    /// Returns whether every clause has at most one positive literal
+    ///
+    /// Satisfiability of Horn formulas is decided by unit propagation alone.
+    pub fn is_horn(&self) -> bool {
+        self.clauses
+            .iter()
+            .all(|clause| clause.positives().nth(1).is_none())
+    }
+
+    /// Returns whether every clause has at most two literals
+    ///
+    /// Satisfiability of such formulas (2-SAT) is decidable in linear time.
+    pub fn is_2cnf(&self) -> bool {
+        self.clauses.iter().all(|clause| clause.len() <= 2)
+    }
+
+    /// Estimates how hard it is to decide this formula, without solving it
+    ///
+    /// The score is 0 for the easy classes: formulas decided by
+    /// [quick_decide](Cnf::quick_decide), Horn formulas and 2-SAT. Otherwise it is the
+    /// number of variables, weighted by how close the ratio of clauses with at least three
+    /// literals to variables is to the satisfiability threshold of random formulas with
+    /// their average length (about 4.26 for 3-SAT). Formulas far below the threshold are
+    /// almost always satisfiable and far above almost always unsatisfiable, and in both
+    /// cases easy to decide.
+    ///
+    /// This is a heuristic for comparing formulas, e.g. to order solves or pick timeouts,
+    /// not a prediction of the running time. Structured formulas may be much harder or
+    /// easier than their score suggests.
+    pub fn hardness_estimate(&self) -> f64 {
+        if self.quick_decide().is_some() || self.is_horn() || self.is_2cnf() {
+            return 0.0;
+        }
+
+        let num_vars = self.num_vars() as f64;
+        let long_clauses = self.clauses.iter().filter(|clause| clause.len() >= 3);
+        let (count, total_len) = long_clauses.fold((0, 0), |(count, total_len), clause| {
+            (count + 1, total_len + clause.len())
+        });
+        let avg_len = total_len as f64 / count as f64;
+
+        // thresholds of random k-SAT for small k, 2^k ln 2 asymptotically
+        let threshold = match avg_len.round() as u32 {
+            3 => 4.267,
+            4 => 9.931,
+            5 => 21.117,
+            6 => 43.37,
+            7 => 87.79,
+            k => 2f64.powi(k as i32) * std::f64::consts::LN_2,
+        };
+
+        // a bell curve over the logarithm of the ratio, halving at about 0.55 and 1.8 times
+        // the threshold
+        let deviation = (count as f64 / num_vars / threshold).ln();
+        num_vars * (-deviation * deviation / 0.5).exp()
+    }
+
+    /// Converts this formula into an equisatisfiable one with exactly three literals
+    /// per clause
+    ///
+    /// Longer clauses are split into a chain of clauses linked by auxiliary variables,
+    /// `(l1 l2 y1) (-y1 l3 y2) ... (-yk ln-1 ln)`. Shorter clauses are padded with every
+    /// combination of signs of new dummy variables, e.g. `(l1 l2 z) (l1 l2 -z)`, which
+    /// also turns an empty clause into eight unsatisfiable ones.
+    ///
+    /// The new variables are numbered after the highest variable of this formula, up to the
+    /// returned variable. Restricting a model of the result to the variables of this formula
+    /// gives a model of this formula.
+    pub fn to_3cnf(&self) -> (Cnf, Var) {
+        let mut next_fresh = self.highest_var() + 1;
+        let mut fresh = || {
+            next_fresh += 1;
+            next_fresh - 1
+        };
+        let mut result = Cnf::new();
+
+        for clause in &self.clauses {
+            let literals = clause.literals().collect::<Vec<_>>();
+
+            if literals.len() > 3 {
+                let (last, inner) = literals.split_last().unwrap();
+                let (second_last, inner) = inner.split_last().unwrap();
+                let mut link = (fresh(), true);
+                result
+                    .clauses
+                    .push(Clause::from_literals(&[inner[0], inner[1], link]));
+                for &lit in &inner[2..] {
+                    let next_link = (fresh(), true);
+                    result
+                        .clauses
+                        .push(Clause::from_literals(&[negate(link), lit, next_link]));
+                    link = next_link;
+                }
+                result
+                    .clauses
+                    .push(Clause::from_literals(&[negate(link), *second_last, *last]));
+                continue;
+            }
+
+            let dummies = (literals.len()..3).map(|_| fresh()).collect::<Vec<_>>();
+            for signs in 0..1u32 << dummies.len() {
+                let padding = dummies
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, &var)| (var, signs & (1 << idx) != 0));
+                let padded = literals.iter().copied().chain(padding).collect::<Vec<_>>();
+                result.clauses.push(Clause::from_literals(&padded));
+            }
+        }
+
+        (result, next_fresh - 1)
+    }
+
+    /// Returns clauses which are satisfiable iff the negation of this formula is
+    ///
+    /// Every clause gets a fresh variable implying its negation, see [Clause::negate], and
+    /// one clause demands that at least one of them is true. A model of the result
+    /// restricted to the variables of this formula violates it. The negation of the empty
+    /// formula is the empty clause.
+    ///
+    /// Two formulas `f` and `g` are equivalent iff neither `f ∧ ¬g` nor `g ∧ ¬f` is
+    /// satisfiable. The fresh variables have to be distinct from those of both formulas, so
+    /// they are allocated starting at `next_fresh`, which is increased accordingly.
+    pub fn negation(&self, next_fresh: &mut Var) -> Vec<Clause> {
+        let mut result = Vec::new();
+        let mut selectors = Clause::new();
+        for clause in &self.clauses {
+            let selector = *next_fresh;
+            *next_fresh += 1;
+            selectors.add_positive(selector);
+            for mut negated in clause.negate() {
+                negated.add_negative(selector);
+                result.push(negated);
+            }
+        }
+        result.push(selectors);
+        result
+    }
+
+    /// Returns the primal graph of this formula as adjacency sets
+    ///
+    /// Every occurring variable is a node, two variables are adjacent iff they occur together
+    /// in a clause. Variables only occurring alone have no neighbours.
+    pub fn primal_graph(&self) -> HashMap<Var, HashSet<Var>> {
+        let mut graph: HashMap<Var, HashSet<Var>> = HashMap::new();
+        for clause in &self.clauses {
+            for (var, _) in clause.literals() {
+                let neighbours = graph.entry(var).or_default();
+                neighbours.extend(clause.literals().map(|(other, _)| other));
+                neighbours.remove(&var);
+            }
+        }
+        graph
+    }
+
+    /// Finds permutations of the variables which map this formula onto itself
+    ///
+    /// The detection is purely syntactic and only looks at two kinds of candidates: swaps of
+    /// two variables, and row swaps pairing the variables of two clauses in ascending order
+    /// (like two pigeons of a pigeonhole formula). Only candidates which map every clause
+    /// onto a clause of this formula are returned, so the result is far from all symmetries.
+    pub fn detect_symmetries(&self) -> Vec<Permutation> {
+        let clauses = self.clauses.iter().collect::<HashSet<_>>();
+        let counts = self.polarity_counts();
+        let mut candidates = Vec::new();
+
+        let vars = self.vars().collect::<Vec<_>>();
+        for (idx, &var) in vars.iter().enumerate() {
+            for &other in &vars[idx + 1..] {
+                if counts[&var] == counts[&other] {
+                    candidates.push(vec![(var, other)]);
+                }
+            }
+        }
+
+        for (idx, clause) in self.clauses.iter().enumerate() {
+            for other in &self.clauses[idx + 1..] {
+                // pairing the variables by sign needs a single sign per variable
+                if clause.is_tautology()
+                    || other.is_tautology()
+                    || clause.positives().count() != other.positives().count()
+                    || clause.negatives().count() != other.negatives().count()
+                    || clause.literals().any(|(var, _)| other.get(var).is_some())
+                {
+                    continue;
+                }
+
+                // the variables of both signs are sorted already
+                let swaps = clause
+                    .positives()
+                    .zip(other.positives())
+                    .chain(clause.negatives().zip(other.negatives()))
+                    .filter(|&(var, other)| counts[&var] == counts[&other])
+                    .collect::<Vec<_>>();
+                if swaps.len() == clause.len() && swaps.len() > 1 {
+                    candidates.push(swaps);
+                }
+            }
+        }
+
+        let mut symmetries: Vec<Permutation> = Vec::new();
+        for swaps in candidates {
+            let permutation = Permutation::from_swaps(&swaps);
+            if !symmetries.contains(&permutation)
+                && self
+                    .clauses
+                    .iter()
+                    .all(|clause| clauses.contains(&permutation.apply(clause)))
+            {
+                symmetries.push(permutation);
+            }
+        }
+        symmetries
+    }
+
+    /// Returns lex-leader clauses breaking the given symmetries of this formula
+    ///
+    /// For every permutation `p` the clauses only allow the assignments `x` which are
+    /// lexicographically (by ascending variables, false before true) at most as large as
+    /// their image under `p`. Each class of assignments mapped onto each other keeps its
+    /// smallest member, so adding the clauses to this formula keeps it satisfiable iff it
+    /// was, while cutting off the equivalent assignments.
+    ///
+    /// The clauses use auxiliary variables numbered after the highest variable of this
+    /// formula. The permutations must be symmetries of this formula, otherwise models may
+    /// be lost.
+    pub fn symmetry_breaking_clauses(&self, permutations: &[Permutation]) -> Vec<Clause> {
+        let mut next_fresh = self.highest_var() + 1;
+        let mut clauses = Vec::new();
+
+        for permutation in permutations {
+            // the positions compared, skipping those whose values are already known to be
+            // equal because they are swapped with an earlier position
+            let mut compared = Vec::new();
+            for (&var, &image) in &permutation.mapping {
+                if image > var || permutation.get(image) != var {
+                    compared.push((var, image));
+                }
+            }
+
+            // `equal` is true if all earlier positions were equal, absent for the first one
+            let mut equal: Option<Var> = None;
+            for (idx, &(var, image)) in compared.iter().enumerate() {
+                let with_prefix = |literals: &[LiteralTpl]| {
+                    let prefix = equal.map(|equal| (equal, false));
+                    let literals = prefix.iter().chain(literals).copied();
+                    Clause::from_literals(&literals.collect::<Vec<_>>())
+                };
+                clauses.push(with_prefix(&[(var, false), (image, true)]));
+
+                if idx + 1 < compared.len() {
+                    let next = next_fresh;
+                    next_fresh += 1;
+                    clauses.push(with_prefix(&[(var, false), (image, false), (next, true)]));
+                    clauses.push(with_prefix(&[(var, true), (image, true), (next, true)]));
+                    equal = Some(next);
+                }
+            }
+        }
+
+        clauses
+    }
+
+    /// Returns the guiding paths for the given variables: one set of assumptions for each
+    /// of the `2^k` combinations of their values
+    ///
+    /// Every assignment extends exactly one of the paths, so this formula is satisfiable
+    /// iff it is satisfiable under any of them, e.g. with
+    /// [Solver::solve_under_assumptions]. The subproblems are independent and can be
+    /// solved in parallel. The paths are ordered like binary numbers, with false before
+    /// true and the first variable being the most significant.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a variable is given twice
+    pub fn guiding_paths(&self, vars: &[Var]) -> Vec<Vec<LiteralTpl>> {
+        let distinct = vars.iter().collect::<HashSet<_>>();
+        assert_eq!(distinct.len(), vars.len(), "Variables are not distinct");
+
+        let mut paths = vec![Vec::with_capacity(vars.len())];
+        for &var in vars {
+            paths = paths
+                .into_iter()
+                .flat_map(|path| {
+                    [false, true].iter().map(move |&val| {
+                        let mut path = path.clone();
+                        path.push((var, val));
+                        path
+                    })
+                })
+                .collect();
+        }
+        paths
+    }
+
+    /// Splits this formula into formulas which do not share any variable
+    ///
+    /// Two clauses end up in the same component iff they are connected by a chain of clauses
+    /// each sharing a variable with the next one. This formula is satisfiable iff every
+    /// component is, and the union of models of the components is a model of this formula.
+    ///
+    /// The components keep the variable numbering and are ordered by their first clause.
+    /// Every empty clause forms a component of its own.
+    pub fn components(&self) -> Vec<Cnf> {
+        let (component_of_clause, num_components) = self.component_of_clauses();
+        let mut components = vec![Cnf::new(); num_components];
+        for (clause, component) in self.clauses.iter().zip(component_of_clause) {
+            components[component].clauses.push(clause.clone());
+        }
+        components
+    }
+
+    /// Like [components](Cnf::components), but moves the clauses into the components
+    ///
+    /// A formula forming a single component is returned as it is, without its parity
+    /// constraints.
+    pub fn into_components(mut self) -> Vec<Cnf> {
+        let (component_of_clause, num_components) = self.component_of_clauses();
+        if num_components == 1 {
+            self.xors.clear();
+            return vec![self];
+        }
+        let mut components = vec![Cnf::new(); num_components];
+        for (clause, component) in self.clauses.into_iter().zip(component_of_clause) {
+            components[component].clauses.push(clause);
+        }
+        components
+    }
+
+    /// The index of the component of every clause and the number of components, see
+    /// [components](Cnf::components)
+    fn component_of_clauses(&self) -> (Vec<usize>, usize) {
+        // union-find over the clause indices
+        fn find(parents: &mut [usize], mut idx: usize) -> usize {
+            while parents[idx] != idx {
+                parents[idx] = parents[parents[idx]];
+                idx = parents[idx];
+            }
+            idx
+        }
+
+        let mut parents = (0..self.clauses.len()).collect::<Vec<_>>();
+        let mut first_clause_of_var: HashMap<Var, usize> = HashMap::new();
+
+        for (clause_idx, clause) in self.clauses.iter().enumerate() {
+            for (var, _) in clause.literals() {
+                let other_idx = *first_clause_of_var.entry(var).or_insert(clause_idx);
+                let root = find(&mut parents, clause_idx);
+                let other_root = find(&mut parents, other_idx);
+                // the smaller root is kept, so every root is the first clause of its component
+                parents[root.max(other_root)] = root.min(other_root);
+            }
+        }
+
+        let mut component_of_root = HashMap::new();
+        let component_of_clause = (0..self.clauses.len())
+            .map(|clause_idx| {
+                let root = find(&mut parents, clause_idx);
+                let num_components = component_of_root.len();
+                *component_of_root.entry(root).or_insert(num_components)
+            })
+            .collect();
+        (component_of_clause, component_of_root.len())
+    }
+
+    /// Brings this formula into its canonical form
+    ///
+    /// The clauses are sorted by their literals, which every clause keeps sorted, and
+    /// duplicate clauses are removed. Two formulas which only differ in the order of
+    /// their clauses or literals (or in duplicate clauses) are identical afterwards.
+    pub fn canonicalize(&mut self) {
+        self.clauses.sort_by(Clause::cmp_sorted);
+        self.clauses.dedup();
+    }
+
+    /// Renumbers the variables to `1..=num_vars`, keeping their order, and returns the
+    /// mapping from the old to the new variables
+    ///
+    /// Afterwards [highest_var](Cnf::highest_var) equals [num_vars](Cnf::num_vars). A model
+    /// of the compacted formula is translated back by looking up the old variable of each
+    /// new one in the inverted mapping.
+    pub fn compact(&mut self) -> HashMap<Var, Var> {
+        let mapping = self.vars().zip(1..).collect::<HashMap<Var, Var>>();
+        for clause in &mut self.clauses {
+            let literals = clause
+                .literals()
+                .map(|(var, val)| (mapping[&var], val))
+                .collect::<Vec<_>>();
+            *clause = Clause::from_literals(&literals);
+        }
+        for xor in &mut self.xors {
+            for var in &mut xor.vars {
+                *var = mapping[var];
+            }
+        }
+        mapping
     }
 }
 
 impl Debug for Cnf {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.clauses.is_empty() {
+        if self.clauses.is_empty() && self.xors.is_empty() {
             write!(f, "true")?;
             return Ok(());
         }
@@ -53,6 +840,7 @@ impl Debug for Cnf {
             .clauses
             .iter()
             .map(|it| format!("{:?}", it))
+            .chain(self.xors.iter().map(|it| format!("{:?}", it)))
             .collect::<Vec<String>>()
             .join(" ∧ ");
 
@@ -61,79 +849,167 @@ impl Debug for Cnf {
     }
 }
 
+/// Builds a [Cnf] from clauses given as DIMACS literals
+///
+/// ```
+/// # use satsolver::cnf::CnfBuilder;
+/// let cnf = CnfBuilder::new().clause([1, -2, 3]).clause([-1]).build().unwrap();
+/// assert_eq!(cnf.clauses.len(), 2);
+/// ```
+#[derive(Debug, Default)]
+pub struct CnfBuilder {
+    clauses: Vec<Clause>,
+    error: Option<ParseError>,
+}
+
+impl CnfBuilder {
+    pub fn new() -> Self {
+        CnfBuilder::default()
+    }
+
+    /// Adds a clause of DIMACS literals, e.g. `[1, -2, 3]` for `x1 ∨ ¬x2 ∨ x3`
+    ///
+    /// Duplicate literals are dropped, an empty iterator adds the empty clause.
+    pub fn clause(mut self, literals: impl IntoIterator<Item = i64>) -> Self {
+        use std::convert::TryFrom;
+
+        if self.error.is_some() {
+            return self;
+        }
+
+        let line = self.clauses.len() + 1;
+        let mut clause = Clause::new();
+        for literal in literals {
+            match Var::try_from(literal.unsigned_abs()) {
+                Ok(0) => {
+                    self.error = Some(ParseError::ZeroLiteral { line });
+                    return self;
+                }
+                Ok(var) if literal < 0 => clause.add_negative(var),
+                Ok(var) => clause.add_positive(var),
+                Err(_) => {
+                    self.error = Some(ParseError::Overflow { line });
+                    return self;
+                }
+            }
+        }
+        self.clauses.push(clause);
+        self
+    }
+
+    /// Returns the formula of all clauses added so far
+    ///
+    /// # Errors
+    ///
+    /// Returns [ParseError::ZeroLiteral] for the literal `0` and [ParseError::Overflow] for
+    /// a variable not fitting into a [Var], the line of the error is the number of the
+    /// first invalid clause starting at 1.
+    pub fn build(self) -> Result<Cnf, ParseError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(Cnf::new_with(self.clauses)),
+        }
+    }
+}
+
 /// A collection of literals (positive or negative [variables]) in logical disjunction
 /// 
 /// [variables]: Var
+///
+/// The literals are kept sorted by their variable, a negative literal before the positive
+/// one of the same variable, so clauses with the same literals are equal regardless of the
+/// order the literals were added in.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
 pub struct Clause {
-    positive: Vec<Var>,
-    negative: Vec<Var>,
+    literals: Vec<LiteralTpl>,
 }
 
 impl Clause {
     pub fn new() -> Clause {
         Clause {
-            positive: Vec::new(),
-            negative: Vec::new(),
+            literals: Vec::new(),
         }
     }
 
+    /// Creates a clause from the given literals
+    ///
+    /// Giving a literal and its negation results in a [tautology](Clause::is_tautology).
+    pub fn from_literals(literals: &[LiteralTpl]) -> Clause {
+        Self::from_literals_counting_duplicates(literals).0
+    }
+
+    /// Like [from_literals](Clause::from_literals), but also returns how many literals
+    /// were dropped because they were given more than once
+    pub fn from_literals_counting_duplicates(literals: &[LiteralTpl]) -> (Clause, usize) {
+        let mut clause = Clause::new();
+        let mut duplicates = 0;
+        for &lit in literals {
+            if !clause.insert(lit) {
+                duplicates += 1;
+            }
+        }
+        (clause, duplicates)
+    }
+
     pub fn positives(&self) -> impl Iterator<Item = Var> + '_ {
-        self.positive.iter().copied()
+        self.literals().filter(|&(_, val)| val).map(|(var, _)| var)
     }
 
     pub fn negatives(&self) -> impl Iterator<Item = Var> + '_ {
-        self.negative.iter().copied()
+        self.literals().filter(|&(_, val)| !val).map(|(var, _)| var)
     }
 
-    /// Returns an iterator over all literals in this clause
+    /// Returns an iterator over all literals in this clause, sorted by their variable
     pub fn literals(&self) -> impl Iterator<Item = LiteralTpl> + '_ {
-        self.positive
-            .iter()
-            .map(|&it| (it, true))
-            .chain(self.negative.iter().map(|&it| (it, false)))
+        self.literals.iter().copied()
     }
 
     /// Adds a positive literal to this clause if it is not already present
     /// 
-    /// # Panics
-    /// 
-    /// Panics if the negated literal is already part of this clause
+    /// If the negated literal is already part of this clause, it becomes a
+    /// [tautology](Clause::is_tautology).
     pub fn add_positive(&mut self, var: Var) {
-        if !self.positive.contains(&var) {
-            if !self.negative.contains(&var) {
-                self.positive.push(var);
-            } else {
-                panic!("Added var both positive and negative!");
-            }
-        }
+        self.insert((var, true));
     }
 
     /// Adds a negative literal to this clause if it is not already present
     /// 
-    /// # Panics
-    /// 
-    /// Panics if the negated literal is already part of this clause
+    /// If the negated literal is already part of this clause, it becomes a
+    /// [tautology](Clause::is_tautology).
     pub fn add_negative(&mut self, var: Var) {
-        if !self.negative.contains(&var) {
-            if !self.positive.contains(&var) {
-                self.negative.push(var);
-            } else {
-                panic!("Added var both positive and negative!");
+        self.insert((var, false));
+    }
+
+    /// Adds the literal at its sorted position, returns false if it is already present
+    fn insert(&mut self, lit: LiteralTpl) -> bool {
+        match self.literals.binary_search(&lit) {
+            Ok(_) => false,
+            Err(position) => {
+                self.literals.insert(position, lit);
+                true
             }
         }
     }
 
+    /// Checks whether this clause contains a literal and its negation, which makes it
+    /// satisfied by every assignment
+    pub fn is_tautology(&self) -> bool {
+        // the two literals of a variable are next to each other
+        self.literals.windows(2).any(|pair| pair[0].0 == pair[1].0)
+    }
+
     /// Returns wether the given variable is part of this clause in positive or negative form
     /// 
     /// If the positive literal of the given variable is part of this clause,
     /// returns `Some(true)`, if the negative literal is part of this clause
-    /// `Some(false)`.
+    /// `Some(false)`. A tautology containing both literals returns `Some(true)`, so this
+    /// cannot tell the polarity of a variable in a clause which may be a tautology.
     /// If neither the positive nor negative literal are part of this clause 
     /// returns `None`.
     pub fn get(&self, var: Var) -> Option<bool> {
-        if self.positive.contains(&var) {
+        if self.contains_literal((var, true)) {
             Some(true)
-        } else if self.negative.contains(&var) {
+        } else if self.contains_literal((var, false)) {
             Some(false)
         } else {
             None
@@ -142,54 +1018,96 @@ impl Clause {
 
     /// Checks wether the given assignment satisfies this clause
     pub fn is_satisfied(&self, assignment: &Assignment) -> bool {
-        self.positive
-            .iter()
-            .any(|&var| matches!(assignment.get(var), Some(true)))
-            || self
-                .negative
-                .iter()
-                .any(|&var| matches!(assignment.get(var), Some(false)))
+        self.literals()
+            .any(|lit| matches!(assignment.get_lit(lit), Some(true)))
     }
 
     pub fn is_empty(&self) -> bool {
-        self.positive.is_empty() && self.negative.is_empty()
+        self.literals.is_empty()
     }
-}
 
-impl PartialEq for Clause {
-    fn eq(&self, other: &Self) -> bool {
-        fn check(literals: &Vec<u32>, other_literals: &Vec<u32>) -> bool {
-            literals
-                .iter()
-                .all(|literal| other_literals.contains(literal))
+    /// Returns the number of literals of this clause
+    pub fn len(&self) -> usize {
+        self.literals.len()
+    }
+
+    /// Returns the negation of this clause as unit clauses, one per negated literal
+    ///
+    /// Adding them to a formula asks whether it allows violating the clause, so a formula
+    /// implies the clause iff the result is unsatisfiable. The negation of the empty clause
+    /// is empty, i.e. true.
+    pub fn negate(&self) -> Vec<Clause> {
+        self.literals()
+            .map(|lit| Clause::from_literals(&[negate(lit)]))
+            .collect()
+    }
+
+    /// Checks whether all literals of this clause are part of `other`
+    ///
+    /// A subsuming clause implies the subsumed one, so the latter can be dropped from a
+    /// formula containing both.
+    pub fn subsumes(&self, other: &Clause) -> bool {
+        self.len() <= other.len() && self.literals().all(|lit| other.contains_literal(lit))
+    }
+
+    fn contains_literal(&self, lit: LiteralTpl) -> bool {
+        self.literals.binary_search(&lit).is_ok()
+    }
+
+    /// Returns the resolvent of this clause and `other` on `var`, or `None` if it is a
+    /// tautology
+    ///
+    /// The resolvent contains all literals of both clauses except the two opposite literals
+    /// of `var` resolved on. If one of the clauses is a tautology in `var`, its other
+    /// literal of `var` is kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `var` does not occur with opposite polarities in the two clauses
+    pub fn resolve(&self, other: &Clause, var: Var) -> Option<Clause> {
+        let val = if self.contains_literal((var, true)) && other.contains_literal((var, false)) {
+            true
+        } else if self.contains_literal((var, false)) && other.contains_literal((var, true)) {
+            false
+        } else {
+            panic!("Cannot resolve on var {}", var);
+        };
+
+        let mut resolvent = Clause::new();
+        let literals = self
+            .literals()
+            .filter(|&lit| lit != (var, val))
+            .chain(other.literals().filter(|&lit| lit != (var, !val)));
+        for (lit_var, val) in literals {
+            match resolvent.get(lit_var) {
+                Some(existing) if existing != val => return None,
+                Some(_) => {}
+                None if val => resolvent.add_positive(lit_var),
+                None => resolvent.add_negative(lit_var),
+            }
         }
+        Some(resolvent)
+    }
 
-        check(&self.positive, &other.positive)
-            && check(&other.positive, &self.positive)
-            && check(&self.negative, &other.negative)
-            && check(&other.negative, &self.negative)
+    /// Compares two clauses by their sorted literals
+    fn cmp_sorted(&self, other: &Clause) -> Ordering {
+        self.literals.cmp(&other.literals)
     }
 }
 
 impl Debug for Clause {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.positive.is_empty() && self.negative.is_empty() {
+        if self.is_empty() {
             write!(f, "false")?;
             return Ok(());
         }
 
         write!(f, "[")?;
-        // TODO very inefficient
-        let mut all_vars = self.positive.clone();
-        all_vars.append(&mut self.negative.clone());
-        all_vars.sort();
-
-        let str = all_vars
-            .iter()
-            .map(|&var| match self.get(var) {
-                Some(true) => format!("{:+03}", var),
-                Some(false) => format!("-{:02}", var),
-                None => unreachable!(),
+        let str = self
+            .literals()
+            .map(|(var, val)| match val {
+                true => format!("{:+03}", var),
+                false => format!("-{:02}", var),
             })
             .collect::<Vec<String>>()
             .join(" ∨ ");
@@ -200,8 +1118,861 @@ impl Debug for Clause {
     }
 }
 
+/// Serializes the formula as a list of [clauses](Clause), e.g. `[[1, -2], [3]]` in JSON
+///
+/// Fails if the formula has [parity constraints](Cnf::xors), which the list cannot
+/// represent.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Cnf {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+
+        if !self.xors.is_empty() {
+            return Err(S::Error::custom("parity constraints cannot be serialized"));
+        }
+        self.clauses.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Cnf {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Cnf::new_with)
+    }
+}
+
+/// Serializes the clause as a list of DIMACS literals without the terminating `0`, sorted
+/// by their variable
+#[cfg(feature = "serde")]
+impl serde::Serialize for Clause {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for (var, val) in self.literals() {
+            let literal = if val { var as i64 } else { -(var as i64) };
+            seq.serialize_element(&literal)?;
+        }
+        seq.end()
+    }
+}
+
+/// Deserializes a list of DIMACS literals like [Clause::from_literals], dropping duplicates
+///
+/// Fails on the literal `0` and on variables not fitting into a [Var].
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Clause {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        use std::convert::TryFrom;
+
+        let literals = Vec::<i64>::deserialize(deserializer)?
+            .into_iter()
+            .map(|literal| match Var::try_from(literal.unsigned_abs()) {
+                Ok(0) => Err(D::Error::custom("clause contains the literal 0")),
+                Ok(var) => Ok((var, literal > 0)),
+                Err(_) => Err(D::Error::custom(format!(
+                    "variable {} is out of range",
+                    literal.unsigned_abs()
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Clause::from_literals(&literals))
+    }
+}
+
+/// A parity constraint: an odd number of the variables is true if `rhs` is, an even number
+/// otherwise
+///
+/// A variable listed twice cancels out.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct XorClause {
+    pub vars: Vec<Var>,
+    pub rhs: bool,
+}
+
+impl XorClause {
+    /// Creates the constraint with the variables sorted and those listed twice removed
+    pub fn new(mut vars: Vec<Var>, rhs: bool) -> Self {
+        vars.sort_unstable();
+        let mut distinct: Vec<Var> = Vec::with_capacity(vars.len());
+        for var in vars {
+            if distinct.last() == Some(&var) {
+                distinct.pop();
+            } else {
+                distinct.push(var);
+            }
+        }
+        XorClause {
+            vars: distinct,
+            rhs,
+        }
+    }
+
+    /// Returns whether all variables are assigned and their parity matches `rhs`
+    pub fn is_satisfied(&self, assignment: &Assignment) -> bool {
+        let mut parity = false;
+        for &var in &self.vars {
+            match assignment.get(var) {
+                Some(val) => parity ^= val,
+                None => return false,
+            }
+        }
+        parity == self.rhs
+    }
+}
+
+impl Debug for XorClause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let vars = self
+            .vars
+            .iter()
+            .map(|var| format!("{:02}", var))
+            .collect::<Vec<String>>()
+            .join(" ⊕ ");
+        write!(f, "[{} = {}]", vars, self.rhs as u8)
+    }
+}
+
+/// A bijection on variables, leaving every variable not mentioned unchanged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permutation {
+    mapping: BTreeMap<Var, Var>,
+}
+
+impl Permutation {
+    /// Creates the permutation exchanging each given pair of variables
+    ///
+    /// # Panics
+    ///
+    /// Panics if a variable is part of more than one pair
+    pub fn from_swaps(swaps: &[(Var, Var)]) -> Permutation {
+        let mut mapping = BTreeMap::new();
+        for &(var, other) in swaps {
+            if var == other {
+                continue;
+            }
+            if mapping.insert(var, other).is_some() || mapping.insert(other, var).is_some() {
+                panic!("Variable swapped more than once!");
+            }
+        }
+        Permutation { mapping }
+    }
+
+    /// Returns the variable the given variable is mapped to
+    pub fn get(&self, var: Var) -> Var {
+        self.mapping.get(&var).copied().unwrap_or(var)
+    }
+
+    /// Returns the variables which are not mapped onto themselves in ascending order
+    pub fn moved_vars(&self) -> impl Iterator<Item = Var> + '_ {
+        self.mapping.keys().copied()
+    }
+
+    /// Returns the clause with every variable replaced by its image
+    pub fn apply(&self, clause: &Clause) -> Clause {
+        let literals = clause
+            .literals()
+            .map(|(var, val)| (self.get(var), val))
+            .collect::<Vec<_>>();
+        Clause::from_literals(&literals)
+    }
+}
+
 pub enum LiteralInfo {
     POSITIVE,
     NEGATIVE,
     NoOcc,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    use super::*;
+    use crate::input::parse_cnf_from_str;
+    use crate::rng::{random_cnf, Rng};
+    use crate::satsolve::{is_satisfiable, Solver};
+
+    fn hash_of(cnf: &Cnf) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        cnf.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let mut cnf0 = parse_cnf_from_str("3 -1 2\n-4 5\n2 1\n1 2 -3").unwrap();
+        let mut cnf1 = parse_cnf_from_str("5 -4\n1 2\n-3 2 1\n2 -1 3\n-4 5").unwrap();
+        assert_ne!(cnf0, cnf1);
+
+        cnf0.canonicalize();
+        cnf1.canonicalize();
+        assert_eq!(cnf0, cnf1);
+        assert_eq!(hash_of(&cnf0), hash_of(&cnf1));
+        assert_eq!(cnf1.clauses.len(), 4);
+
+        // The clause order is fixed, not only equal up to permutation
+        let literals = |clause: &Clause| clause.literals().collect::<Vec<_>>();
+        assert_eq!(
+            literals(&cnf0.clauses[0]),
+            vec![(1, false), (2, true), (3, true)]
+        );
+        assert_eq!(literals(&cnf0.clauses[1]), vec![(1, true), (2, true)]);
+        assert_eq!(
+            literals(&cnf0.clauses[2]),
+            vec![(1, true), (2, true), (3, false)]
+        );
+    }
+
+    #[test]
+    fn test_from_dimacs_iter() {
+        let cnf = Cnf::from_dimacs_iter([[1, -2, 3], [-1, 2, 0]]).unwrap();
+        assert_eq!(cnf, parse_cnf_from_str("1 -2 3\n-1 2").unwrap());
+
+        // duplicates are dropped, tautologies skipped
+        let clauses = vec![vec![1, 1, -2], vec![3, -3], vec![], vec![0]];
+        let cnf = Cnf::from_dimacs_iter(&clauses).unwrap();
+        assert_eq!(cnf, parse_cnf_from_str("1 -2\nfalse\nfalse").unwrap());
+
+        assert_eq!(
+            Cnf::from_dimacs_iter(vec![vec![1], vec![2, 0, 3]]),
+            Err(ParseError::ZeroLiteral { line: 2 })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        use serde::de::value::Error;
+        use serde::de::IntoDeserializer;
+        use serde::Deserialize;
+
+        let deserialize = |clauses: Vec<Vec<i64>>| {
+            Cnf::deserialize(IntoDeserializer::<Error>::into_deserializer(clauses))
+        };
+        assert_eq!(
+            deserialize(vec![vec![1, -2, 1], vec![], vec![-3, 4]]).unwrap(),
+            parse_cnf_from_str("1 -2\nfalse\n-3 4").unwrap()
+        );
+        assert_eq!(deserialize(vec![]).unwrap(), Cnf::new());
+        assert!(deserialize(vec![vec![1], vec![2, 0]]).is_err());
+        assert!(deserialize(vec![vec![1 << 40]]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let inputs = ["", "false", "1 -2\nfalse\n-3 4", "-1\n2 3 -4\n1 -2\n-1"];
+        for input in inputs.iter() {
+            let cnf = parse_cnf_from_str(input).unwrap();
+            let json = serde_json::to_string(&cnf).unwrap();
+            assert_eq!(serde_json::from_str::<Cnf>(&json).unwrap(), cnf);
+        }
+
+        // the duplicated literal is dropped, the empty clause kept
+        let cnf = serde_json::from_str::<Cnf>("[[1, -2, 1], [], [3]]").unwrap();
+        let json = serde_json::to_string(&cnf).unwrap();
+        assert_eq!(json, "[[1,-2],[],[3]]");
+        assert_eq!(serde_json::from_str::<Cnf>(&json).unwrap(), cnf);
+
+        let mut cnf = Cnf::new();
+        cnf.xors.push(XorClause::new(vec![1, 2], true));
+        assert!(serde_json::to_string(&cnf).is_err());
+    }
+
+    #[test]
+    fn test_cnf_builder() {
+        let cnf = CnfBuilder::new()
+            .clause([1, -2, 3, 1])
+            .clause(vec![])
+            .clause((4..6).map(|var| -var))
+            .build();
+        assert_eq!(cnf, Ok(parse_cnf_from_str("1 -2 3\nfalse\n-4 -5").unwrap()));
+        assert_eq!(CnfBuilder::new().build(), Ok(Cnf::new()));
+
+        assert_eq!(
+            CnfBuilder::new()
+                .clause([1])
+                .clause([2, 0])
+                .clause([0])
+                .build(),
+            Err(ParseError::ZeroLiteral { line: 2 })
+        );
+        assert_eq!(
+            CnfBuilder::new().clause([-(1 << 40)]).build(),
+            Err(ParseError::Overflow { line: 1 })
+        );
+    }
+
+    #[test]
+    fn test_to_dimacs() {
+        let cnf = parse_cnf_from_str("1 -2 3\nfalse\n-4").unwrap();
+        assert_eq!(cnf.to_dimacs(), "p cnf 4 3\n1 -2 3 0\n0\n-4 0\n");
+        assert_eq!(Cnf::new().to_dimacs(), "p cnf 0 0\n");
+
+        let mut rng = Rng::new(753);
+        for _ in 0..50 {
+            let num_clauses = rng.below(10) as usize;
+            let cnf = random_cnf(&mut rng, 8, num_clauses, 0..=4);
+            assert_eq!(crate::input::parse_dimacs(&cnf.to_dimacs()), Ok(cnf));
+        }
+    }
+
+    #[test]
+    fn test_vars() {
+        let cnf = parse_cnf_from_str("7 -3\n-7 12\nfalse\n3 1").unwrap();
+        assert_eq!(cnf.vars().collect::<Vec<_>>(), vec![1, 3, 7, 12]);
+        assert_eq!(cnf.num_vars(), 4);
+        assert_eq!(cnf.highest_var(), 12);
+
+        assert_eq!(Cnf::new().num_vars(), 0);
+    }
+
+    #[test]
+    fn test_first_violated_clause() {
+        let cnf = parse_cnf_from_str("1 2\n-1 3\n-2 -3").unwrap();
+        let assignment = Assignment::new().with(1, true).with(2, false).with(3, true);
+        assert_eq!(cnf.first_violated_clause(&assignment), None);
+        assert_eq!(
+            cnf.first_violated_clause(&assignment.with(3, false)),
+            Some(1)
+        );
+        assert_eq!(cnf.first_violated_clause(&Assignment::new()), Some(0));
+    }
+
+    #[test]
+    fn test_verify() {
+        let cnf = parse_cnf_from_str("1 2\n-1 3\n-2 -3").unwrap();
+        let assignment = Assignment::new().with(1, true).with(2, false).with(3, true);
+        assert_eq!(cnf.verify(&assignment), Ok(()));
+        assert_eq!(cnf.verify(&assignment.with(1, false)), Err(0));
+        assert_eq!(cnf.verify(&assignment.with(2, true)), Err(2));
+        assert_eq!(Cnf::new().verify(&Assignment::new()), Ok(()));
+    }
+
+    #[test]
+    fn test_minimize_model() {
+        let all_true = |cnf: &Cnf| {
+            cnf.vars()
+                .fold(Assignment::new(), |a, var| a.with(var, true))
+        };
+
+        let cnf = parse_cnf_from_str("1 2\n-1 3").unwrap();
+        let minimal = cnf.minimize_model(&all_true(&cnf));
+        assert_eq!(
+            minimal,
+            Assignment::new()
+                .with(1, false)
+                .with(2, true)
+                .with(3, false)
+        );
+
+        // no single variable can be flipped, but both together
+        let cnf = parse_cnf_from_str("1 -2\n-1 2\n3 4").unwrap();
+        let minimal = cnf.minimize_model(&all_true(&cnf));
+        assert!(cnf.is_satisfied(&minimal));
+        assert_eq!(minimal.get(1), Some(false));
+        assert_eq!(minimal.get(2), Some(false));
+        assert_eq!(minimal.iter().filter(|&(_, val)| val).count(), 1);
+
+        // unassigned variables end up false
+        let cnf = parse_cnf_from_str("1 -2").unwrap();
+        let minimal = cnf.minimize_model(&Assignment::new_with(1, true));
+        assert_eq!(minimal, Assignment::new().with(1, false).with(2, false));
+    }
+
+    #[test]
+    fn test_validate() {
+        let cnf = parse_cnf_from_str("1 -2\n3\nfalse").unwrap();
+        assert_eq!(cnf.validate(), Ok(()));
+        assert_eq!(cnf.validate_dense(), Ok(()));
+
+        let sparse = parse_cnf_from_str("1 -2\n4").unwrap();
+        assert_eq!(sparse.validate(), Ok(()));
+        assert_eq!(sparse.validate_dense(), Err(ValidationError::Gap { var: 3 }));
+
+        let mut zero = Clause::new();
+        zero.add_negative(0);
+        let cnf = Cnf::new_with(vec![Clause::from_literals(&[(1, true)]), zero]);
+        assert_eq!(
+            cnf.validate(),
+            Err(ValidationError::ZeroVariable { clause_idx: 1 })
+        );
+
+        let cnf = Cnf::new_with(vec![Clause::from_literals(&[
+            (1, true),
+            (2, false),
+            (2, true),
+        ])]);
+        assert_eq!(
+            cnf.validate(),
+            Err(ValidationError::Tautology {
+                clause_idx: 0,
+                var: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_remove_subsumed() {
+        let mut cnf = parse_cnf_from_str("1 2 3\n1 2").unwrap();
+        assert_eq!(cnf.remove_subsumed(), 1);
+        assert_eq!(cnf, parse_cnf_from_str("1 2").unwrap());
+
+        let mut cnf = parse_cnf_from_str("-1 2 3\n4 5\n3 2 -1\n-1 3\n5 4 -6\n1 2").unwrap();
+        assert_eq!(cnf.remove_subsumed(), 3);
+        assert_eq!(cnf, parse_cnf_from_str("4 5\n-1 3\n1 2").unwrap());
+
+        let mut cnf = parse_cnf_from_str("1 2\nfalse\n3\nfalse").unwrap();
+        assert_eq!(cnf.remove_subsumed(), 3);
+        assert_eq!(cnf, parse_cnf_from_str("false").unwrap());
+
+        // the result is the same as comparing every pair of clauses
+        let mut rng = Rng::new(760);
+        for _ in 0..50 {
+            let mut cnf = random_cnf(&mut rng, 5, 20, 1..=4);
+            let expected = cnf
+                .clauses
+                .iter()
+                .enumerate()
+                .filter(|&(idx, clause)| {
+                    cnf.clauses.iter().enumerate().all(|(other_idx, other)| {
+                        !other.subsumes(clause) || (other == clause && other_idx >= idx)
+                    })
+                })
+                .map(|(_, clause)| clause.clone())
+                .collect::<Vec<_>>();
+            cnf.remove_subsumed();
+            assert_eq!(cnf.clauses, expected);
+        }
+    }
+
+    #[test]
+    fn test_remove_tautologies() {
+        let mut tautology = Clause::from_literals(&[(1, true), (2, true)]);
+        assert!(!tautology.is_tautology());
+        tautology.add_negative(1);
+        assert!(tautology.is_tautology());
+        assert!(tautology.is_satisfied(&Assignment::new_with(1, false)));
+        assert!(tautology.is_satisfied(&Assignment::new_with(1, true)));
+
+        let mut cnf = parse_cnf_from_str("1 2\n-3").unwrap();
+        cnf.clauses.insert(1, tautology);
+        cnf.clauses
+            .push(Clause::from_literals(&[(4, false), (4, true)]));
+        assert_eq!(cnf.remove_tautologies(), 2);
+        assert_eq!(cnf, parse_cnf_from_str("1 2\n-3").unwrap());
+        assert_eq!(cnf.validate(), Ok(()));
+        assert_eq!(cnf.remove_tautologies(), 0);
+    }
+
+    #[test]
+    fn test_quick_decide() {
+        let decide = |input| parse_cnf_from_str(input).unwrap().quick_decide();
+        assert_eq!(decide(""), Some(Satisfiability::Satisfiable));
+        assert_eq!(decide("1\n-2\n3"), Some(Satisfiability::Satisfiable));
+        assert_eq!(decide("1 -2\n-2 3"), Some(Satisfiability::Satisfiable));
+        assert_eq!(decide("1 2\nfalse"), Some(Satisfiability::Unsatisfiable));
+        assert_eq!(decide("1 2\n-3\n3"), Some(Satisfiability::Unsatisfiable));
+        assert_eq!(decide("1 2\n-1 2"), None);
+        assert_eq!(decide("1\n-1 2"), None);
+    }
+
+    #[test]
+    fn test_hardness_estimate() {
+        let random_3cnf = |num_vars: Var, num_clauses: usize| {
+            let mut rng = Rng::new(u64::from(num_vars) * 1000 + num_clauses as u64);
+            random_cnf(&mut rng, num_vars, num_clauses, 3..=3)
+        };
+
+        // easy classes
+        let horn = parse_cnf_from_str("1 -2 -3\n-1 -4 -5\n2\n3 -6 -7").unwrap();
+        assert_eq!(horn.hardness_estimate(), 0.0);
+        let two_sat = parse_cnf_from_str("1 2\n-1 3\n-2 -3\n2 3").unwrap();
+        assert_eq!(two_sat.hardness_estimate(), 0.0);
+
+        // the ratio near the threshold is hardest
+        let at_threshold = random_3cnf(50, 213).hardness_estimate();
+        assert!(at_threshold > 45.0);
+        assert!(random_3cnf(50, 100).hardness_estimate() < at_threshold / 2.0);
+        assert!(random_3cnf(50, 500).hardness_estimate() < at_threshold / 2.0);
+
+        // more variables are harder
+        assert!(random_3cnf(100, 426).hardness_estimate() > at_threshold);
+    }
+
+    #[test]
+    fn test_to_3cnf() {
+        let cnf = parse_cnf_from_str("1 2 3 4 -5\n-1 2\n3\n-2 -3 -4").unwrap();
+        assert!(!cnf.is_3cnf());
+        let (converted, max_var) = cnf.to_3cnf();
+        assert!(converted.is_3cnf());
+        // two links for the first clause, one dummy for the second, two for the third
+        assert_eq!(max_var, 10);
+        assert_eq!(converted.highest_var(), max_var);
+        assert_eq!(converted.clauses.len(), 3 + 2 + 4 + 1);
+        assert!(converted.clauses.contains(&cnf.clauses[3]));
+
+        for bits in 0..1u32 << 5 {
+            let assignment =
+                Assignment::new().with_all((1..=5).map(|var| (var, bits & (1 << (var - 1)) != 0)));
+            let mut solver = Solver::new(converted.clone());
+            let fixed = assignment.iter().collect::<Vec<_>>();
+            assert_eq!(
+                solver.solve_under_assumptions(&fixed).is_sat(),
+                cnf.is_satisfied(&assignment),
+                "{:?}",
+                assignment
+            );
+        }
+
+        let (converted, max_var) = parse_cnf_from_str("false").unwrap().to_3cnf();
+        assert_eq!((converted.clauses.len(), max_var), (8, 3));
+        assert!(!is_satisfiable(&converted).0);
+        assert_eq!(Cnf::new().to_3cnf(), (Cnf::new(), 0));
+    }
+
+    #[test]
+    fn test_primal_graph() {
+        let graph = parse_cnf_from_str("1 -2 3\n-3 4\n5\nfalse").unwrap().primal_graph();
+        let neighbours = |var| {
+            let mut neighbours = graph[&var].iter().copied().collect::<Vec<_>>();
+            neighbours.sort_unstable();
+            neighbours
+        };
+        assert_eq!(graph.len(), 5);
+        assert_eq!(neighbours(1), vec![2, 3]);
+        assert_eq!(neighbours(3), vec![1, 2, 4]);
+        assert_eq!(neighbours(4), vec![3]);
+        assert_eq!(neighbours(5), Vec::<Var>::new());
+    }
+
+    #[test]
+    fn test_detect_symmetries() {
+        let cnf = parse_cnf_from_str("1 2\n-1 3\n-2 3").unwrap();
+        assert_eq!(
+            cnf.detect_symmetries(),
+            vec![Permutation::from_swaps(&[(1, 2)])]
+        );
+
+        // three pigeons in two holes, the pigeons can be swapped
+        let pigeons = "1 2\n3 4\n5 6\n-1 -3\n-1 -5\n-3 -5\n-2 -4\n-2 -6\n-4 -6";
+        let cnf = parse_cnf_from_str(pigeons).unwrap();
+        let symmetries = cnf.detect_symmetries();
+        assert_eq!(symmetries.len(), 3);
+        assert!(symmetries.contains(&Permutation::from_swaps(&[(1, 3), (2, 4)])));
+        assert!(symmetries.contains(&Permutation::from_swaps(&[(3, 5), (4, 6)])));
+
+        let mut broken = cnf.clone();
+        broken
+            .clauses
+            .extend(cnf.symmetry_breaking_clauses(&symmetries));
+        assert!(!is_satisfiable(&broken).0);
+
+        // tautologies cannot be paired by sign
+        let cnf = Cnf::new_with(vec![
+            Clause::from_literals(&[(1, true), (1, false), (2, true)]),
+            Clause::from_literals(&[(3, true), (3, false), (4, true)]),
+        ]);
+        for symmetry in cnf.detect_symmetries() {
+            assert!(cnf
+                .clauses
+                .iter()
+                .all(|clause| cnf.clauses.contains(&symmetry.apply(clause))));
+        }
+    }
+
+    #[test]
+    fn test_symmetry_breaking_clauses() {
+        let cnf = parse_cnf_from_str("1 2\n-1 3\n-2 3").unwrap();
+        let swap = Permutation::from_swaps(&[(1, 2)]);
+        assert_eq!(
+            cnf.symmetry_breaking_clauses(&[swap]),
+            vec![Clause::from_literals(&[(1, false), (2, true)])]
+        );
+
+        // exactly the assignments at most as large as their image remain, using variable 5
+        let cnf = parse_cnf_from_str("1 2\n3 4").unwrap();
+        let permutations = [Permutation::from_swaps(&[(1, 3), (2, 4)])];
+        let clauses = cnf.symmetry_breaking_clauses(&permutations);
+        for bits in 0..16u32 {
+            let value = |var: Var| bits & (1 << (var - 1)) != 0;
+            let assignment = (1..=4).fold(Assignment::new(), |assignment, var| {
+                assignment.with(var, value(var))
+            });
+            let allowed = [true, false].iter().any(|&aux| {
+                let assignment = assignment.with(5, aux);
+                clauses
+                    .iter()
+                    .all(|clause| clause.is_satisfied(&assignment))
+            });
+            let own = (1..=4).map(value).collect::<Vec<_>>();
+            let image = (1..=4)
+                .map(|var| value(permutations[0].get(var)))
+                .collect::<Vec<_>>();
+            assert_eq!(allowed, own <= image, "{:?}", assignment);
+        }
+    }
+
+    #[test]
+    fn test_guiding_paths() {
+        let cnf = parse_cnf_from_str("1 2\n-1 -2\n2 3\n-3 -1").unwrap();
+        let paths = cnf.guiding_paths(&[3, 1]);
+        assert_eq!(
+            paths,
+            vec![
+                vec![(3, false), (1, false)],
+                vec![(3, false), (1, true)],
+                vec![(3, true), (1, false)],
+                vec![(3, true), (1, true)],
+            ]
+        );
+        assert_eq!(cnf.guiding_paths(&[]), vec![Vec::new()]);
+
+        // satisfiable iff some subproblem is
+        let mut solver = Solver::new(cnf);
+        let satisfiable = paths
+            .iter()
+            .filter(|path| solver.solve_under_assumptions(path).is_sat())
+            .count();
+        assert_eq!(satisfiable, 2);
+        let mut solver = Solver::new(parse_cnf_from_str("1 2\n-1 2\n1 -2\n-1 -2").unwrap());
+        assert!(paths
+            .iter()
+            .all(|path| !solver.solve_under_assumptions(path).is_sat()));
+    }
+
+    #[test]
+    fn test_components() {
+        let cnf = parse_cnf_from_str("1 2\n3 -4\n-2 5\nfalse\n4\n6\n5 -1").unwrap();
+        let components = cnf.components();
+
+        assert_eq!(
+            components,
+            vec![
+                parse_cnf_from_str("1 2\n-2 5\n5 -1").unwrap(),
+                parse_cnf_from_str("3 -4\n4").unwrap(),
+                parse_cnf_from_str("false").unwrap(),
+                parse_cnf_from_str("6").unwrap(),
+            ]
+        );
+
+        assert_eq!(cnf.into_components(), components);
+
+        // clauses joining two components merge them
+        let cnf = parse_cnf_from_str("1\n2\n3\n1 3\n2 3").unwrap();
+        assert_eq!(cnf.components(), vec![cnf.clone()]);
+        assert_eq!(cnf.clone().into_components(), vec![cnf]);
+
+        assert!(Cnf::new().components().is_empty());
+        assert!(Cnf::new().into_components().is_empty());
+    }
+
+    #[test]
+    fn test_polarity_counts() {
+        let counts = parse_cnf_from_str("1 -2\n1 2 3\n-1 -2").unwrap().polarity_counts();
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[&1], (2, 1));
+        assert_eq!(counts[&2], (1, 2));
+        assert_eq!(counts[&3], (1, 0));
+    }
+
+    #[test]
+    fn test_eliminate_pure_literals() {
+        // 4 is pure, then 1 and 2 once the clauses with 4 and 1 are gone
+        let original = parse_cnf_from_str("1 2\n-2 3\n-3 -2\n4 -1").unwrap();
+        let mut cnf = original.clone();
+        let pure = cnf.eliminate_pure_literals();
+        assert_eq!(cnf, Cnf::new());
+        assert_eq!(
+            pure,
+            Assignment::new().with(1, true).with(2, false).with(4, true)
+        );
+        assert!(original.is_satisfied(&pure));
+
+        let mut cnf = parse_cnf_from_str("1 2\n-1 -2\n1 3").unwrap();
+        let pure = cnf.eliminate_pure_literals();
+        assert_eq!(cnf, parse_cnf_from_str("1 2\n-1 -2").unwrap());
+        assert_eq!(pure, Assignment::new_with(3, true));
+
+        let mut rng = Rng::new(758);
+        for _ in 0..50 {
+            let original = random_cnf(&mut rng, 8, 12, 3..=3);
+            let mut cnf = original.clone();
+            let pure = cnf.eliminate_pure_literals();
+            match Solver::new(cnf).solve() {
+                SolveResult::Sat(model) => {
+                    assert!(original.is_satisfied(&model.with_all(pure.iter())));
+                }
+                SolveResult::Unsat => assert!(!is_satisfiable(&original).0),
+                SolveResult::Unknown => panic!("the search gave up"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_literals_counting_duplicates() {
+        let literals = [(1, true), (2, false), (1, true), (2, false)];
+        let (clause, duplicates) = Clause::from_literals_counting_duplicates(&literals);
+        assert_eq!(clause, Clause::from_literals(&[(1, true), (2, false)]));
+        assert_eq!(duplicates, 2);
+        assert_eq!(Clause::from_literals_counting_duplicates(&[(1, true)]).1, 0);
+    }
+
+    #[test]
+    fn test_clause_resolve() {
+        let lhs = Clause::from_literals(&[(1, true), (2, false), (3, true)]);
+        let rhs = Clause::from_literals(&[(1, false), (2, false), (4, true)]);
+        assert_eq!(
+            lhs.resolve(&rhs, 1),
+            Some(Clause::from_literals(&[(2, false), (3, true), (4, true)]))
+        );
+        assert_eq!(rhs.resolve(&lhs, 1), lhs.resolve(&rhs, 1));
+
+        let tautology = Clause::from_literals(&[(1, false), (3, false)]);
+        assert_eq!(lhs.resolve(&tautology, 1), None);
+
+        let unit = Clause::from_literals(&[(1, false)]);
+        assert_eq!(
+            Clause::from_literals(&[(1, true)]).resolve(&unit, 1),
+            Some(Clause::new())
+        );
+
+        // only one pair of opposite literals is resolved on
+        let both = Clause::from_literals(&[(1, true), (1, false), (2, true)]);
+        assert_eq!(
+            both.resolve(&unit, 1),
+            Some(Clause::from_literals(&[(1, false), (2, true)]))
+        );
+        assert_eq!(unit.resolve(&both, 1), both.resolve(&unit, 1));
+        assert_eq!(both.resolve(&both, 1), None);
+    }
+
+    #[test]
+    fn test_clause_subsumes() {
+        let clause = Clause::from_literals(&[(1, true), (2, false)]);
+        let larger = Clause::from_literals(&[(2, false), (3, true), (1, true)]);
+        assert!(clause.subsumes(&larger));
+        assert!(!larger.subsumes(&clause));
+        assert!(clause.subsumes(&clause));
+        assert!(!clause.subsumes(&Clause::from_literals(&[(1, true), (2, true), (3, true)])));
+        assert!(Clause::new().subsumes(&clause));
+    }
+
+    #[test]
+    fn test_clause_negate() {
+        let clause = Clause::from_literals(&[(1, true), (2, false)]);
+        let cnf = parse_cnf_from_str("1 2\n-2 3\n1 -3").unwrap();
+        assert_eq!(
+            clause.negate(),
+            parse_cnf_from_str("-1\n2").unwrap().clauses
+        );
+        assert!(Clause::new().negate().is_empty());
+
+        // 1 ∨ ¬2 follows from the formula
+        let mut negated = cnf.clone();
+        negated.clauses.extend(clause.negate());
+        assert!(!is_satisfiable(&negated).0);
+
+        // 2 ∨ 3 does not
+        let mut negated = cnf.clone();
+        negated
+            .clauses
+            .extend(Clause::from_literals(&[(2, true), (3, true)]).negate());
+        assert!(is_satisfiable(&negated).0);
+    }
+
+    #[test]
+    fn test_negation() {
+        let cnf = parse_cnf_from_str("1 2\n-2 3").unwrap();
+        let mut next_fresh = 4;
+        let negation = Cnf::new_with(cnf.negation(&mut next_fresh));
+        assert_eq!(next_fresh, 6);
+        assert!(
+            !is_satisfiable(&Cnf::new_with(
+                [cnf.clauses.clone(), negation.clauses.clone()].concat()
+            ))
+            .0
+        );
+
+        // the models of the negation are exactly the assignments violating the formula
+        for bits in 0..8u32 {
+            let assignment = Assignment::new()
+                .with(1, bits & 1 != 0)
+                .with(2, bits & 2 != 0)
+                .with(3, bits & 4 != 0);
+            let mut fixed = negation.clone();
+            fixed
+                .clauses
+                .extend(assignment.iter().map(|lit| Clause::from_literals(&[lit])));
+            assert_eq!(is_satisfiable(&fixed).0, !cnf.is_satisfied(&assignment));
+        }
+
+        // the formula implies a weaker one, but is not equivalent to it
+        let mut next_fresh = 4;
+        let weaker = parse_cnf_from_str("1 2 3").unwrap();
+        let mut weaker_and_not_cnf = weaker.clone();
+        weaker_and_not_cnf
+            .clauses
+            .extend(cnf.negation(&mut next_fresh));
+        assert!(is_satisfiable(&weaker_and_not_cnf).0);
+        let mut cnf_and_not_weaker = cnf.clone();
+        cnf_and_not_weaker
+            .clauses
+            .extend(weaker.negation(&mut next_fresh));
+        assert!(!is_satisfiable(&cnf_and_not_weaker).0);
+
+        assert_eq!(Cnf::new().negation(&mut next_fresh), vec![Clause::new()]);
+    }
+
+    #[test]
+    fn test_compact() {
+        let original = parse_cnf_from_str("12 -3\n-7\n3 7 -40").unwrap();
+        let mut cnf = original.clone();
+        let mapping = cnf.compact();
+        assert_eq!(cnf, parse_cnf_from_str("3 -1\n-2\n1 2 -4").unwrap());
+        assert_eq!(cnf.highest_var(), 4);
+        assert_eq!(mapping.len(), 4);
+        assert_eq!(mapping[&40], 4);
+
+        // the inverted mapping restores the original formula and translates models back
+        let inverse = mapping
+            .iter()
+            .map(|(&old, &new)| (new, old))
+            .collect::<HashMap<_, _>>();
+        let restored = cnf
+            .clauses
+            .iter()
+            .map(|clause| {
+                let literals = clause
+                    .literals()
+                    .map(|(var, val)| (inverse[&var], val))
+                    .collect::<Vec<_>>();
+                Clause::from_literals(&literals)
+            })
+            .collect();
+        assert_eq!(Cnf::new_with(restored), original);
+
+        let model = match Solver::new(cnf.clone()).solve() {
+            SolveResult::Sat(model) => model,
+            _ => panic!("formula is satisfiable"),
+        };
+        let translated =
+            Assignment::new().with_all(model.iter().map(|(var, val)| (inverse[&var], val)));
+        assert!(original.is_satisfied(&translated));
+
+        // an already dense formula keeps its numbering
+        let mut dense = cnf.clone();
+        assert!(dense.compact().iter().all(|(old, new)| old == new));
+        assert_eq!(dense, cnf);
+    }
+
+    #[test]
+    fn test_canonicalize_empty_clauses() {
+        let mut cnf = parse_cnf_from_str("false\n1\nfalse").unwrap();
+        cnf.canonicalize();
+        assert_eq!(cnf, parse_cnf_from_str("false\n1").unwrap());
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use crate::cnf::{negate, Clause, Cnf, LiteralTpl, Var};
+
+/// A boolean formula over named variables, built from arbitrarily nested gates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Var(String),
+    Not(Box<Expr>),
+    /// True iff all operands are true, so the empty conjunction is true
+    And(Vec<Expr>),
+    /// True iff at least one operand is true, so the empty disjunction is false
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    pub fn var(name: &str) -> Expr {
+        Expr::Var(name.to_string())
+    }
+
+    /// The value of the formula for the given values of its variables, `None` if one of
+    /// them is missing
+    pub fn evaluate(&self, values: &HashMap<String, bool>) -> Option<bool> {
+        let evaluate_all = |operands: &[Expr]| {
+            operands
+                .iter()
+                .map(|operand| operand.evaluate(values))
+                .collect::<Option<Vec<_>>>()
+        };
+        Some(match self {
+            Expr::Var(name) => *values.get(name)?,
+            Expr::Not(operand) => !operand.evaluate(values)?,
+            Expr::And(operands) => evaluate_all(operands)?.iter().all(|&val| val),
+            Expr::Or(operands) => evaluate_all(operands)?.iter().any(|&val| val),
+        })
+    }
+}
+
+impl std::ops::Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        Expr::Not(Box::new(self))
+    }
+}
+
+/// Encodes the formula as an equisatisfiable CNF (Tseitin transformation)
+///
+/// Every AND and OR gate gets an auxiliary variable `x` and the clauses of `x ↔ gate`, a
+/// negation just negates the literal of its operand. A unit clause asserts the literal of
+/// the whole formula, so the CNF is linear in the size of the formula.
+///
+/// Both the named variables and the auxiliary ones are allocated starting at `next_fresh`,
+/// which is increased accordingly. Returns the variable of every name, a model of the CNF
+/// restricted to them satisfies the formula.
+pub fn tseitin(expr: &Expr, next_fresh: &mut Var) -> (Cnf, HashMap<String, Var>) {
+    let mut encoder = Encoder {
+        cnf: Cnf::new(),
+        vars: HashMap::new(),
+        next_fresh,
+    };
+    let root = encoder.encode(expr);
+    encoder.cnf.clauses.push(Clause::from_literals(&[root]));
+    (encoder.cnf, encoder.vars)
+}
+
+struct Encoder<'a> {
+    cnf: Cnf,
+    vars: HashMap<String, Var>,
+    next_fresh: &'a mut Var,
+}
+
+impl Encoder<'_> {
+    fn fresh_var(&mut self) -> Var {
+        let var = *self.next_fresh;
+        *self.next_fresh += 1;
+        var
+    }
+
+    /// The literal equivalent to the formula, after adding the clauses defining it
+    fn encode(&mut self, expr: &Expr) -> LiteralTpl {
+        match expr {
+            Expr::Var(name) => match self.vars.get(name) {
+                Some(&var) => (var, true),
+                None => {
+                    let var = self.fresh_var();
+                    self.vars.insert(name.clone(), var);
+                    (var, true)
+                }
+            },
+            Expr::Not(operand) => negate(self.encode(operand)),
+            Expr::And(operands) => {
+                let operands = operands
+                    .iter()
+                    .map(|operand| self.encode(operand))
+                    .collect::<Vec<_>>();
+                (self.define_and(&operands), true)
+            }
+            // x ↔ a ∨ b is ¬x ↔ ¬a ∧ ¬b
+            Expr::Or(operands) => {
+                let operands = operands
+                    .iter()
+                    .map(|operand| negate(self.encode(operand)))
+                    .collect::<Vec<_>>();
+                (self.define_and(&operands), false)
+            }
+        }
+    }
+
+    /// A fresh variable `x` with the clauses `¬x ∨ a` for every operand `a` and
+    /// `x ∨ ¬a1 ∨ … ∨ ¬an`
+    fn define_and(&mut self, operands: &[LiteralTpl]) -> Var {
+        let output = self.fresh_var();
+        for &operand in operands {
+            self.push_clause(&[(output, false), operand]);
+        }
+        let mut literals = vec![(output, true)];
+        literals.extend(operands.iter().copied().map(negate));
+        self.push_clause(&literals);
+        output
+    }
+
+    /// Adds the clause unless it is a tautology, like for the operands `a` and `¬a`
+    fn push_clause(&mut self, literals: &[LiteralTpl]) {
+        let clause = Clause::from_literals(literals);
+        if !clause.is_tautology() {
+            self.cnf.clauses.push(clause);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::satsolve::{SolveResult, Solver};
+
+    const NAMES: [&str; 4] = ["a", "b", "c", "d"];
+
+    /// Solves the encoding of the formula and checks a found model against the formula
+    fn check_tseitin(expr: &Expr) -> bool {
+        let (cnf, vars) = tseitin(expr, &mut 1);
+        match Solver::new(cnf.clone()).solve() {
+            SolveResult::Sat(model) => {
+                // a variable missing from the model can take any value
+                let values = vars
+                    .iter()
+                    .map(|(name, &var)| (name.clone(), model.get(var).unwrap_or(false)))
+                    .collect::<HashMap<_, _>>();
+                assert_eq!(expr.evaluate(&values), Some(true), "model {:?}", model);
+                true
+            }
+            SolveResult::Unsat => false,
+            SolveResult::Unknown => panic!("the search gave up"),
+        }
+    }
+
+    /// Whether some values of [NAMES] satisfy the formula
+    fn brute_force(expr: &Expr) -> bool {
+        (0..1u32 << NAMES.len()).any(|bits| {
+            let values = NAMES
+                .iter()
+                .enumerate()
+                .map(|(i, name)| (name.to_string(), bits & (1 << i) != 0))
+                .collect::<HashMap<_, _>>();
+            expr.evaluate(&values) == Some(true)
+        })
+    }
+
+    fn random_expr(rng: &mut Rng, depth: u32) -> Expr {
+        match if depth == 0 { 0 } else { rng.below(4) } {
+            0 => Expr::var(NAMES[rng.below(NAMES.len() as u64) as usize]),
+            1 => !random_expr(rng, depth - 1),
+            kind => {
+                let operands = (0..rng.below(4))
+                    .map(|_| random_expr(rng, depth - 1))
+                    .collect();
+                if kind == 2 {
+                    Expr::And(operands)
+                } else {
+                    Expr::Or(operands)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tseitin_satisfiable() {
+        let (a, b, c) = (Expr::var("a"), Expr::var("b"), Expr::var("c"));
+        let expr = Expr::And(vec![
+            Expr::Or(vec![a.clone(), b.clone()]),
+            !Expr::And(vec![a.clone(), b]),
+            Expr::Or(vec![!a, c]),
+        ]);
+        assert!(check_tseitin(&expr));
+
+        assert!(check_tseitin(&Expr::And(vec![])));
+        assert!(check_tseitin(&!Expr::Or(vec![])));
+    }
+
+    #[test]
+    fn test_tseitin_unsatisfiable() {
+        let a = Expr::var("a");
+        assert!(!check_tseitin(&Expr::And(vec![a.clone(), !a.clone()])));
+        assert!(!check_tseitin(&!Expr::Or(vec![a.clone(), !a])));
+        assert!(!check_tseitin(&Expr::Or(vec![])));
+    }
+
+    #[test]
+    fn test_tseitin_variables() {
+        let expr = Expr::Or(vec![Expr::var("a"), !Expr::var("b"), Expr::var("a")]);
+        let mut next_fresh = 5;
+        let (cnf, vars) = tseitin(&expr, &mut next_fresh);
+
+        assert_eq!(vars.len(), 2);
+        assert_eq!((vars["a"], vars["b"]), (5, 6));
+        // a single auxiliary variable for the OR gate
+        assert_eq!(next_fresh, 8);
+        assert_eq!(cnf.highest_var(), 7);
+    }
+
+    #[test]
+    fn test_tseitin_random() {
+        let mut rng = Rng::new(787);
+        for _ in 0..200 {
+            let expr = random_expr(&mut rng, 4);
+            assert_eq!(check_tseitin(&expr), brute_force(&expr), "{:?}", expr);
+        }
+    }
+}
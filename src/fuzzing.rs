@@ -0,0 +1,108 @@
+//! Entrypoints for coverage guided fuzzers, see the `fuzz` directory
+//!
+//! Only compiled with the `fuzzing` feature, which the fuzz targets enable.
+//!
+//! Every function panics if the solver panics or gives a wrong answer.
+
+use crate::{
+    assignment::Assignment,
+    cnf::{Clause, Cnf, Var},
+    input::parse_dimacs,
+    satsolve::{SolveResult, Solver},
+};
+
+/// Formulas with at most this many distinct variables are checked against brute force
+const MAX_BRUTE_FORCE_VARS: usize = 12;
+
+/// Parses `data` as DIMACS text and solves it if it is valid
+pub fn check_dimacs(data: &[u8]) {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+    if let Ok(cnf) = parse_dimacs(text) {
+        check_formula(&cnf);
+    }
+}
+
+/// Interprets `data` directly as a formula and solves it
+///
+/// Every byte is a literal: the lower 7 bits are the variable, the highest bit its sign.
+/// Variable 0 terminates the current clause. Literals which are already part of the clause
+/// with either sign are skipped.
+pub fn check_bytes(data: &[u8]) {
+    let mut cnf = Cnf::new();
+    let mut clause = Clause::new();
+
+    for &byte in data {
+        let var = Var::from(byte & 0x7f);
+        if var == 0 {
+            cnf.clauses.push(std::mem::take(&mut clause));
+        } else if clause.get(var).is_none() {
+            if byte & 0x80 == 0 {
+                clause.add_positive(var);
+            } else {
+                clause.add_negative(var);
+            }
+        }
+    }
+    cnf.clauses.push(clause);
+
+    check_formula(&cnf);
+}
+
+/// Solves `cnf`, checks a found model and compares the result to brute force for small formulas
+pub fn check_formula(cnf: &Cnf) {
+    let result = Solver::new(cnf.clone()).solve();
+
+    if let SolveResult::Sat(model) = &result {
+        assert!(cnf.is_satisfied(model), "wrong model {:?}", model);
+    }
+
+    let vars = cnf.vars().collect::<Vec<_>>();
+    if vars.len() <= MAX_BRUTE_FORCE_VARS {
+        let expected = brute_force(cnf, &vars);
+        assert_eq!(
+            result.is_sat(),
+            expected.is_some(),
+            "brute force found {:?}",
+            expected
+        );
+    }
+}
+
+/// Tries every assignment of `vars`
+fn brute_force(cnf: &Cnf, vars: &[Var]) -> Option<Assignment> {
+    (0..1u64 << vars.len())
+        .map(|bits| {
+            Assignment::new().with_all(
+                vars.iter()
+                    .enumerate()
+                    .map(|(i, &var)| (var, bits & (1 << i) != 0)),
+            )
+        })
+        .find(|assignment| cnf.is_satisfied(assignment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_dimacs() {
+        check_dimacs(b"p cnf 3 3\n1 2 0\n-1 3 0\n-3 -2 0\n");
+        check_dimacs(b"p cnf 1 2\n1 0\n-1 0\n");
+        check_dimacs(b"p cnf 1 1\n0\n");
+        check_dimacs(b"not a formula");
+        check_dimacs(&[0xff, 0xfe]);
+    }
+
+    #[test]
+    fn test_check_bytes() {
+        check_bytes(&[]);
+        check_bytes(&[1, 2, 0, 0x81, 3, 0, 0x83, 0x82]);
+        check_bytes(&[1, 0, 0x81]);
+        // duplicate and complementary literals are skipped
+        check_bytes(&[1, 1, 0x81, 0, 0x81]);
+    }
+}
@@ -0,0 +1,426 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::assignment::Assignment;
+use crate::cnf::{negate, Clause, Cnf, LiteralTpl, Var};
+
+/// The function a [Gate] computes from its inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateKind {
+    /// The output is true iff all inputs are true
+    And,
+    /// The output is true iff at least one input is true
+    Or,
+    /// The output is true iff exactly one of the two inputs is true
+    Xor,
+}
+
+/// A variable defined as a function of other literals by some clauses of a formula, like
+/// the auxiliary variables of a Tseitin encoding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gate {
+    pub kind: GateKind,
+    pub output: Var,
+    pub inputs: Vec<LiteralTpl>,
+    /// The indices of the clauses encoding `output ↔ kind(inputs)`
+    pub clauses: Vec<usize>,
+}
+
+impl Gate {
+    /// The value of the output for the values of the inputs in the assignment, `None` if
+    /// an input is unassigned
+    pub fn evaluate(&self, assignment: &Assignment) -> Option<bool> {
+        let values = self
+            .inputs
+            .iter()
+            .map(|&lit| assignment.get_lit(lit))
+            .collect::<Option<Vec<_>>>()?;
+        Some(match self.kind {
+            GateKind::And => values.iter().all(|&val| val),
+            GateKind::Or => values.iter().any(|&val| val),
+            GateKind::Xor => values.iter().fold(false, |acc, &val| acc ^ val),
+        })
+    }
+}
+
+/// Finds the AND, OR and XOR gates encoded among the clauses of the formula
+///
+/// An AND or OR gate with `n >= 2` inputs is the `n + 1` clauses of its Tseitin encoding,
+/// e.g. `x ↔ a ∧ b` is `¬x ∨ a`, `¬x ∨ b` and `x ∨ ¬a ∨ ¬b`. An XOR gate has two inputs and
+/// is encoded by four 3-clauses over the same variables. Each of these variables is
+/// defined by the other two, so every XOR encoding yields three gates.
+///
+/// A variable may be the output of several gates. The gates are ordered by the index of
+/// their first clause. Tautological clauses are never part of a gate.
+pub fn detect_gates(cnf: &Cnf) -> Vec<Gate> {
+    let mut binaries = HashMap::new();
+    let mut clause_indices = HashMap::new();
+    for (idx, clause) in cnf.clauses.iter().enumerate() {
+        if clause.len() == 2 && !clause.is_tautology() {
+            let mut literals = clause.literals();
+            let key = binary_key(literals.next().unwrap(), literals.next().unwrap());
+            binaries.entry(key).or_insert(idx);
+        }
+        clause_indices.entry(clause).or_insert(idx);
+    }
+
+    let mut gates = Vec::new();
+    for (idx, clause) in cnf.clauses.iter().enumerate() {
+        if clause.len() < 3 || clause.is_tautology() {
+            continue;
+        }
+
+        // `o ∨ m1 ∨ … ∨ mn` with binary clauses `¬o ∨ ¬mi` encodes `o ↔ ¬m1 ∧ … ∧ ¬mn`
+        for output in clause.literals() {
+            let others = clause
+                .literals()
+                .filter(|&lit| lit != output)
+                .collect::<Vec<_>>();
+            let defining = others
+                .iter()
+                .map(|&lit| {
+                    binaries
+                        .get(&binary_key(negate(output), negate(lit)))
+                        .copied()
+                })
+                .collect::<Option<Vec<_>>>();
+            if let Some(defining) = defining {
+                let (kind, inputs) = if output.1 {
+                    (GateKind::And, others.into_iter().map(negate).collect())
+                } else {
+                    (GateKind::Or, others)
+                };
+                gates.push(Gate {
+                    kind,
+                    output: output.0,
+                    inputs,
+                    clauses: std::iter::once(idx).chain(defining).collect(),
+                });
+            }
+        }
+
+        if clause.len() == 3 {
+            gates.extend(detect_xor(clause, idx, &clause_indices));
+        }
+    }
+    gates
+}
+
+/// The three gates of the XOR encoding which `clause` is the first clause of, if any
+fn detect_xor(clause: &Clause, idx: usize, clause_indices: &HashMap<&Clause, usize>) -> Vec<Gate> {
+    let mut vars = clause.literals().map(|(var, _)| var).collect::<Vec<_>>();
+    vars.sort_unstable();
+    let parity = clause.negatives().count() % 2;
+
+    // the four sign patterns with the same parity of negative literals
+    let mut indices = Vec::new();
+    for negatives in 0..8u32 {
+        if negatives.count_ones() as usize % 2 != parity {
+            continue;
+        }
+        let literals = vars
+            .iter()
+            .enumerate()
+            .map(|(pos, &var)| (var, negatives & (1 << pos) == 0))
+            .collect::<Vec<_>>();
+        match clause_indices.get(&Clause::from_literals(&literals)) {
+            Some(&clause_idx) => indices.push(clause_idx),
+            None => return Vec::new(),
+        }
+    }
+    indices.sort_unstable();
+    if indices[0] != idx {
+        return Vec::new();
+    }
+
+    // an odd number of negative literals in every clause forbids an odd number of true
+    // variables, so each variable is the XOR of the other two
+    vars.iter()
+        .map(|&output| {
+            let mut inputs = vars
+                .iter()
+                .filter(|&&var| var != output)
+                .map(|&var| (var, true))
+                .collect::<Vec<_>>();
+            if parity == 0 {
+                inputs[0] = negate(inputs[0]);
+            }
+            Gate {
+                kind: GateKind::Xor,
+                output,
+                inputs,
+                clauses: indices.clone(),
+            }
+        })
+        .collect()
+}
+
+fn binary_key(lhs: LiteralTpl, rhs: LiteralTpl) -> (LiteralTpl, LiteralTpl) {
+    (lhs.min(rhs), lhs.max(rhs))
+}
+
+/// Eliminates the outputs of the gates found by [detect_gates] from the formula
+///
+/// Eliminating an output replaces all clauses containing it by their resolvents on it,
+/// but only those between a clause of the gate and a clause outside of it. The others are
+/// implied, so the result is satisfiable iff the formula is. An output is only eliminated
+/// if this does not increase the number of clauses, and only while all clauses of its
+/// gate are still part of the formula. Gates emerging from the resolvents are not
+/// detected. Tautological clauses are dropped.
+///
+/// Returns the simplified formula and the gates whose outputs were eliminated, in the
+/// order of elimination. A model of the simplified formula is turned into one of the
+/// original formula by [extend_model].
+pub fn eliminate_gates(cnf: &Cnf) -> (Cnf, Vec<Gate>) {
+    // tautologies are dropped right away, resolving on them would only yield clauses
+    // implied by the other side
+    let mut clauses = cnf
+        .clauses
+        .iter()
+        .map(|clause| Some(clause.clone()).filter(|clause| !clause.is_tautology()))
+        .collect::<Vec<_>>();
+    let mut occurrences = HashMap::<LiteralTpl, Vec<usize>>::new();
+    for (idx, clause) in clauses.iter().enumerate() {
+        for lit in clause.iter().flat_map(Clause::literals) {
+            occurrences.entry(lit).or_default().push(idx);
+        }
+    }
+
+    let mut eliminated = Vec::new();
+    let mut eliminated_vars = HashSet::new();
+    for gate in detect_gates(cnf) {
+        if eliminated_vars.contains(&gate.output)
+            || gate.clauses.iter().any(|&idx| clauses[idx].is_none())
+        {
+            continue;
+        }
+
+        let occurring = |val: bool| -> Vec<usize> {
+            occurrences
+                .get(&(gate.output, val))
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|&idx| clauses[idx].is_some())
+                .collect()
+        };
+        let (positive, negative) = (occurring(true), occurring(false));
+
+        let mut resolvents = Vec::new();
+        for &pos_idx in &positive {
+            for &neg_idx in &negative {
+                if gate.clauses.contains(&pos_idx) == gate.clauses.contains(&neg_idx) {
+                    continue;
+                }
+                let (pos, neg) = (&clauses[pos_idx], &clauses[neg_idx]);
+                let resolvent = pos
+                    .as_ref()
+                    .unwrap()
+                    .resolve(neg.as_ref().unwrap(), gate.output);
+                resolvents.extend(resolvent);
+            }
+        }
+        if resolvents.len() > positive.len() + negative.len() {
+            continue;
+        }
+
+        for idx in positive.into_iter().chain(negative) {
+            clauses[idx] = None;
+        }
+        for resolvent in resolvents {
+            for lit in resolvent.literals() {
+                occurrences.entry(lit).or_default().push(clauses.len());
+            }
+            clauses.push(Some(resolvent));
+        }
+        eliminated_vars.insert(gate.output);
+        eliminated.push(gate);
+    }
+
+    (
+        Cnf::new_with(clauses.into_iter().flatten().collect()),
+        eliminated,
+    )
+}
+
+/// Extends a model of the formula returned by [eliminate_gates] to one of the original
+/// formula, by assigning the outputs of the eliminated gates
+///
+/// Inputs left unassigned by the model are set to false first.
+pub fn extend_model(eliminated: &[Gate], model: &Assignment) -> Assignment {
+    let mut model = model.clone();
+    // an output may be the input of a gate eliminated before
+    for gate in eliminated.iter().rev() {
+        for &(var, _) in &gate.inputs {
+            if model.get(var).is_none() {
+                model.change(var, false);
+            }
+        }
+        model.change(gate.output, gate.evaluate(&model).unwrap());
+    }
+    model
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::parse_cnf_from_str;
+    use crate::rng::Rng;
+    use crate::satsolve::{is_satisfiable, SolveResult, Solver};
+
+    #[test]
+    fn test_detect_gates() {
+        // 3 ↔ 1 ∧ 2, 4 ↔ 3 ∨ ¬5
+        let cnf = parse_cnf_from_str("-3 1\n-3 2\n3 -1 -2\n4 -3\n4 5\n-4 3 -5").unwrap();
+        let gates = detect_gates(&cnf);
+        assert_eq!(
+            gates,
+            vec![
+                Gate {
+                    kind: GateKind::And,
+                    output: 3,
+                    inputs: vec![(1, true), (2, true)],
+                    clauses: vec![2, 0, 1],
+                },
+                Gate {
+                    kind: GateKind::Or,
+                    output: 4,
+                    inputs: vec![(3, true), (5, false)],
+                    clauses: vec![5, 3, 4],
+                },
+            ]
+        );
+
+        let model = Assignment::new().with(1, true).with(2, false).with(5, true);
+        assert_eq!(gates[0].evaluate(&model), Some(false));
+        assert_eq!(gates[1].evaluate(&model), None);
+        assert_eq!(gates[1].evaluate(&model.with(3, false)), Some(false));
+    }
+
+    #[test]
+    fn test_detect_xor() {
+        // 1 ⊕ 2 ⊕ 3 = 0
+        let cnf = parse_cnf_from_str("1 2 -3\n1 -2 3\n-1 2 3\n-1 -2 -3").unwrap();
+        let gates = detect_gates(&cnf);
+        assert_eq!(gates.len(), 3);
+        assert!(gates.iter().all(|gate| gate.kind == GateKind::Xor));
+        assert_eq!(gates[0].output, 1);
+        assert_eq!(gates[0].inputs, vec![(2, true), (3, true)]);
+        assert_eq!(gates[0].clauses, vec![0, 1, 2, 3]);
+
+        // 1 ⊕ 2 ⊕ 3 = 1 makes 1 ↔ ¬2 ⊕ 3
+        let cnf = parse_cnf_from_str("1 2 3\n1 -2 -3\n-1 2 -3\n-1 -2 3").unwrap();
+        let gates = detect_gates(&cnf);
+        assert_eq!(gates.len(), 3);
+        assert_eq!(gates[0].inputs, vec![(2, false), (3, true)]);
+        let model = Assignment::new().with(2, true).with(3, true);
+        assert_eq!(gates[0].evaluate(&model), Some(true));
+
+        // an incomplete encoding is no gate
+        let cnf = parse_cnf_from_str("1 2 -3\n1 -2 3\n-1 2 3").unwrap();
+        assert!(detect_gates(&cnf).is_empty());
+    }
+
+    #[test]
+    fn test_eliminate_gates() {
+        // 4 ↔ 1 ∧ 2, 5 ↔ 4 ∨ 3, 6 ↔ 5 ⊕ 1, asserting 6 and ¬3
+        let input = "-4 1\n-4 2\n4 -1 -2\n5 -4\n5 -3\n-5 4 3\n\
+            -6 5 1\n-6 -5 -1\n6 -5 1\n6 5 -1\n6\n-3";
+        let cnf = parse_cnf_from_str(input).unwrap();
+        let (simplified, eliminated) = eliminate_gates(&cnf);
+        assert!(!eliminated.is_empty());
+        assert!(simplified.clauses.len() < cnf.clauses.len());
+        for gate in &eliminated {
+            assert!(simplified.vars().all(|var| var != gate.output));
+        }
+
+        let model = match Solver::new(simplified).solve() {
+            SolveResult::Sat(model) => model,
+            _ => panic!("the simplified formula is satisfiable"),
+        };
+        assert!(cnf.is_satisfied(&extend_model(&eliminated, &model)));
+
+        // 4 ↔ 1 ∧ 2 contradicts ¬1 and 4
+        let cnf = parse_cnf_from_str("-4 1\n-4 2\n4 -1 -2\n-1\n4").unwrap();
+        let (simplified, eliminated) = eliminate_gates(&cnf);
+        assert_eq!(eliminated.len(), 1);
+        assert!(!Solver::new(simplified).solve().is_sat());
+
+        // tautologies are neither part of a gate nor resolved on
+        let clause = |literals: &[LiteralTpl]| Clause::from_literals(literals);
+        let cnf = Cnf::new_with(vec![
+            clause(&[(1, false), (2, true)]),
+            clause(&[(1, false), (2, false), (2, true)]),
+            clause(&[(1, false), (1, true), (2, false)]),
+            clause(&[(1, true), (2, true)]),
+            clause(&[(1, false), (1, true)]),
+        ]);
+        let (simplified, eliminated) = eliminate_gates(&cnf);
+        assert!(simplified
+            .clauses
+            .iter()
+            .all(|clause| !clause.is_tautology()));
+        let model = match Solver::new(simplified).solve() {
+            SolveResult::Sat(model) => model,
+            _ => panic!("the formula is satisfiable"),
+        };
+        assert!(cnf.is_satisfied(&extend_model(&eliminated, &model)));
+    }
+
+    #[test]
+    fn test_eliminate_gates_random_circuits() {
+        let mut rng = Rng::new(7);
+        for _ in 0..100 {
+            // gates over the inputs 1 to 4 and the previous gates, constrained by random
+            // clauses over all variables
+            let mut clauses = Vec::new();
+            for output in 5..=10 {
+                let mut lit = || (rng.below(output as u64 - 1) as Var + 1, rng.below(2) == 0);
+                let (a, b) = (lit(), lit());
+                if a.0 == b.0 {
+                    continue;
+                }
+                let x = (output, true);
+                match rng.below(3) {
+                    0 => clauses.extend(vec![
+                        vec![negate(x), a],
+                        vec![negate(x), b],
+                        vec![x, negate(a), negate(b)],
+                    ]),
+                    1 => clauses.extend(vec![
+                        vec![x, negate(a)],
+                        vec![x, negate(b)],
+                        vec![negate(x), a, b],
+                    ]),
+                    _ => clauses.extend(vec![
+                        vec![negate(x), a, b],
+                        vec![negate(x), negate(a), negate(b)],
+                        vec![x, negate(a), b],
+                        vec![x, a, negate(b)],
+                    ]),
+                }
+            }
+            for _ in 0..rng.below(6) {
+                let clause = (0..3)
+                    .map(|_| (rng.below(10) as Var + 1, rng.below(2) == 0))
+                    .collect::<Vec<_>>();
+                clauses.push(clause);
+            }
+            let cnf = Cnf::new_with(
+                clauses
+                    .iter()
+                    .filter(|literals| literals.iter().all(|&lit| !literals.contains(&negate(lit))))
+                    .map(|literals| Clause::from_literals(literals))
+                    .collect(),
+            );
+
+            let (simplified, eliminated) = eliminate_gates(&cnf);
+            match Solver::new(simplified).solve() {
+                SolveResult::Sat(model) => {
+                    assert!(cnf.is_satisfied(&extend_model(&eliminated, &model)))
+                }
+                SolveResult::Unsat => assert!(!is_satisfiable(&cnf).0),
+                SolveResult::Unknown => panic!("the search gave up"),
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::assignment::Assignment;
+use crate::cnf::{Clause, LiteralTpl, Var, XorClause};
+
+/// The outcome of a Gaussian elimination of the parity constraints under an assignment
+#[derive(Debug, PartialEq, Eq)]
+pub enum Elimination {
+    /// A clause following from the constraints which is false or unit under the assignment
+    ///
+    /// It is false if the constraints contradict the assignment, otherwise it implies the
+    /// value of its only unassigned variable.
+    Clause(Clause),
+    /// The constraints neither contradict the assignment nor imply a value, the literals
+    /// assign their unassigned variables such that all constraints are satisfied
+    Consistent(Vec<LiteralTpl>),
+}
+
+/// A parity constraint over the unassigned variables, combined from the original ones
+struct Row {
+    /// The columns of the unassigned variables in the row
+    columns: Vec<u64>,
+    /// The indices of the original constraints the row is the sum of
+    sources: Vec<u64>,
+    rhs: bool,
+}
+
+impl Row {
+    fn get(&self, column: usize) -> bool {
+        self.columns[column / 64] & (1 << (column % 64)) != 0
+    }
+
+    fn add(&mut self, other: &Row) {
+        for (word, other_word) in self.columns.iter_mut().zip(&other.columns) {
+            *word ^= other_word;
+        }
+        for (word, other_word) in self.sources.iter_mut().zip(&other.sources) {
+            *word ^= other_word;
+        }
+        self.rhs ^= other.rhs;
+    }
+
+    fn set_columns(&self) -> impl Iterator<Item = usize> + '_ {
+        ones(&self.columns)
+    }
+}
+
+fn toggle(words: &mut [u64], idx: usize) {
+    words[idx / 64] ^= 1 << (idx % 64);
+}
+
+fn ones(words: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    words.iter().enumerate().flat_map(|(word_idx, &word)| {
+        (0..64)
+            .filter(move |bit| word & (1 << bit) != 0)
+            .map(move |bit| word_idx * 64 + bit)
+    })
+}
+
+/// Brings the constraints into reduced row echelon form over their unassigned variables
+///
+/// A row without unassigned variables but an odd right hand side is a contradiction, a row
+/// with a single one implies its value. Contradictions are reported before implications.
+pub fn eliminate(xors: &[XorClause], assignment: &Assignment) -> Elimination {
+    let mut columns = HashMap::new();
+    let mut column_vars = Vec::new();
+    for var in xors.iter().flat_map(|xor| xor.vars.iter().copied()) {
+        if assignment.get(var).is_none() && !columns.contains_key(&var) {
+            columns.insert(var, column_vars.len());
+            column_vars.push(var);
+        }
+    }
+
+    let mut rows = xors
+        .iter()
+        .enumerate()
+        .map(|(idx, xor)| {
+            let mut row = Row {
+                columns: vec![0; column_vars.len().div_ceil(64)],
+                sources: vec![0; xors.len().div_ceil(64)],
+                rhs: xor.rhs,
+            };
+            toggle(&mut row.sources, idx);
+            for &var in &xor.vars {
+                match assignment.get(var) {
+                    Some(val) => row.rhs ^= val,
+                    None => toggle(&mut row.columns, columns[&var]),
+                }
+            }
+            row
+        })
+        .collect::<Vec<_>>();
+
+    let mut pivots = Vec::new();
+    for column in 0..column_vars.len() {
+        let pivot_row = pivots.len();
+        let found = (pivot_row..rows.len()).find(|&idx| rows[idx].get(column));
+        if let Some(idx) = found {
+            rows.swap(pivot_row, idx);
+            let (above, rest) = rows.split_at_mut(pivot_row);
+            let (pivot, below) = rest.split_first_mut().unwrap();
+            for row in above.iter_mut().chain(below) {
+                if row.get(column) {
+                    row.add(pivot);
+                }
+            }
+            pivots.push(column);
+        }
+    }
+
+    if let Some(row) = rows[pivots.len()..].iter().find(|row| row.rhs) {
+        return Elimination::Clause(explain(xors, row, assignment, None));
+    }
+    for (row, &column) in rows.iter().zip(&pivots) {
+        if row.set_columns().nth(1).is_none() {
+            let implied = (column_vars[column], row.rhs);
+            return Elimination::Clause(explain(xors, row, assignment, Some(implied)));
+        }
+    }
+
+    // the variables without a pivot are set to false, so every pivot equals its rhs
+    let mut extension = column_vars
+        .iter()
+        .map(|&var| (var, false))
+        .collect::<Vec<_>>();
+    for (row, &column) in rows.iter().zip(&pivots) {
+        extension[column].1 = row.rhs;
+    }
+    Elimination::Consistent(extension)
+}
+
+/// The clause stating that the variables of the original sum of the row do not all keep
+/// their value, or that the implied literal holds
+fn explain(
+    xors: &[XorClause],
+    row: &Row,
+    assignment: &Assignment,
+    implied: Option<LiteralTpl>,
+) -> Clause {
+    // a variable occurring in an even number of the summed constraints cancels out
+    let mut vars = BTreeSet::<Var>::new();
+    for idx in ones(&row.sources) {
+        for &var in &xors[idx].vars {
+            if !vars.remove(&var) {
+                vars.insert(var);
+            }
+        }
+    }
+
+    let mut literals = implied.into_iter().collect::<Vec<_>>();
+    for var in vars {
+        if Some(var) != implied.map(|(implied_var, _)| implied_var) {
+            let val = assignment
+                .get(var)
+                .expect("Only the implied variable of a row is unassigned");
+            literals.push((var, !val));
+        }
+    }
+    Clause::from_literals(&literals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xor(vars: &[Var], rhs: bool) -> XorClause {
+        XorClause::new(vars.to_vec(), rhs)
+    }
+
+    fn clause(literals: &[LiteralTpl]) -> Elimination {
+        Elimination::Clause(Clause::from_literals(literals))
+    }
+
+    #[test]
+    fn test_eliminate_consistent() {
+        let xors = [xor(&[1, 2, 3], true), xor(&[2, 3, 4], false)];
+        let extension = match eliminate(&xors, &Assignment::new()) {
+            Elimination::Consistent(extension) => extension,
+            clause => panic!("nothing is implied, but got {:?}", clause),
+        };
+        let assignment = Assignment::new().with_all(extension.into_iter());
+        assert!(xors.iter().all(|xor| xor.is_satisfied(&assignment)));
+
+        assert_eq!(
+            eliminate(&[], &Assignment::new()),
+            Elimination::Consistent(vec![])
+        );
+    }
+
+    #[test]
+    fn test_eliminate_implication() {
+        // the sum of both constraints is 1 ⊕ 4 = 1
+        let xors = [xor(&[1, 2, 3], true), xor(&[2, 3, 4], false)];
+        let assignment = Assignment::new_with(1, true);
+        assert_eq!(
+            eliminate(&xors, &assignment),
+            clause(&[(1, false), (4, false)])
+        );
+
+        let assignment = Assignment::new().with(2, true).with(3, false).with(4, true);
+        assert_eq!(
+            eliminate(&xors, &assignment),
+            clause(&[(1, false), (2, false), (3, true)])
+        );
+    }
+
+    #[test]
+    fn test_eliminate_contradiction() {
+        let xors = [xor(&[1, 2], true), xor(&[2, 3], true), xor(&[1, 3], true)];
+        assert_eq!(eliminate(&xors, &Assignment::new()), clause(&[]));
+
+        let xors = [xor(&[1, 2, 3], true), xor(&[2, 3, 4], false)];
+        let assignment = Assignment::new().with(1, true).with(4, true);
+        assert_eq!(
+            eliminate(&xors, &assignment),
+            clause(&[(1, false), (4, false)])
+        );
+
+        // a variable listed twice cancels out
+        assert_eq!(
+            eliminate(&[xor(&[5, 5], true)], &Assignment::new()),
+            clause(&[])
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use crate::{
+    assignment::Assignment,
+    cnf::{Clause, Cnf, LiteralTpl, Var},
+    rng::Rng,
+};
+
+/// Chooses the decisions of the search
+///
+/// The solver asks for a variable whenever propagation has finished without finding
+/// a model or a conflict.
+pub trait DecisionHeuristic {
+    /// Picks an unassigned variable to decide next, or None if there is none left
+    ///
+    /// `max` is the highest variable of the formula, the search determines it once when
+    /// it starts.
+    fn pick(&mut self, cnf: &Cnf, assignment: &Assignment, max: Var) -> Option<Var>;
+
+    /// The value the picked variable is tried with first
+    ///
+    /// Returning None leaves the choice to the configured
+    /// [Polarity](crate::satsolve::Polarity), which is the default.
+    fn polarity(&mut self, _cnf: &Cnf, _assignment: &Assignment, _var: Var) -> Option<bool> {
+        None
+    }
+
+    /// Called with the clause falsified by every conflict of the search, before backtracking
+    fn on_conflict(&mut self, _clause: &Clause) {}
+}
+
+/// Decides the variables in numeric order
+#[derive(Debug, Clone, Default)]
+pub struct NumericOrder;
+
+impl DecisionHeuristic for NumericOrder {
+    fn pick(&mut self, _cnf: &Cnf, assignment: &Assignment, max: Var) -> Option<Var> {
+        (1..=max).find(|&var| assignment.get(var).is_none())
+    }
+}
+
+/// Decides the variables in the order of the given list
+///
+/// Variables missing from the list are decided afterwards like by [NumericOrder].
+#[derive(Debug, Clone, Default)]
+pub struct StaticOrder(pub Vec<Var>);
+
+impl StaticOrder {
+    /// The variables `1..=max` in an order determined by the seed
+    pub(crate) fn shuffled(max: Var, seed: u64) -> Self {
+        let mut order = (1..=max).collect::<Vec<_>>();
+        Rng::new(seed).shuffle(&mut order);
+        StaticOrder(order)
+    }
+}
+
+impl DecisionHeuristic for StaticOrder {
+    fn pick(&mut self, cnf: &Cnf, assignment: &Assignment, max: Var) -> Option<Var> {
+        self.0
+            .iter()
+            .copied()
+            .find(|&var| assignment.get(var).is_none())
+            .or_else(|| NumericOrder.pick(cnf, assignment, max))
+    }
+}
+
+/// Decides the literal which satisfies the most clauses not yet satisfied
+///
+/// This one step look-ahead counts the occurrences of every unassigned literal in these
+/// clauses on each decision, which is much more expensive than a fixed order. Ties are
+/// broken towards the lower variable and towards false.
+#[derive(Debug, Clone, Default)]
+pub struct Greedy {
+    /// The last picked literal, its sign is the polarity of the decision
+    picked: Option<LiteralTpl>,
+}
+
+impl DecisionHeuristic for Greedy {
+    fn pick(&mut self, cnf: &Cnf, assignment: &Assignment, _max: Var) -> Option<Var> {
+        let mut counts: HashMap<LiteralTpl, usize> = HashMap::new();
+        let open_clauses = cnf
+            .clauses
+            .iter()
+            .filter(|clause| !clause.is_satisfied(assignment));
+        for clause in open_clauses {
+            for lit in clause.literals() {
+                if assignment.get(lit.0).is_none() {
+                    *counts.entry(lit).or_default() += 1;
+                }
+            }
+        }
+
+        // the maximum prefers greater counts, then lower variables, then false
+        self.picked = counts
+            .into_iter()
+            .max_by_key(|&((var, val), count)| (count, std::cmp::Reverse(var), !val))
+            .map(|(lit, _)| lit);
+        self.picked.map(|(var, _)| var)
+    }
+
+    fn polarity(&mut self, _cnf: &Cnf, _assignment: &Assignment, var: Var) -> Option<bool> {
+        self.picked
+            .filter(|&(picked_var, _)| picked_var == var)
+            .map(|(_, val)| val)
+    }
+}
+
+/// Decides the unassigned variable with the highest activity (VSIDS)
+///
+/// Every conflict bumps the activity of the variables of the falsified clause, so the search
+/// focuses on the variables involved in recent conflicts. Instead of decaying all activities
+/// on every conflict, the bump grows by the inverse of the decay factor, and all activities
+/// are scaled down once they get too large. Ties are broken towards the lower variable, so
+/// before the first conflict this decides like [NumericOrder], but including the highest
+/// variable. Finding the maximum is a scan over all variables.
+#[derive(Debug, Clone)]
+pub struct Vsids {
+    /// The activity of every variable, indexed by the variable
+    activities: Vec<f64>,
+    /// The amount the next conflict bumps the activities by
+    bump: f64,
+    /// The factor all activities decay by on every conflict, in `(0, 1]`
+    decay: f64,
+}
+
+impl Vsids {
+    /// Activities are scaled down once one of them exceeds this
+    const RESCALE_LIMIT: f64 = 1e100;
+
+    pub fn new(decay: f64) -> Self {
+        assert!(
+            decay > 0.0 && decay <= 1.0,
+            "Decay factor {} is not in (0, 1]",
+            decay
+        );
+        Vsids {
+            activities: Vec::new(),
+            bump: 1.0,
+            decay,
+        }
+    }
+
+    /// The current activity of the variable, relative to the activities of the others
+    pub fn activity(&self, var: Var) -> f64 {
+        self.activities.get(var as usize).copied().unwrap_or(0.0)
+    }
+}
+
+impl Default for Vsids {
+    fn default() -> Self {
+        Self::new(0.95)
+    }
+}
+
+impl DecisionHeuristic for Vsids {
+    fn pick(&mut self, _cnf: &Cnf, assignment: &Assignment, max: Var) -> Option<Var> {
+        // the first of equally active variables is kept
+        (1..=max)
+            .filter(|&var| assignment.get(var).is_none())
+            .fold(None, |best, var| match best {
+                Some(best) if self.activity(best) >= self.activity(var) => Some(best),
+                _ => Some(var),
+            })
+    }
+
+    fn on_conflict(&mut self, clause: &Clause) {
+        for (var, _) in clause.literals() {
+            let idx = var as usize;
+            if idx >= self.activities.len() {
+                self.activities.resize(idx + 1, 0.0);
+            }
+            self.activities[idx] += self.bump;
+            if self.activities[idx] > Self::RESCALE_LIMIT {
+                for activity in &mut self.activities {
+                    *activity /= Self::RESCALE_LIMIT;
+                }
+                self.bump /= Self::RESCALE_LIMIT;
+            }
+        }
+        self.bump /= self.decay;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::parse_cnf_from_str;
+
+    #[test]
+    fn test_numeric_order() {
+        let cnf = parse_cnf_from_str("1 2\n3 4").unwrap();
+        let max = cnf.highest_var();
+        let assignment = Assignment::new().with(1, true).with(2, false);
+        assert_eq!(NumericOrder.pick(&cnf, &assignment, max), Some(3));
+        let assignment = assignment.with(3, true);
+        assert_eq!(NumericOrder.pick(&cnf, &assignment, max), Some(4));
+        assert_eq!(
+            NumericOrder.pick(&cnf, &assignment.with(4, false), max),
+            None
+        );
+
+        // the highest variable is decided as well, not left to propagation
+        let cnf = parse_cnf_from_str("1 2\n-1 2").unwrap();
+        let max = cnf.highest_var();
+        assert_eq!(
+            NumericOrder.pick(&cnf, &Assignment::new_with(1, true), max),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_shuffled_order() {
+        let order = |seed| StaticOrder::shuffled(30, seed).0;
+        assert_eq!(order(5), order(5));
+        assert_ne!(order(5), order(6));
+
+        let mut sorted = order(5);
+        sorted.sort_unstable();
+        assert_eq!(sorted, (1..=30).collect::<Vec<_>>());
+
+        let mut heuristic = StaticOrder(vec![3, 1]);
+        let cnf = parse_cnf_from_str("1 2\n-3 4").unwrap();
+        let max = cnf.highest_var();
+        assert_eq!(heuristic.pick(&cnf, &Assignment::new(), max), Some(3));
+        let assignment = Assignment::new_with(3, false);
+        assert_eq!(heuristic.pick(&cnf, &assignment, max), Some(1));
+
+        // the variables not listed follow in numeric order
+        let assignment = assignment.with(1, true);
+        assert_eq!(heuristic.pick(&cnf, &assignment, max), Some(2));
+    }
+
+    #[test]
+    fn test_vsids() {
+        let cnf = parse_cnf_from_str("1 2\n-2 3 4\n-3 -4").unwrap();
+        let max = cnf.highest_var();
+        let mut vsids = Vsids::new(0.5);
+
+        // without conflicts like numeric order
+        assert_eq!(vsids.pick(&cnf, &Assignment::new(), max), Some(1));
+        let assignment = Assignment::new().with(1, true).with(2, true).with(3, false);
+        assert_eq!(vsids.pick(&cnf, &assignment, max), Some(4));
+
+        vsids.on_conflict(&cnf.clauses[2]);
+        assert_eq!(vsids.pick(&cnf, &Assignment::new(), max), Some(3));
+        // later conflicts weigh more, 2 is bumped by 2 and 3 by 1 + 2
+        vsids.on_conflict(&cnf.clauses[1]);
+        assert_eq!(vsids.activity(2), 2.0);
+        assert_eq!(vsids.activity(3), 3.0);
+        assert_eq!(vsids.pick(&cnf, &Assignment::new(), max), Some(3));
+        assert_eq!(
+            vsids.pick(&cnf, &Assignment::new_with(3, true), max),
+            Some(4)
+        );
+
+        // rescaling keeps the order
+        for _ in 0..400 {
+            vsids.on_conflict(&cnf.clauses[0]);
+        }
+        assert!(vsids.activity(1) <= Vsids::RESCALE_LIMIT);
+        assert!(vsids.activity(1) > vsids.activity(3));
+        assert_eq!(vsids.pick(&cnf, &Assignment::new(), max), Some(1));
+    }
+
+    #[test]
+    fn test_greedy() {
+        let cnf = parse_cnf_from_str("1 -2\n-2 3\n-2 -3 4\n2 4\n4 -5").unwrap();
+        let max = cnf.highest_var();
+        let mut greedy = Greedy::default();
+
+        // -2 occurs in three clauses, 4 in three as well, 2 is lower
+        assert_eq!(greedy.pick(&cnf, &Assignment::new(), max), Some(2));
+        assert_eq!(greedy.polarity(&cnf, &Assignment::new(), 2), Some(false));
+
+        // the clauses satisfied by -2 do not count anymore
+        let assignment = Assignment::new_with(2, false);
+        assert_eq!(greedy.pick(&cnf, &assignment, max), Some(4));
+        assert_eq!(greedy.polarity(&cnf, &assignment, 4), Some(true));
+
+        let assignment = assignment.with(4, true);
+        assert_eq!(greedy.pick(&cnf, &assignment, max), None);
+    }
+}
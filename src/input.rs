@@ -1,13 +1,86 @@
+use std::collections::HashMap;
+use std::fmt::Display;
 use std::io::Write;
 
-use crate::cnf::{Clause, Cnf};
+use crate::cnf::{Clause, Cnf, LiteralTpl, Var};
+
+/// An error encountered while parsing a formula
+///
+/// Line numbers start at 1.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// A token is not a valid literal
+    InvalidToken { line: usize, text: String },
+    /// The literal `0` inside a clause of a format without clause terminators
+    ZeroLiteral { line: usize },
+    /// A literal does not fit into a variable
+    Overflow { line: usize },
+    /// The `p cnf <vars> <clauses>` header is missing, malformed or repeated
+    InvalidHeader { line: usize },
+    /// The last clause is not terminated by `0`
+    UnterminatedClause { line: usize },
+    /// A literal's variable is higher than the number of variables declared by the header
+    UndeclaredVar { line: usize, var: Var },
+    /// The number of clauses differs from the one declared by the header
+    ClauseCount { declared: usize, found: usize },
+    /// A second weight declaration for the same variable, see [parse_weighted]
+    DuplicateWeight { line: usize, var: Var },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::InvalidToken { line, text } => {
+                write!(f, "line {}: invalid literal '{}'", line, text)
+            }
+            ParseError::ZeroLiteral { line } => write!(f, "line {}: 0 is not a literal", line),
+            ParseError::Overflow { line } => write!(f, "line {}: literal out of range", line),
+            ParseError::InvalidHeader { line } => {
+                write!(f, "line {}: expected header 'p cnf <vars> <clauses>'", line)
+            }
+            ParseError::UnterminatedClause { line } => {
+                write!(f, "line {}: clause is not terminated by 0", line)
+            }
+            ParseError::UndeclaredVar { line, var } => {
+                write!(
+                    f,
+                    "line {}: variable {} exceeds the declared count",
+                    line, var
+                )
+            }
+            ParseError::ClauseCount { declared, found } => write!(
+                f,
+                "header declares {} clauses, but {} were found",
+                declared, found
+            ),
+            ParseError::DuplicateWeight { line, var } => {
+                write!(
+                    f,
+                    "line {}: weight of variable {} is declared twice",
+                    line, var
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A literal given twice in the same clause, which is dropped while parsing
+///
+/// The duplicate is harmless for the formula, but often hints at a bug in the encoder
+/// which produced the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateLiteral {
+    /// The line of the second occurrence, starting at 1
+    pub line: usize,
+    pub lit: LiteralTpl,
+}
 
 pub fn read_cnf_interactive() -> Cnf {
     println!("New CNF formula:");
 
-    let mut phi = Cnf {
-        clauses: Vec::new(),
-    };
+    let mut phi = Cnf::new();
 
     'clausesLoop: for i in 1.. {
         print!("  Cls {:02}: ", i);
@@ -47,105 +120,592 @@ pub fn read_cnf_interactive() -> Cnf {
     phi
 }
 
+/// Parses a formula in the DIMACS CNF format
+///
+/// The input consists of a `p cnf <vars> <clauses>` header followed by clauses given as
+/// signed literals, each clause terminated by `0`. Clauses may span several lines and
+/// several clauses may share a line.
+///
+/// Tolerated:
+/// * comment lines starting with `c`, before and after the header
+/// * blank lines and any amount of whitespace between tokens
+/// * a line starting with `%` (as found in SATLIB files) ends the formula, everything after
+///   it is ignored, including the stray `0` usually following it
+/// * clauses containing a literal twice, the duplicate is dropped
+/// * tautological clauses (e.g. `1 -1 0`), they are dropped as they are always satisfied
+///
+/// Rejected:
+/// * clauses before the header, a second header or a header not of the form `p cnf <vars> <clauses>`
+/// * tokens that are not integers, or literals whose variable does not fit into [Var]
+/// * variables higher than the number declared by the header
+/// * a number of clauses other than the declared one, dropped tautologies count as well
+/// * a last clause which is not terminated by `0` before the end of the input (or the `%` line)
+pub fn parse_dimacs(input: &str) -> Result<Cnf, ParseError> {
+    parse_dimacs_with_num_vars(input).map(|(cnf, _)| cnf)
+}
+
+/// Like [parse_dimacs], but also returns the number of variables declared by the header
+///
+/// The declared count may exceed the highest variable occurring in the clauses, see
+/// [SolverConfig::num_vars](crate::satsolve::SolverConfig::num_vars) to get models
+/// covering all declared variables. A count which does not fit into [Var] is rejected
+/// as an invalid header.
+pub fn parse_dimacs_with_num_vars(input: &str) -> Result<(Cnf, Var), ParseError> {
+    parse_dimacs_impl(input, false, None, &mut Vec::new())
+}
+
+/// Like [parse_dimacs], but also returns the literals which were dropped as duplicates
+///
+/// The duplicates are listed in the order of the input, including those of dropped
+/// tautological clauses.
+pub fn parse_dimacs_reporting_duplicates(
+    input: &str,
+) -> Result<(Cnf, Vec<DuplicateLiteral>), ParseError> {
+    let mut duplicates = Vec::new();
+    let (cnf, _) = parse_dimacs_impl(input, false, None, &mut duplicates)?;
+    Ok((cnf, duplicates))
+}
+
+/// Parses the propositional matrix of a quantified formula in the QDIMACS format
+///
+/// The quantifier prefix, lines starting with `a` or `e` between the header and the first
+/// clause, is skipped and the rest is parsed like [parse_dimacs]. The quantified variables
+/// are still checked against the header like the literals of the clauses. The result is the
+/// matrix with all variables treated as existential, so its satisfiability is only
+/// necessary for the truth of the quantified formula. This is not a QBF solver.
+pub fn parse_qdimacs_matrix(input: &str) -> Result<Cnf, ParseError> {
+    parse_dimacs_impl(input, true, None, &mut Vec::new()).map(|(cnf, _)| cnf)
+}
+
+/// Like [parse_dimacs], but also accepts weight declarations `w <var> <weight>` and returns
+/// the declared weights
+///
+/// The non-standard `w` lines may appear anywhere after the header, the weight has to be
+/// a non-negative integer. The variable has to be declared by the header and may only get
+/// one weight. Variables without a declaration are missing from the map, it is up to the
+/// caller which weight they get.
+pub fn parse_weighted(input: &str) -> Result<(Cnf, HashMap<Var, u64>), ParseError> {
+    let mut weights = HashMap::new();
+    let (cnf, _) = parse_dimacs_impl(input, false, Some(&mut weights), &mut Vec::new())?;
+    Ok((cnf, weights))
+}
+
+/// Parses DIMACS, skipping a QDIMACS quantifier prefix if `skip_quantifiers` is set and
+/// collecting weight declarations if `weights` is given
+fn parse_dimacs_impl(
+    input: &str,
+    skip_quantifiers: bool,
+    mut weights: Option<&mut HashMap<Var, u64>>,
+    duplicates: &mut Vec<DuplicateLiteral>,
+) -> Result<(Cnf, Var), ParseError> {
+    let mut cnf = Cnf::new();
+    let mut num_vars = None;
+    let mut num_clauses = 0;
+    // the number of terminated clauses, including dropped tautologies
+    let mut clauses_read = 0;
+
+    let mut clause = Clause::new();
+    let mut tautology = false;
+    // line of the first literal of the current clause, if it has any
+    let mut clause_start = None;
+
+    for (line_idx, line) in input.lines().enumerate() {
+        let line_no = line_idx + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('c') {
+            continue;
+        }
+        if line.starts_with('%') {
+            break;
+        }
+        if line.starts_with('p') {
+            let fields = line.split_ascii_whitespace().collect::<Vec<_>>();
+            let declared = match fields.as_slice() {
+                ["p", "cnf", vars, clauses] => vars.parse::<Var>().ok().zip(clauses.parse().ok()),
+                _ => None,
+            };
+            match declared {
+                Some((vars, clauses)) if num_vars.is_none() => {
+                    num_vars = Some(vars);
+                    num_clauses = clauses;
+                }
+                _ => return Err(ParseError::InvalidHeader { line: line_no }),
+            }
+            continue;
+        }
+        if num_vars.is_none() {
+            return Err(ParseError::InvalidHeader { line: line_no });
+        }
+        let in_prefix = cnf.clauses.is_empty() && clause_start.is_none();
+        let quantifier = matches!(line.split_ascii_whitespace().next(), Some("a") | Some("e"));
+        if skip_quantifiers && in_prefix && quantifier {
+            let fields = line.split_ascii_whitespace().skip(1).collect::<Vec<_>>();
+            check_quantified_vars(&fields, line_no, num_vars.unwrap())?;
+            continue;
+        }
+        if let Some(weights) = &mut weights {
+            let mut fields = line.split_ascii_whitespace();
+            if fields.next() == Some("w") {
+                let (var, weight) = parse_weight(&fields.collect::<Vec<_>>(), line_no)?;
+                if Some(var) > num_vars {
+                    return Err(ParseError::UndeclaredVar { line: line_no, var });
+                }
+                if weights.insert(var, weight).is_some() {
+                    return Err(ParseError::DuplicateWeight { line: line_no, var });
+                }
+                continue;
+            }
+        }
+
+        for token in line.split_ascii_whitespace() {
+            let literal = token.parse::<i64>().map_err(|_| ParseError::InvalidToken {
+                line: line_no,
+                text: token.to_string(),
+            })?;
+
+            if literal == 0 {
+                let finished = std::mem::take(&mut clause);
+                if !std::mem::take(&mut tautology) {
+                    cnf.clauses.push(finished);
+                }
+                clauses_read += 1;
+                clause_start = None;
+                continue;
+            }
+
+            let var = literal.unsigned_abs();
+            if var > Var::MAX as u64 {
+                return Err(ParseError::Overflow { line: line_no });
+            }
+            let var = var as Var;
+            if Some(var) > num_vars {
+                return Err(ParseError::UndeclaredVar { line: line_no, var });
+            }
+            clause_start.get_or_insert(line_no);
+
+            match (clause.get(var), literal > 0) {
+                (Some(present), positive) if present != positive => tautology = true,
+                (Some(_), positive) => duplicates.push(DuplicateLiteral {
+                    line: line_no,
+                    lit: (var, positive),
+                }),
+                (_, true) => clause.add_positive(var),
+                (_, false) => clause.add_negative(var),
+            }
+        }
+    }
+
+    match (clause_start, num_vars) {
+        (Some(line), _) => Err(ParseError::UnterminatedClause { line }),
+        (None, None) => Err(ParseError::InvalidHeader { line: 1 }),
+        (None, Some(_)) if clauses_read != num_clauses => Err(ParseError::ClauseCount {
+            declared: num_clauses,
+            found: clauses_read,
+        }),
+        (None, Some(num_vars)) => Ok((cnf, num_vars)),
+    }
+}
+
+/// Checks the variables following the `a` or `e` of a quantifier line, the terminating `0`
+/// included
+fn check_quantified_vars(fields: &[&str], line_no: usize, num_vars: Var) -> Result<(), ParseError> {
+    for field in fields {
+        let var = field.parse::<u64>().map_err(|_| ParseError::InvalidToken {
+            line: line_no,
+            text: field.to_string(),
+        })?;
+        if var > Var::MAX as u64 {
+            return Err(ParseError::Overflow { line: line_no });
+        }
+        if var as Var > num_vars {
+            return Err(ParseError::UndeclaredVar {
+                line: line_no,
+                var: var as Var,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Parses the fields `<var> <weight>` following the `w` of a weight declaration
+fn parse_weight(fields: &[&str], line_no: usize) -> Result<(Var, u64), ParseError> {
+    let invalid = |text: &str| ParseError::InvalidToken {
+        line: line_no,
+        text: text.to_string(),
+    };
+
+    match fields {
+        [var, weight] => {
+            let var = match var.parse::<u64>() {
+                Ok(0) | Err(_) => return Err(invalid(var)),
+                Ok(var) if var > Var::MAX as u64 => {
+                    return Err(ParseError::Overflow { line: line_no })
+                }
+                Ok(var) => var as Var,
+            };
+            let weight = weight.parse::<u64>().map_err(|_| invalid(weight))?;
+            Ok((var, weight))
+        }
+        _ => Err(invalid(&fields.join(" "))),
+    }
+}
+
+/// Parses one clause per line, `false` standing for the empty clause
 #[cfg(test)]
-pub fn parse_cnf_from_str(input: &str) -> Option<Cnf> {
+pub fn parse_cnf_from_str(input: &str) -> Result<Cnf, ParseError> {
     let mut cnf = Cnf::new();
 
-    for line_clause in input
-        .lines()
-        .map(|line| line.trim())
-        .filter(|&line| !line.is_empty())
-    {
+    for (line_idx, line_clause) in input.lines().enumerate() {
+        let line = line_idx + 1;
+        let line_clause = line_clause.trim();
+        if line_clause.is_empty() {
+            continue;
+        }
         if line_clause == "false" {
             cnf.clauses.push(Clause::new());
             continue;
         }
 
-        let parts = line_clause
-            .split_ascii_whitespace()
-            .map(|it| it.trim())
-            .filter(|it| !it.is_empty())
-            .map(|part| part.parse::<i64>())
-            .collect::<Result<Vec<i64>, _>>()
-            .ok()?;
-
         let mut clause = Clause::new();
-        for part in parts {
-            if part < 0 {
-                clause.add_negative(-part as u32);
-            } else if part > 0 {
-                clause.add_positive(part as u32);
+        for token in line_clause.split_ascii_whitespace() {
+            let literal = token.parse::<i64>().map_err(|_| ParseError::InvalidToken {
+                line,
+                text: token.to_string(),
+            })?;
+
+            let var = literal.unsigned_abs();
+            if var == 0 {
+                return Err(ParseError::ZeroLiteral { line });
+            } else if var > Var::MAX as u64 {
+                return Err(ParseError::Overflow { line });
+            } else if literal < 0 {
+                clause.add_negative(var as Var);
             } else {
-                return None;
+                clause.add_positive(var as Var);
             }
         }
         cnf.clauses.push(clause);
     }
 
-    Some(cnf)
+    Ok(cnf)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::cnf::{Clause, Cnf};
+    use std::collections::HashMap;
 
-    use super::parse_cnf_from_str;
+    use crate::cnf::{Clause, Cnf, CnfBuilder};
+
+    use super::{
+        parse_cnf_from_str, parse_dimacs, parse_dimacs_reporting_duplicates,
+        parse_dimacs_with_num_vars, parse_qdimacs_matrix, parse_weighted, DuplicateLiteral,
+        ParseError,
+    };
 
     #[test]
     fn test_parse_empty_formula() {
-        assert_eq!(parse_cnf_from_str(""), Some(Cnf::new()));
-        assert_eq!(parse_cnf_from_str("    "), Some(Cnf::new()));
+        assert_eq!(parse_cnf_from_str(""), Ok(Cnf::new()));
+        assert_eq!(parse_cnf_from_str("    "), Ok(Cnf::new()));
     }
 
     #[test]
     fn test_parse_empty_clauses() {
         assert_eq!(
             parse_cnf_from_str("false\n"),
-            Some(Cnf::new_with(vec![Clause::new()]))
+            Ok(Cnf::new_with(vec![Clause::new()]))
         );
         assert_eq!(
             parse_cnf_from_str("1\nfalse\n2"),
-            Some(Cnf::new_with(vec![
-                {
-                    let mut cls = Clause::new();
-                    cls.add_positive(1);
-                    cls
-                },
-                Clause::new(),
-                {
-                    let mut cls = Clause::new();
-                    cls.add_positive(2);
-                    cls
-                }
-            ]))
+            CnfBuilder::new().clause([1]).clause([]).clause([2]).build()
         );
     }
 
     #[test]
     fn test_parse() {
-        let (mut cls0, mut cls1, mut cls2) = (Clause::new(), Clause::new(), Clause::new());
-        cls0.add_positive(1);
-        cls0.add_positive(2);
-        cls0.add_positive(4);
-        cls0.add_negative(3);
-        cls0.add_negative(5);
-        cls0.add_negative(6);
-
-        cls1.add_negative(7);
-        cls1.add_negative(8);
-        cls1.add_negative(9);
-
-        cls2.add_positive(10);
-        cls2.add_negative(11);
-        cls2.add_positive(12);
-
+        let cnf = CnfBuilder::new()
+            .clause([1, 2, -3, 4, -5, -6])
+            .clause([-7, -8, -9])
+            .clause([10, -11, 12])
+            .build();
         assert_eq!(
             parse_cnf_from_str(
                 "1 2 -3 4 -5 -6
         -7 -8 -9
         10 -11 12"
             ),
-            Some(Cnf::new_with(vec![cls0, cls1, cls2]))
+            cnf
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_cnf_from_str("1 2\n\n-3 x"),
+            Err(ParseError::InvalidToken {
+                line: 3,
+                text: "x".to_string()
+            })
+        );
+        assert_eq!(
+            parse_cnf_from_str("1 0 2"),
+            Err(ParseError::ZeroLiteral { line: 1 })
+        );
+        assert_eq!(
+            parse_cnf_from_str("false\n-4294967296"),
+            Err(ParseError::Overflow { line: 2 })
+        );
+        assert_eq!(
+            parse_cnf_from_str("1\n99999999999999999999"),
+            Err(ParseError::InvalidToken {
+                line: 2,
+                text: "99999999999999999999".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_dimacs() {
+        let input = "c example
+p cnf 12 3
+1 2 -3 4 -5 -6 0
+-7 -8
+-9 0 10 -11 12 0
+";
+        assert_eq!(
+            parse_dimacs(input),
+            Ok(parse_cnf_from_str("1 2 -3 4 -5 -6\n-7 -8 -9\n10 -11 12").unwrap())
+        );
+        assert_eq!(parse_dimacs("p cnf 0 0\n"), Ok(Cnf::new()));
+        assert_eq!(
+            parse_dimacs("p cnf 1 1\n0\n"),
+            Ok(Cnf::new_with(vec![Clause::new()]))
+        );
+    }
+
+    #[test]
+    fn test_parse_dimacs_reporting_duplicates() {
+        let (cnf, duplicates) =
+            parse_dimacs_reporting_duplicates("p cnf 2 2\n1 -2 1\n0\n2 2 0\n").unwrap();
+        assert_eq!(cnf, parse_dimacs("p cnf 2 2\n1 -2 0\n2 0\n").unwrap());
+        assert_eq!(
+            duplicates,
+            vec![
+                DuplicateLiteral {
+                    line: 2,
+                    lit: (1, true)
+                },
+                DuplicateLiteral {
+                    line: 4,
+                    lit: (2, true)
+                },
+            ]
+        );
+        assert_eq!(
+            parse_dimacs_reporting_duplicates("p cnf 2 1\n1 2 0\n"),
+            Ok((parse_cnf_from_str("1 2").unwrap(), vec![]))
+        );
+    }
+
+    #[test]
+    fn test_parse_qdimacs_matrix() {
+        let input = "c quantified\np cnf 4 2\na 1 2 0\ne 3 4 0\n1 -3 0\n-2 4 0\n";
+        assert_eq!(
+            parse_qdimacs_matrix(input),
+            Ok(parse_cnf_from_str("1 -3\n-2 4").unwrap())
+        );
+        assert_eq!(
+            parse_dimacs(input),
+            Err(ParseError::InvalidToken {
+                line: 3,
+                text: "a".to_string()
+            })
+        );
+        // the prefix has to precede the clauses
+        assert_eq!(
+            parse_qdimacs_matrix("p cnf 2 1\n1 0\ne 2 0\n"),
+            Err(ParseError::InvalidToken {
+                line: 3,
+                text: "e".to_string()
+            })
+        );
+        assert_eq!(
+            parse_qdimacs_matrix("a 1 0\np cnf 1 0\n"),
+            Err(ParseError::InvalidHeader { line: 1 })
+        );
+
+        // the quantified variables have to be declared like the others
+        assert_eq!(
+            parse_qdimacs_matrix("p cnf 3 1\na 4 0\n1 0\n"),
+            Err(ParseError::UndeclaredVar { line: 2, var: 4 })
+        );
+        assert_eq!(
+            parse_qdimacs_matrix("p cnf 3 1\ne 1 -2 0\n1 0\n"),
+            Err(ParseError::InvalidToken {
+                line: 2,
+                text: "-2".to_string()
+            })
+        );
+        assert_eq!(
+            parse_qdimacs_matrix("p cnf 3 1\ne 1 99999999999 0\n1 0\n"),
+            Err(ParseError::Overflow { line: 2 })
+        );
+    }
+
+    #[test]
+    fn test_parse_weighted() {
+        let input = "p cnf 3 2\nw 1 5\n1 -2 0\nw 3 0\n2 3 0\n";
+        let (cnf, weights) = parse_weighted(input).unwrap();
+        assert_eq!(cnf, parse_cnf_from_str("1 -2\n2 3").unwrap());
+        assert_eq!(weights, vec![(1, 5), (3, 0)].into_iter().collect());
+        assert_eq!(
+            parse_weighted("p cnf 1 1\n1 0\n").unwrap().1,
+            HashMap::new()
+        );
+
+        // plain DIMACS rejects the declarations
+        assert_eq!(
+            parse_dimacs(input),
+            Err(ParseError::InvalidToken {
+                line: 2,
+                text: "w".to_string()
+            })
+        );
+
+        let error = |input| parse_weighted(input).unwrap_err();
+        assert_eq!(
+            error("p cnf 2 1\nw 3 5\n1 0\n"),
+            ParseError::UndeclaredVar { line: 2, var: 3 }
+        );
+        assert_eq!(
+            error("p cnf 3 1\nw 1 5\n1 0\nw 1 7\n"),
+            ParseError::DuplicateWeight { line: 4, var: 1 }
+        );
+        assert_eq!(
+            error("p cnf 1 0\nw 1 -3\n"),
+            ParseError::InvalidToken {
+                line: 2,
+                text: "-3".to_string()
+            }
+        );
+        assert_eq!(
+            error("p cnf 1 0\nw 0 3\n"),
+            ParseError::InvalidToken {
+                line: 2,
+                text: "0".to_string()
+            }
+        );
+        assert_eq!(
+            error("p cnf 1 0\nw 1\n"),
+            ParseError::InvalidToken {
+                line: 2,
+                text: "1".to_string()
+            }
+        );
+        assert_eq!(
+            error("p cnf 1 0\nw 4294967296 1\n"),
+            ParseError::Overflow { line: 2 }
+        );
+    }
+
+    #[test]
+    fn test_parse_dimacs_num_vars() {
+        let (cnf, num_vars) = parse_dimacs_with_num_vars("p cnf 5 1\n1 -2 0\n").unwrap();
+        assert_eq!(cnf, parse_cnf_from_str("1 -2").unwrap());
+        assert_eq!(num_vars, 5);
+        assert_eq!(
+            parse_dimacs_with_num_vars("p cnf 99999999999 0\n"),
+            Err(ParseError::InvalidHeader { line: 1 })
+        );
+    }
+
+    #[test]
+    fn test_parse_dimacs_tolerated_trailer() {
+        let input = "c SATLIB style
+p cnf 3 2
+
+  1 -2   0
+c in between
+2 3 0
+%
+0
+
+";
+        assert_eq!(
+            parse_dimacs(input),
+            Ok(parse_cnf_from_str("1 -2\n2 3").unwrap())
+        );
+
+        // everything after '%' is ignored
+        assert_eq!(
+            parse_dimacs("p cnf 1 1\n1 0\n%\nnot a clause\n"),
+            Ok(parse_cnf_from_str("1").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_dimacs_tautology() {
+        assert_eq!(
+            parse_dimacs("p cnf 2 2\n1 -1 2 0\n2 2 0\n"),
+            Ok(parse_cnf_from_str("2").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_dimacs_errors() {
+        assert_eq!(
+            parse_dimacs("1 2 0\n"),
+            Err(ParseError::InvalidHeader { line: 1 })
+        );
+        assert_eq!(parse_dimacs(""), Err(ParseError::InvalidHeader { line: 1 }));
+        assert_eq!(
+            parse_dimacs("p cnf 2\n1 2 0\n"),
+            Err(ParseError::InvalidHeader { line: 1 })
+        );
+        assert_eq!(
+            parse_dimacs("p cnf 2 1\np cnf 2 1\n1 2 0\n"),
+            Err(ParseError::InvalidHeader { line: 2 })
+        );
+        assert_eq!(
+            parse_dimacs("p cnf 2 1\n1 x 0\n"),
+            Err(ParseError::InvalidToken {
+                line: 2,
+                text: "x".to_string()
+            })
+        );
+        assert_eq!(
+            parse_dimacs("p cnf 2 1\n1 99999999999 0\n"),
+            Err(ParseError::Overflow { line: 2 })
+        );
+        assert_eq!(
+            parse_dimacs("p cnf 2 2\n1 0\n\n-1\n2\n%\n0\n"),
+            Err(ParseError::UnterminatedClause { line: 4 })
+        );
+    }
+
+    #[test]
+    fn test_parse_dimacs_header_counts() {
+        assert_eq!(
+            parse_dimacs("p cnf 2 2\n1 -2 0\n-3 0\n"),
+            Err(ParseError::UndeclaredVar { line: 3, var: 3 })
+        );
+        assert_eq!(
+            parse_dimacs("p cnf 2 3\n1 -2 0 2 0\n"),
+            Err(ParseError::ClauseCount {
+                declared: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            parse_dimacs("p cnf 2 1\n1 0\n-1 2 0\n"),
+            Err(ParseError::ClauseCount {
+                declared: 1,
+                found: 2
+            })
+        );
+        assert_eq!(
+            parse_dimacs("p cnf 2 -1\n"),
+            Err(ParseError::InvalidHeader { line: 1 })
         );
     }
 }
@@ -1,5 +1,46 @@
+/// Checks an invariant like [assert!], unless the `unchecked` feature is enabled
+///
+/// The condition is still type checked with the feature, it is just never evaluated.
+macro_rules! invariant {
+    ($($arg:tt)*) => {
+        if cfg!(not(feature = "unchecked")) {
+            assert!($($arg)*);
+        }
+    };
+}
+
+/// Prints like [print!], but only if the `verbose` feature is enabled
+///
+/// The arguments are still type checked without the feature, they are just never formatted.
+macro_rules! verbose_print {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "verbose") {
+            print!($($arg)*);
+        }
+    };
+}
+
+/// Prints like [println!], but only if the `verbose` feature is enabled
+macro_rules! verbose_println {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "verbose") {
+            println!($($arg)*);
+        }
+    };
+}
+
+pub mod aiger;
 pub mod assignment;
+pub mod cardinality;
 pub mod cnf;
-mod input;
+pub mod expr;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzzing;
+pub mod gates;
+pub mod heuristic;
+pub mod input;
+pub mod maxsat;
+pub mod rng;
 pub mod satsolve;
+mod gauss;
 mod watchedliterals;
\ No newline at end of file
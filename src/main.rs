@@ -1,8 +1,4 @@
-mod assignment;
-mod cnf;
-mod input;
-mod satsolve;
-mod watchedliterals;
+use satsolver::{input, satsolve};
 
 fn main() {
     println!(" S A T ");
@@ -20,11 +16,12 @@ fn main() {
         "not satisfiable"
     };
     println!("phi = {:?}", phi);
-    let combinations = 2f64.powi(phi.highest_var() as i32);
+    let combinations = 2f64.powi(phi.num_vars() as i32);
     println!(
         "is {}, took {} evaluations ({:02.1}% of all combinations)",
         sat_str,
         stats.tries,
         stats.tries as f64 * 100f64 / combinations as f64
     );
+    println!("{}", stats);
 }
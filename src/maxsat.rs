@@ -0,0 +1,238 @@
+use crate::{
+    assignment::Assignment,
+    cardinality,
+    cnf::{Clause, Cnf, Var},
+    satsolve::{SolveResult, Solver},
+};
+
+/// How the optimal number of falsified soft clauses is searched for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaxSatStrategy {
+    /// Tries the bounds 0, 1, 2, ... until the first satisfiable one
+    LinearSearchUnsat,
+    /// Bisects the bound between 0 and the cost of the first model found
+    BinarySearch,
+    /// Starts with any model and requires a strictly better one until this is unsatisfiable
+    #[default]
+    LinearSearchSat,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MaxSatConfig {
+    pub strategy: MaxSatStrategy,
+}
+
+/// Statistics about the optimization process
+#[derive(Debug, Default)]
+pub struct MaxSatStats {
+    /// Number of calls to the SAT solver which found a model
+    pub sat_calls: usize,
+    /// Number of calls to the SAT solver which proved the bound unsatisfiable
+    pub unsat_calls: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MaxSatResult {
+    /// The hard clauses are satisfiable, `model` falsifies exactly `cost` soft clauses
+    /// and no assignment falsifies fewer
+    Optimum { model: Assignment, cost: usize },
+    /// The hard clauses are unsatisfiable
+    Unsat,
+}
+
+/// Solves an unweighted partial MaxSAT instance
+///
+/// Finds an assignment satisfying all `hard` clauses which falsifies as few `soft` clauses
+/// as possible.
+///
+/// Each soft clause gets relaxed by a fresh variable and the number of true relaxation
+/// variables is counted by a [cardinality::counter]. The formula is handed to a single
+/// [Solver] once, the cost bounds of the search are passed as assumptions on the counter.
+pub fn solve_maxsat(
+    hard: &Cnf,
+    soft: &[Clause],
+    config: &MaxSatConfig,
+) -> (MaxSatResult, MaxSatStats) {
+    let mut stats = MaxSatStats::default();
+
+    let highest_var = soft
+        .iter()
+        .flat_map(|cls| cls.literals())
+        .map(|(var, _)| var)
+        .fold(hard.highest_var(), Var::max);
+    let mut next_fresh = highest_var + 1;
+
+    let mut cnf = hard.clone();
+    let mut relaxation_vars = Vec::with_capacity(soft.len());
+    for soft_clause in soft {
+        let relax = next_fresh;
+        next_fresh += 1;
+
+        let mut relaxed = soft_clause.clone();
+        relaxed.add_positive(relax);
+        cnf.clauses.push(relaxed);
+        relaxation_vars.push(relax);
+    }
+
+    let (counter_clauses, outputs) = cardinality::counter(&relaxation_vars, &mut next_fresh);
+    cnf.clauses.extend(counter_clauses);
+
+    let mut oracle = Oracle {
+        solver: Solver::new(cnf),
+        relaxation_vars,
+        outputs,
+        stats: &mut stats,
+    };
+
+    let optimum = match config.strategy {
+        MaxSatStrategy::LinearSearchUnsat => oracle.linear_search_unsat(),
+        MaxSatStrategy::BinarySearch => oracle.binary_search(),
+        MaxSatStrategy::LinearSearchSat => oracle.linear_search_sat(),
+    };
+
+    let result = match optimum {
+        Some((model, cost)) => {
+            // strip relaxation and counter variables
+            let model =
+                Assignment::new().with_all(model.iter().filter(|&(var, _)| var <= highest_var));
+            MaxSatResult::Optimum { model, cost }
+        }
+        None => MaxSatResult::Unsat,
+    };
+
+    (result, stats)
+}
+
+/// The relaxed formula together with the counter over its relaxation variables
+struct Oracle<'a> {
+    solver: Solver,
+    relaxation_vars: Vec<Var>,
+    outputs: Vec<Var>,
+    stats: &'a mut MaxSatStats,
+}
+
+impl Oracle<'_> {
+    /// Searches a model with at most `bound` true relaxation variables
+    ///
+    /// Returns the model and its number of true relaxation variables
+    fn solve_with_bound(&mut self, bound: usize) -> Option<(Assignment, usize)> {
+        let assumptions = match self.outputs.get(bound) {
+            Some(&output) => vec![(output, false)],
+            None => vec![], // bound is not restricting
+        };
+
+        match self.solver.solve_under_assumptions(&assumptions) {
+            SolveResult::Sat(model) => {
+                self.stats.sat_calls += 1;
+                let cost = self
+                    .relaxation_vars
+                    .iter()
+                    .filter(|&&var| model.get(var) == Some(true))
+                    .count();
+                Some((model, cost))
+            }
+            SolveResult::Unsat => {
+                self.stats.unsat_calls += 1;
+                None
+            }
+            SolveResult::Unknown => {
+                unreachable!("a solver without a limit always decides the formula")
+            }
+        }
+    }
+
+    fn linear_search_unsat(&mut self) -> Option<(Assignment, usize)> {
+        (0..=self.relaxation_vars.len()).find_map(|bound| self.solve_with_bound(bound))
+    }
+
+    fn linear_search_sat(&mut self) -> Option<(Assignment, usize)> {
+        let mut best = self.solve_with_bound(self.relaxation_vars.len())?;
+
+        while best.1 > 0 {
+            match self.solve_with_bound(best.1 - 1) {
+                Some(better) => best = better,
+                None => break,
+            }
+        }
+
+        Some(best)
+    }
+
+    fn binary_search(&mut self) -> Option<(Assignment, usize)> {
+        let mut best = self.solve_with_bound(self.relaxation_vars.len())?;
+
+        // every bound below lower is unsatisfiable, best satisfies best.1
+        let mut lower = 0;
+        while lower < best.1 {
+            let mid = (lower + best.1 - 1) / 2;
+            match self.solve_with_bound(mid) {
+                Some(better) => best = better,
+                None => lower = mid + 1,
+            }
+        }
+
+        Some(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::parse_cnf_from_str;
+
+    const STRATEGIES: [MaxSatStrategy; 3] = [
+        MaxSatStrategy::LinearSearchUnsat,
+        MaxSatStrategy::BinarySearch,
+        MaxSatStrategy::LinearSearchSat,
+    ];
+
+    fn solve(hard: &str, soft: &str, strategy: MaxSatStrategy) -> (MaxSatResult, MaxSatStats) {
+        let hard = parse_cnf_from_str(hard).unwrap();
+        let soft = parse_cnf_from_str(soft).unwrap().clauses;
+        solve_maxsat(&hard, &soft, &MaxSatConfig { strategy })
+    }
+
+    #[test]
+    fn test_maxsat_optimum() {
+        for &strategy in &STRATEGIES {
+            let (result, stats) = solve("1 2\n-3 -4", "-1\n-2\n3\n4\n5", strategy);
+            match result {
+                MaxSatResult::Optimum { model, cost } => {
+                    assert_eq!(cost, 2, "{:?}", strategy);
+                    assert!(parse_cnf_from_str("1 2\n-3 -4")
+                        .unwrap()
+                        .is_satisfied(&model));
+                    assert!(model.iter().all(|(var, _)| var <= 5));
+                }
+                MaxSatResult::Unsat => panic!("{:?} found no model", strategy),
+            }
+            assert!(stats.sat_calls >= 1);
+        }
+    }
+
+    #[test]
+    fn test_maxsat_all_soft_satisfiable() {
+        for &strategy in &STRATEGIES {
+            let (result, _) = solve("1 2", "1\n-2", strategy);
+            assert!(matches!(result, MaxSatResult::Optimum { cost: 0, .. }));
+        }
+    }
+
+    #[test]
+    fn test_maxsat_hard_unsat() {
+        for &strategy in &STRATEGIES {
+            let (result, stats) = solve("1\n-1 2\n-2", "3", strategy);
+            assert_eq!(result, MaxSatResult::Unsat);
+            assert_eq!(stats.sat_calls, 0);
+            assert!(stats.unsat_calls >= 1);
+        }
+    }
+
+    #[test]
+    fn test_maxsat_oracle_calls() {
+        let (_, stats) = solve("", "1\n-1\n2\n-2", MaxSatStrategy::LinearSearchUnsat);
+        // bounds 0 and 1 fail, 2 is optimal
+        assert_eq!(stats.unsat_calls, 2);
+        assert_eq!(stats.sat_calls, 1);
+    }
+}
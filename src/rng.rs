@@ -0,0 +1,129 @@
+use std::ops::RangeInclusive;
+
+use crate::cnf::{Clause, Cnf, Var};
+
+/// A small seedable pseudo random number generator (SplitMix64)
+///
+/// Not suited for cryptography, but fast and reproducible: the same seed always yields
+/// the same sequence of numbers on every platform.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..bound`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bound` is 0
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "Cannot pick a number below 0");
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+
+    /// Shuffles the given slice uniformly (Fisher-Yates)
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// A random formula with `num_clauses` clauses over the variables `1..=num_vars`
+///
+/// The length of every clause is drawn uniformly from `len`. Its variables are distinct and
+/// each of them is negated with a chance of one half.
+///
+/// # Panics
+///
+/// Panics if `len` allows more literals than there are variables
+pub fn random_cnf(
+    rng: &mut Rng,
+    num_vars: Var,
+    num_clauses: usize,
+    len: RangeInclusive<usize>,
+) -> Cnf {
+    assert!(
+        *len.end() <= num_vars as usize,
+        "Cannot pick {} distinct variables out of {}",
+        len.end(),
+        num_vars
+    );
+    let mut vars = (1..=num_vars).collect::<Vec<_>>();
+    let clauses = (0..num_clauses)
+        .map(|_| {
+            rng.shuffle(&mut vars);
+            let num_literals =
+                len.start() + rng.below((len.end() - len.start()) as u64 + 1) as usize;
+            let literals = vars[..num_literals]
+                .iter()
+                .map(|&var| (var, rng.below(2) == 0))
+                .collect::<Vec<_>>();
+            Clause::from_literals(&literals)
+        })
+        .collect();
+    Cnf::new_with(clauses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng_reproducible() {
+        let (mut rng0, mut rng1) = (Rng::new(42), Rng::new(42));
+        for _ in 0..100 {
+            assert_eq!(rng0.next_u64(), rng1.next_u64());
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn test_rng_below() {
+        let mut rng = Rng::new(7);
+        assert!((0..1000).all(|_| rng.below(10) < 10));
+        assert_eq!(rng.below(1), 0);
+    }
+
+    #[test]
+    fn test_rng_shuffle() {
+        let mut items = (0..50).collect::<Vec<_>>();
+        Rng::new(3).shuffle(&mut items);
+        assert_ne!(items, (0..50).collect::<Vec<_>>());
+
+        items.sort_unstable();
+        assert_eq!(items, (0..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_random_cnf() {
+        let cnf = random_cnf(&mut Rng::new(5), 6, 100, 2..=4);
+        assert_eq!(cnf.clauses.len(), 100);
+        assert!(cnf
+            .clauses
+            .iter()
+            .all(|clause| (2..=4).contains(&clause.len())));
+        assert!(cnf.vars().all(|var| (1..=6).contains(&var)));
+        assert!(cnf.clauses.iter().all(|clause| !clause.is_tautology()));
+        assert_eq!(cnf, random_cnf(&mut Rng::new(5), 6, 100, 2..=4));
+
+        assert_eq!(
+            random_cnf(&mut Rng::new(5), 0, 2, 0..=0).clauses,
+            [Clause::new(), Clause::new()]
+        );
+    }
+}
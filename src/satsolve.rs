@@ -1,471 +1,4370 @@
-use std::collections::VecDeque;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use crate::{
     assignment::Assignment,
-    cnf::{Cnf, LiteralTpl, Var},
+    cnf::{negate, Clause, Cnf, LiteralTpl, Satisfiability, Var},
+    gates::eliminate_gates,
+    gauss::{self, Elimination},
+    heuristic::{DecisionHeuristic, Greedy, NumericOrder, StaticOrder, Vsids},
     watchedliterals::{UpdateResult, WatchedLiterals},
 };
 
-
+/// A level of the search, opened by deciding a variable, see [Solver::decision_levels]
 #[derive(Debug, PartialEq)]
-struct DecisionLevel {
+pub struct DecisionLevel {
     assignment: Assignment,
     changed_var: Var,
-    next_var_at_least: Var,
     flipped: bool,
 }
 
+impl DecisionLevel {
+    /// The decided variable
+    pub fn changed_var(&self) -> Var {
+        self.changed_var
+    }
+
+    /// The decided literal, with its flipped value if [flipped](DecisionLevel::flipped)
+    pub fn decision(&self) -> LiteralTpl {
+        let val = self
+            .assignment
+            .get(self.changed_var)
+            .expect("Decision is assigned");
+        (self.changed_var, val)
+    }
+
+    /// Whether the first value of the decision led to a conflict and was flipped, so both
+    /// values have been tried
+    pub fn flipped(&self) -> bool {
+        self.flipped
+    }
+
+    /// The assignment at this level: the decision, everything assigned at the levels below
+    /// and all propagations
+    pub fn assignment(&self) -> &Assignment {
+        &self.assignment
+    }
+}
+
+/// Describes why propagation failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// Index of the clause which has become unsatisfiable
+    pub clause_idx: usize,
+    /// The assigned literal which falsified the last literal of the clause, if known
+    pub literal: Option<LiteralTpl>,
+}
+
+/// A step of the search, recorded if [SolverConfig::trace] is enabled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveEvent {
+    /// The literal was fixed by an assumption before the search
+    Assumption(LiteralTpl),
+    /// A new decision level was opened by deciding the literal
+    Decision(LiteralTpl),
+    /// The literal was implied by the clause with the index `reason_clause`, which is a
+    /// unit clause or has become unit
+    Propagation {
+        lit: LiteralTpl,
+        reason_clause: usize,
+    },
+    /// The clause with the given index has become unsatisfiable
+    Conflict { clause_idx: usize },
+    /// All decision levels above `to_level` were undone and the decision of level
+    /// `to_level` was flipped, `from_level` is the level the conflict occurred at
+    ///
+    /// With [SolverConfig::learn_first_uip], no decision is flipped. The learned clause
+    /// propagates at `to_level` instead.
+    Backtrack { from_level: usize, to_level: usize },
+    /// All decisions were undone to start the search over
+    ///
+    /// The solver does not restart yet, so this event is never recorded.
+    Restart,
+}
+
 /// Statistics about the solving process
+#[derive(Debug, Clone, Default)]
 pub struct Stats {
+    /// The number of times an assignment was checked for being a model
     pub tries: usize,
+    /// The number of decisions, not counting the flips of decisions by backtracks
+    pub decisions: usize,
+    /// The number of literals implied by a clause, including the unit clauses
+    pub propagations: usize,
+    /// The number of propagations which ran into an unsatisfiable clause
+    pub conflicts: usize,
+    /// The number of restarts, the solver does not restart yet, so this is always zero
+    pub restarts: usize,
+    /// The highest number of decision levels the search had at once
+    pub max_decision_level: usize,
+    /// The number of variables a model found by [Solver] leaves unassigned, counting
+    /// the variables up to [SolverConfig::num_vars] or else the highest variable
+    ///
+    /// These variables can take either value, so the formula has at least
+    /// `2^free_vars` models. Zero if no model was found.
+    pub free_vars: usize,
+    /// The number of times a decision was flipped after a conflict
+    pub backtracks: usize,
+    /// The number of decision levels discarded by all backtracks
+    ///
+    /// The level whose decision is flipped is not counted, so a backtrack which only flips
+    /// the latest decision adds nothing.
+    pub undone_levels: usize,
+    /// How unsatisfiability was concluded, `None` if no search found the formula
+    /// unsatisfiable
+    pub unsat_reason: Option<UnsatReason>,
+    /// The number of literals looked up while propagating, see [Solver::solve_bounded]
+    ///
+    /// Unlike the time taken, this does not depend on the machine, so it allows comparing
+    /// the effort of different heuristics or versions of the solver.
+    pub literal_accesses: u64,
+    pub preprocess: PreprocessStats,
+    /// The thread of [solve_portfolio] whose search decided the formula, the other stats
+    /// are the ones of its search
+    pub winner: Option<usize>,
 }
 
-pub fn is_satisfiable(cnf: &Cnf) -> (bool, Stats) {
-    const FIRST_TRY: bool = false;
+impl Stats {
+    /// Adds the counters of a search of another part of the formula
+    fn merge(&mut self, other: &Stats) {
+        self.tries += other.tries;
+        self.decisions += other.decisions;
+        self.propagations += other.propagations;
+        self.conflicts += other.conflicts;
+        self.restarts += other.restarts;
+        self.max_decision_level = self.max_decision_level.max(other.max_decision_level);
+        self.backtracks += other.backtracks;
+        self.undone_levels += other.undone_levels;
+        self.unsat_reason = other.unsat_reason.or(self.unsat_reason);
+        self.literal_accesses += other.literal_accesses;
+        self.preprocess.pure_literals += other.preprocess.pure_literals;
+        self.preprocess.units += other.preprocess.units;
+        self.preprocess.subsumed_clauses += other.preprocess.subsumed_clauses;
+        self.preprocess.eliminated_vars += other.preprocess.eliminated_vars;
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} decisions, {} propagations, {} conflicts, {} restarts, max decision level {}",
+            self.decisions,
+            self.propagations,
+            self.conflicts,
+            self.restarts,
+            self.max_decision_level
+        )
+    }
+}
+
+/// The way a search concluded that the formula is unsatisfiable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsatReason {
+    /// A clause was falsified before any decision and without assumptions, so the empty
+    /// clause follows by unit resolution alone. This proves the formula unsatisfiable.
+    EmptyClause,
+    /// A clause was falsified before any decision, but possibly only because of the
+    /// assumptions. The formula may be satisfiable without them.
+    Assumptions,
+    /// Every decision was tried with both values and each led to a conflict
+    ///
+    /// The formula is unsatisfiable as well, but the proof is the whole search tree. A
+    /// search with [SolverConfig::learn_first_uip] keeps learning until a clause is falsified
+    /// before the first decision, so it reports one of the other reasons instead.
+    SearchExhausted,
+}
+
+/// Statistics about the simplifications before the first decision
+///
+/// Only [is_satisfiable] removes subsumed clauses, pure literals and gate outputs, a
+/// [Solver] just propagates the units.
+#[derive(Debug, Clone, Default)]
+pub struct PreprocessStats {
+    /// Variables assigned because they occur with a single polarity only
+    pub pure_literals: usize,
+    /// Literals of unit clauses and the literals propagated from them before any decision,
+    /// not counting assumptions
+    pub units: usize,
+    /// Clauses removed because another clause subsumes them
+    pub subsumed_clauses: usize,
+    /// Gate outputs eliminated from the formula by resolution, see [eliminate_gates]
+    pub eliminated_vars: usize,
+}
 
-    let mut stats = Stats { tries: 0 };
+/// The outcome of solving a formula
+#[derive(Debug, PartialEq)]
+pub enum SolveResult {
+    /// The formula is satisfiable, the assignment satisfies it
+    Sat(Assignment),
+    /// The formula is unsatisfiable
+    Unsat,
+    /// The search was interrupted before it could decide the formula, e.g. by
+    /// [solve_with_timeout]
+    Unknown,
+}
 
-    // fast checks
-    if cnf.clauses.is_empty() {
-        return (true, stats);
+impl SolveResult {
+    pub fn is_sat(&self) -> bool {
+        matches!(self, SolveResult::Sat(_))
     }
-    if cnf.clauses.iter().any(|cls| cls.is_empty()) {
-        return (false, stats);
+}
+
+/// The value a variable is tried with first when it is decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Polarity {
+    #[default]
+    False,
+    True,
+    /// The polarity the variable occurs with in more clauses, or in fewer clauses if
+    /// `prefer_frequent` is false. Ties are broken towards false.
+    OccurrenceBased {
+        prefer_frequent: bool,
+    },
+}
+
+/// The order in which the literals waiting for propagation are handled
+///
+/// The order decides which conflict is found first if there are several, and therefore
+/// which clause is learned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PropagationOrder {
+    /// The literal queued first is handled first
+    #[default]
+    Fifo,
+    /// The literal queued last is handled first
+    Lifo,
+    /// The literal implied by the clause with the lowest index is handled first
+    ClauseIndex,
+}
+
+/// The [DecisionHeuristic] choosing the variables to decide
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Heuristic {
+    /// [NumericOrder], or a shuffled order if [SolverConfig::shuffle_seed] is set
+    #[default]
+    Numeric,
+    /// [StaticOrder] with the given variables first
+    Static(Vec<Var>),
+    /// [Greedy], ignoring [SolverConfig::polarity]
+    Greedy,
+    /// [Vsids] with the given decay factor, focusing on the variables of recent conflicts
+    Vsids(f64),
+}
+
+/// Options influencing the search of the solver
+#[derive(Debug, Clone)]
+pub struct SolverConfig {
+    /// The value decided variables are tried with first
+    pub polarity: Polarity,
+
+    /// Values to try first for specific variables, taking precedence over `polarity`
+    /// and the polarity chosen by the heuristic
+    pub initial_phases: HashMap<Var, bool>,
+
+    /// Picks the variables to decide
+    pub heuristic: Heuristic,
+
+    /// Record every step of the search, see [Solver::trace]
+    pub trace: bool,
+
+    /// Complete the models of [Solver] to assign every variable from 1 to this one
+    ///
+    /// Without it, variables which do not occur in the formula or are not needed to satisfy
+    /// it are missing from the model. Such free variables get the value `polarity` would try
+    /// first, which is false for [Polarity::OccurrenceBased] as they do not occur. The count
+    /// declared by a DIMACS header is available through
+    /// [parse_dimacs_with_num_vars](crate::input::parse_dimacs_with_num_vars).
+    pub num_vars: Option<Var>,
+
+    /// Branch on the variables in an order shuffled with this seed instead of their numeric order
+    ///
+    /// The same seed always yields the same order, so differently seeded solvers explore
+    /// the search space differently while each of them stays reproducible.
+    pub shuffle_seed: Option<u64>,
+
+    /// Keep at most this many learned clauses in a [Solver], deleting the least active ones
+    ///
+    /// The limit is enforced before every search. Derived clauses are never deleted.
+    /// Without a limit, all learned clauses are kept.
+    pub max_learned_clauses: Option<usize>,
+
+    /// The factor the activities of the learned clauses decay by on every conflict
+    ///
+    /// A learned clause gains activity whenever it is the clause found unsatisfiable in a
+    /// conflict, so the clauses which took no part in recent conflicts are deleted first.
+    /// The factor has to be in `(0, 1]`, a value near 1 remembers conflicts for longer.
+    /// Defaults to 0.999.
+    pub clause_decay: f64,
+
+    /// The number of reductions to [max_learned_clauses](SolverConfig::max_learned_clauses)
+    /// after which the clause database is compacted
+    ///
+    /// A reduction only stops watching the deleted clauses. A compaction removes them from
+    /// the formula of the solver and renumbers the remaining clauses, which takes a pass
+    /// over all watches. 0 never compacts. Defaults to 1, compacting after every reduction.
+    pub compaction_interval: usize,
+
+    /// The order in which implied literals are propagated
+    pub propagation_order: PropagationOrder,
+
+    /// Do not watch the clauses satisfied by the unit clauses of the formula
+    ///
+    /// The unit clauses hold in every search, so such clauses can never become unit or
+    /// unsatisfiable and propagation does not need to look at them. This pays off on formulas
+    /// with many unit clauses. A [Solver] watches the skipped clauses again when one of the
+    /// unit clauses is removed.
+    pub skip_satisfied_clauses: bool,
+
+    /// Learn the clauses of the conflicts from the implication graph instead of the decisions
+    ///
+    /// The conflict clause is resolved with the clauses which implied its literals until
+    /// only one literal of the conflict level is left, the first unique implication point.
+    /// Such a clause is usually much shorter than the negated decisions and is watched
+    /// right away, so it already prunes the rest of the search. Instead of flipping the last
+    /// decision, the search jumps back to the highest level at which the clause implies
+    /// the negation of that literal. Without this option, the clauses learned from the
+    /// decisions are only added once the search is finished.
+    pub learn_first_uip: bool,
+
+    /// Decide variables with the value they were last assigned, if they were assigned before
+    ///
+    /// Backtracking unassigns the variables of many levels which did not take part in the
+    /// conflict. Saving their phases retries the values which satisfied their clauses
+    /// before. The initial phase of a variable is still chosen by `initial_phases`, the
+    /// heuristic or `polarity`.
+    pub phase_saving: bool,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            polarity: Polarity::default(),
+            initial_phases: HashMap::new(),
+            heuristic: Heuristic::default(),
+            trace: false,
+            num_vars: None,
+            shuffle_seed: None,
+            max_learned_clauses: None,
+            clause_decay: 0.999,
+            compaction_interval: 1,
+            propagation_order: PropagationOrder::default(),
+            skip_satisfied_clauses: false,
+            learn_first_uip: false,
+            phase_saving: false,
+        }
     }
+}
 
-    // solve
-    let mut watchedliterals = WatchedLiterals::new(&cnf);
+/// A solver owning its formula, so it can be queried repeatedly without rebuilding
+/// the watched literals every time
+///
+/// Every conflict of a search teaches the solver a clause: the negation of the assumptions
+/// and decisions which led to it, or with [SolverConfig::learn_first_uip] the clause
+/// derived from the implication graph. Such a clause follows from the formula alone, so it is
+/// kept for all later calls and spares them from running into the same conflict again,
+/// unless it is deleted to stay within [SolverConfig::max_learned_clauses]. A learned clause
+/// subsumed by another clause is not kept, and one subsuming older learned clauses
+/// replaces them.
+pub struct Solver {
+    /// The formula, followed by the learned and derived clauses
+    cnf: Cnf,
+    /// The learned and derived clauses at the end of `cnf`, in the same order
+    extra_clauses: Vec<ExtraClause>,
+    /// The amount the activity of a clause is bumped by, growing with every conflict
+    activity_inc: f64,
+    /// The number of reductions of the learned clauses since the last compaction
+    reductions: usize,
+    watchedliterals: WatchedLiterals,
+    config: SolverConfig,
+    /// The last or currently stepped search
+    search: Search,
+}
 
-    let initial_assignment = {
-        // first get clauses with single literals, they have to be true
-        let mut assignment = match get_assignment_from_single_clauses(&cnf) {
-            Some(a) => a,
-            None => return (false, stats), // unsatisfiable
+impl Solver {
+    pub fn new(cnf: Cnf) -> Self {
+        Self::with_config(cnf, SolverConfig::default())
+    }
+
+    pub fn with_config(cnf: Cnf, config: SolverConfig) -> Self {
+        let watchedliterals = if config.skip_satisfied_clauses {
+            WatchedLiterals::new_skipping_satisfied(&cnf, &unit_assignment(&cnf))
+        } else {
+            WatchedLiterals::new(&cnf)
         };
-        let assignments_vec = assignment.iter().collect::<Vec<_>>();
+        Solver {
+            cnf,
+            extra_clauses: Vec::new(),
+            activity_inc: 1.0,
+            reductions: 0,
+            watchedliterals,
+            config,
+            search: Search::new(&[]),
+        }
+    }
 
-        // propagate these
-        for new_literal in assignments_vec {
-            match propagate_assignment(new_literal, &mut assignment, cnf, &mut watchedliterals) {
-                ExecuteAssignmentResult::Unsatisfiable => {
-                    // Conflict in DL0
-                    return (false, stats);
-                }
-                ExecuteAssignmentResult::AssignmentDone => {
-                    // left intentionally empty
-                }
-            }
+    /// Creates a solver which already knows the given learned clauses, e.g. the
+    /// [learned clauses](Solver::learned_clauses) of a solver for a related formula
+    ///
+    /// The clauses are treated like clauses learned by this solver, so they have to follow
+    /// from `cnf`. Otherwise models of `cnf` violating them are missed.
+    pub fn with_learned(cnf: Cnf, learned: impl IntoIterator<Item = Clause>) -> Self {
+        let mut solver = Self::new(cnf);
+        solver.add_learned_clauses(learned.into_iter().collect());
+        solver
+    }
+
+    /// The formula, including the clauses learned so far at its end
+    ///
+    /// The learned clauses deleted since the last compaction are still included, see
+    /// [SolverConfig::compaction_interval].
+    pub fn cnf(&self) -> &Cnf {
+        &self.cnf
+    }
+
+    /// The clauses learned by the searches so far, oldest first
+    ///
+    /// Each of them follows from the formula, so they remain valid for any formula which
+    /// contains all clauses of this one. Deleted and derived clauses are not included.
+    pub fn learned_clauses(&self) -> impl Iterator<Item = &Clause> + '_ {
+        let extra_clauses = &self.cnf.clauses[self.num_original_clauses()..];
+        extra_clauses
+            .iter()
+            .zip(&self.extra_clauses)
+            .filter(|(_, extra)| extra.learned && !extra.deleted)
+            .map(|(clause, _)| clause)
+    }
+
+    /// Adds a clause to the formula, keeping the clauses learned so far
+    ///
+    /// Only the new clause is watched, so this is cheaper than a new solver for the extended
+    /// formula. It is inserted after the other clauses of the formula, before the learned
+    /// ones, which follow from the extended formula as well. A running search is ended,
+    /// the next step starts a new one. The clause indices of a finished search, e.g. of
+    /// [Solver::explain] and [Solver::trace], are shifted along with the clauses.
+    pub fn add_clause(&mut self, lits: &[LiteralTpl]) {
+        if !matches!(self.search.state, State::Start | State::Done) {
+            // the clause may be unit or false under the assignment of the search
+            self.finish_search();
+            self.search = Search::new(&[]);
         }
 
-        // after propagation this assignment contains all clauses with a single literal and their propagations
-        assignment
-    };
-    println!("---Initial: {:?}", initial_assignment);
+        let clause_idx = self.num_original_clauses();
+        let clause = Clause::from_literals(lits);
+        self.search.shift_clause_indices(clause_idx);
+        self.watchedliterals.insert_clause(clause_idx, &clause);
+        self.cnf.clauses.insert(clause_idx, clause);
+    }
 
-    let max = cnf.highest_var();
+    /// Permanently removes the clause with the given index from the formula and returns it
+    ///
+    /// Like [Vec::remove], the indices of all following clauses are decremented by one.
+    /// The learned clauses may have been derived from the removed clause, so all of them
+    /// are dropped as well.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `clause_idx` is out of bounds or names a learned clause
+    pub fn remove_clause(&mut self, clause_idx: usize) -> Clause {
+        assert!(
+            clause_idx < self.num_original_clauses(),
+            "Clause index {} is out of bounds",
+            clause_idx
+        );
+        self.truncate_clauses(self.num_original_clauses());
 
-    stats.tries += 1;
-    if cnf.is_satisfied(&initial_assignment) {
-        return (true, stats);
+        let clause = self.cnf.clauses.remove(clause_idx);
+        if self.config.skip_satisfied_clauses && clause.len() == 1 {
+            // the clauses satisfied by the removed unit clause are no longer skipped
+            watch_clauses(&mut self.watchedliterals, &self.cnf, &self.config);
+        } else {
+            self.watchedliterals.remove_clause(clause_idx);
+        }
+        clause
     }
 
-    let mut dec_levels: Vec<DecisionLevel> = Vec::new();
+    pub fn solve(&mut self) -> SolveResult {
+        self.solve_under_assumptions(&[])
+    }
 
-    #[derive(Debug, PartialEq, Eq)]
-    enum State {
-        CheckCurrentLevel,
-        AssignNewVar,
-        NewDecLevelWithAssignment(LiteralTpl),
-        PropagateAssignment(LiteralTpl),
-        Backtrack,
+    /// Solves the formula with the given literals fixed to true
+    ///
+    /// The assumptions only hold for this call, the formula itself is not changed. The
+    /// clauses learned during the call contain the negated assumptions, so they remain
+    /// valid and are reused when the solver is called again with other assumptions.
+    pub fn solve_under_assumptions(&mut self, assumptions: &[LiteralTpl]) -> SolveResult {
+        self.start_search(assumptions);
+        loop {
+            if let Some(result) = self.step() {
+                return result;
+            }
+        }
     }
-    let mut state = State::CheckCurrentLevel;
 
-    loop {
-        match state {
-            State::CheckCurrentLevel => {
-                // Check for satisfiability
-                if let Some(dl) = dec_levels.last() {
-                    if check_assignment(&cnf, &dl.assignment, &mut stats) {
-                        return (true, stats);
-                    }
+    /// Like [Solver::solve_under_assumptions], but gives up once the search has looked up
+    /// more than `max_literal_accesses` literals, see [Stats::literal_accesses]
+    ///
+    /// Returns `None` if the search gave up. It is left running, so [Solver::step]
+    /// continues it.
+    pub fn solve_bounded(
+        &mut self,
+        assumptions: &[LiteralTpl],
+        max_literal_accesses: u64,
+    ) -> Option<SolveResult> {
+        self.start_search(assumptions);
+        loop {
+            if let Some(result) = self.step() {
+                return Some(result);
+            }
+            if self.stats().literal_accesses > max_literal_accesses {
+                return None;
+            }
+        }
+    }
+
+    /// Performs a single step of the search, e.g. a decision, a propagation or a backtrack
+    ///
+    /// Returns the result once the search is finished. The next call after that starts a
+    /// new search without assumptions. In between, [Solver::current_assignment] and
+    /// [Solver::current_decision_level] show the state of the search.
+    /// The current assignment does not contain the free variables added to the model
+    /// for [SolverConfig::num_vars].
+    ///
+    /// Before the assignment is checked, the [parity constraints](Cnf::xors) are reduced
+    /// by Gaussian elimination. A clause they imply which is unit or false under the
+    /// assignment is added like by [Solver::add_derived_clause], which takes a step of its
+    /// own.
+    pub fn step(&mut self) -> Option<SolveResult> {
+        if self.search.is_done() {
+            self.start_search(&[]);
+        }
+        let result = self.step_search();
+        if let Some(SolveResult::Sat(model)) = &result {
+            debug_assert_eq!(self.cnf.verify(model), Ok(()), "Model violates a clause");
+            let num_vars = self
+                .config
+                .num_vars
+                .unwrap_or_else(|| self.cnf.highest_var());
+            self.search.stats.free_vars = (1..=num_vars)
+                .filter(|&var| model.get(var).is_none())
+                .count();
+        }
+        let result = match (result, self.config.num_vars) {
+            (Some(SolveResult::Sat(model)), Some(num_vars)) => {
+                let polarity = first_polarity(0, self.config.polarity, &HashMap::new());
+                let free = (1..=num_vars)
+                    .filter(|&var| model.get(var).is_none())
+                    .map(|var| (var, polarity));
+                Some(SolveResult::Sat(model.with_all(free)))
+            }
+            (result, _) => result,
+        };
+
+        if result.is_some() {
+            self.finish_search();
+        }
+        result
+    }
+
+    /// Performs a step of the running search like [Solver::step], but leaves a finished
+    /// search as it is
+    fn step_search(&mut self) -> Option<SolveResult> {
+        let mut xor_extension = Vec::new();
+        if !self.cnf.xors.is_empty() && self.search.state == State::CheckCurrentLevel {
+            match gauss::eliminate(&self.cnf.xors, self.search.current_assignment()) {
+                Elimination::Clause(clause) => {
+                    self.add_derived_clause(clause);
+                    return None;
                 }
-                state = State::AssignNewVar;
+                Elimination::Consistent(extension) => xor_extension = extension,
+            }
+        }
+        let literal_accesses = self.watchedliterals.literal_accesses();
+        let result = self
+            .search
+            .step(&self.cnf, &mut self.watchedliterals, &self.config);
+        if result.is_none() {
+            self.add_clauses_learned_so_far();
+        }
+        self.search.stats.literal_accesses +=
+            self.watchedliterals.literal_accesses() - literal_accesses;
+        match result {
+            // the clauses are satisfied without the unassigned variables of the constraints
+            Some(SolveResult::Sat(model)) if !xor_extension.is_empty() => {
+                Some(SolveResult::Sat(model.with_all(xor_extension.into_iter())))
             }
+            result => result,
+        }
+    }
 
-            State::AssignNewVar => {
-                // pick a new variable to set
-                let var = choose_next_var(max, &dec_levels, &initial_assignment);
+    /// Removes the learned and derived clauses from the given index on
+    fn truncate_clauses(&mut self, len: usize) {
+        debug_assert!(len >= self.num_original_clauses());
+        while self.cnf.clauses.len() > len {
+            self.extra_clauses.pop();
+            self.cnf.clauses.pop();
+            self.watchedliterals.remove_clause(self.cnf.clauses.len());
+        }
+    }
 
-                // Check if the assignment is complete, i.e. no variable to be set could be found
-                let var = match var {
-                    None => {
-                        // Assignment complete, therefore backtrack
-                        state = State::Backtrack;
-                        continue;
-                    }
-                    Some(var) => var,
-                };
+    /// The number of clauses of the formula before the learned and derived ones
+    fn num_original_clauses(&self) -> usize {
+        self.cnf.clauses.len() - self.extra_clauses.len()
+    }
+
+    /// Replaces the finished search by a new one under the given assumptions
+    ///
+    /// The learned clauses are only deleted now, so that the clause indices of the
+    /// finished search stay valid until then.
+    fn start_search(&mut self, assumptions: &[LiteralTpl]) {
+        if let Some(max_learned) = self.config.max_learned_clauses {
+            self.reduce_learned_clauses(max_learned);
+        }
+        self.search = Search::new(assumptions);
+    }
 
-                // Assignment incomplete, we found a new variable to set
-                state = State::NewDecLevelWithAssignment((var, FIRST_TRY));
+    /// Bumps the clauses of the conflicts of the finished search and adds the clauses
+    /// learned from them
+    fn finish_search(&mut self) {
+        let num_original = self.num_original_clauses();
+        for clause_idx in self.search.conflict_clauses.drain(..) {
+            if let Some(idx) = clause_idx.checked_sub(num_original) {
+                self.extra_clauses[idx].activity += self.activity_inc;
             }
+            self.activity_inc /= self.config.clause_decay;
+        }
 
-            State::Backtrack => {
-                print!("Backtracking... ");
-                let result = backtrack(&mut dec_levels);
-                match result {
-                    BacktrackResult::UnsatisfiableFormula => {
-                        // Return unsat
-                        println!("Unsatisfiable!");
-                        return (false, stats);
-                    }
-                    BacktrackResult::ContinueWith(new_assignment) => {
-                        // Backtracking did undo multiple decision levels and the resulting decision level had this assignment
-                        // Skip State::ExecAssignment and jump to PropagateAssignment, because the (now) latest
-                        // decision level already has the expected assignment set due to the call to backtrack
-                        println!("Continuing with dl {}", dec_levels.len() + 1);
-                        state = State::PropagateAssignment(new_assignment);
-                    }
-                }
+        // no search is running anymore, so the learned clauses can be watched
+        let learned = std::mem::take(&mut self.search.learned);
+        self.add_learned_clauses(learned);
+
+        // only the ratios of the activities matter, keep them from overflowing
+        if self.activity_inc > 1e100 {
+            for extra in &mut self.extra_clauses {
+                extra.activity *= 1e-100;
             }
+            self.activity_inc *= 1e-100;
+        }
+    }
 
-            State::NewDecLevelWithAssignment(new_assigned_lit) => {
-                println!(
-                    "Trying to assign new var {:?} = {:?}",
-                    new_assigned_lit.0, new_assigned_lit.1
-                );
-                let new_assignment = dec_levels
-                    .last()
-                    .map(|dl| &dl.assignment)
-                    .unwrap_or(&initial_assignment)
-                    .with(new_assigned_lit.0, new_assigned_lit.1);
+    /// Adds the given learned clauses, unless another clause subsumes them, i.e. contains
+    /// only literals of them
+    ///
+    /// The learned clauses which an added one subsumes are deleted, while the clauses of
+    /// the formula and the derived clauses are always kept. No search may be running.
+    fn add_learned_clauses(&mut self, learned: Vec<Clause>) {
+        let num_original = self.num_original_clauses();
+        let mut occurrences = HashMap::<LiteralTpl, Vec<usize>>::new();
+        for (idx, clause) in self.cnf.clauses.iter().enumerate() {
+            for lit in clause.literals() {
+                occurrences.entry(lit).or_default().push(idx);
+            }
+        }
 
-                let next_var_at_least = {
-                    let nval = dec_levels
-                        .last()
-                        .map(|dl| dl.next_var_at_least)
-                        .unwrap_or(0);
-                    if new_assigned_lit.0 == nval + 1 {
-                        new_assigned_lit.0
-                    } else {
-                        nval
-                    }
-                };
+        for clause in learned {
+            let extra_clauses = &self.extra_clauses;
+            let deleted =
+                |idx: usize| idx >= num_original && extra_clauses[idx - num_original].deleted;
 
-                let new_dl = DecisionLevel {
-                    assignment: new_assignment,
-                    changed_var: new_assigned_lit.0,
-                    next_var_at_least,
-                    flipped: false,
+            // a subsuming clause occurs in the lists of all of its literals
+            let mut hits = HashMap::new();
+            let subsumed = clause.literals().any(|lit| {
+                occurrences.get(&lit).into_iter().flatten().any(|&idx| {
+                    let count = hits.entry(idx).or_insert(0);
+                    *count += 1;
+                    !deleted(idx) && *count == self.cnf.clauses[idx].len()
+                })
+            });
+            if subsumed {
+                continue;
+            }
+
+            // a subsumed clause contains the literal of the fewest occurrences
+            let candidates = match clause
+                .literals()
+                .min_by_key(|lit| occurrences.get(lit).map_or(0, Vec::len))
+            {
+                Some(lit) => occurrences.get(&lit).cloned().unwrap_or_default(),
+                None => (num_original..self.cnf.clauses.len()).collect(),
+            };
+            for idx in candidates {
+                let extra = match idx.checked_sub(num_original) {
+                    Some(extra_idx) => &mut self.extra_clauses[extra_idx],
+                    None => continue,
                 };
-                dec_levels.push(new_dl);
-                state = State::PropagateAssignment(new_assigned_lit);
+                if extra.learned && !extra.deleted && clause.subsumes(&self.cnf.clauses[idx]) {
+                    extra.deleted = true;
+                    self.watchedliterals.unwatch_clause(idx);
+                }
             }
 
-            State::PropagateAssignment(new_assigned_lit) => {
-                print!("Propagating assignment {:?}: ", new_assigned_lit);
-                // The current/top decision level already has the assignment set
-                // but it needs to be propagated
-                debug_assert!(matches!(
-                    dec_levels
-                        .last()
-                        .unwrap()
-                        .assignment
-                        .get_lit(new_assigned_lit),
-                    Some(true)
-                ));
+            for lit in clause.literals() {
+                occurrences
+                    .entry(lit)
+                    .or_default()
+                    .push(self.cnf.clauses.len());
+            }
+            self.push_learned_clause(clause);
+        }
+    }
 
-                let assignment = &mut dec_levels
-                    .last_mut()
-                    .expect("Encountered State::PropagateAssignment without decision level")
-                    .assignment;
+    /// Watches the given learned clause and appends it to the formula
+    ///
+    /// No search may be running.
+    fn push_learned_clause(&mut self, clause: Clause) {
+        self.watchedliterals.add_clause(&clause);
+        self.cnf.clauses.push(clause);
+        self.extra_clauses.push(ExtraClause {
+            learned: true,
+            deleted: false,
+            activity: self.activity_inc,
+        });
+    }
 
-                let result =
-                    propagate_assignment(new_assigned_lit, assignment, &cnf, &mut watchedliterals);
+    /// Watches the clauses the running search learned with [SolverConfig::learn_first_uip]
+    /// and appends them to the formula, so they prune the rest of the search
+    ///
+    /// Unlike [Solver::add_learned_clauses], this does not look for subsumed clauses.
+    fn add_clauses_learned_so_far(&mut self) {
+        for clause in self.search.take_learned_for_search(&self.config) {
+            self.cnf.clauses.push(clause);
+            self.extra_clauses.push(ExtraClause {
+                learned: true,
+                deleted: false,
+                activity: self.activity_inc,
+            });
+            let clause_idx = self.cnf.clauses.len() - 1;
+            self.search.add_clause(
+                &self.cnf,
+                &mut self.watchedliterals,
+                clause_idx,
+                &self.config,
+            );
+        }
+    }
 
-                match result {
-                    ExecuteAssignmentResult::Unsatisfiable => {
-                        // Assignment caused insatisfiability => backtrack
-                        println!("Unsatisfiable.");
-                        state = State::Backtrack
-                    }
-                    ExecuteAssignmentResult::AssignmentDone => {
-                        println!("Done.");
-                        state = State::CheckCurrentLevel;
-                    }
-                }
-            }
+    /// Deletes the least active learned clauses until at most `max_learned` are left
+    ///
+    /// Of two clauses with the same activity, the older one is deleted first. The deleted
+    /// clauses are no longer watched, but stay in the formula until the next compaction.
+    /// They follow from the formula, so the checks of a search may still look at them.
+    fn reduce_learned_clauses(&mut self, max_learned: usize) {
+        let mut learned = self
+            .extra_clauses
+            .iter()
+            .enumerate()
+            .filter(|(_, extra)| extra.learned && !extra.deleted)
+            .map(|(idx, extra)| (extra.activity, idx))
+            .collect::<Vec<_>>();
+        if learned.len() <= max_learned {
+            return;
+        }
+
+        // the sort is stable, so older clauses stay in front on ties
+        learned.sort_by(|(lhs, _), (rhs, _)| lhs.total_cmp(rhs));
+        let num_original = self.num_original_clauses();
+        for &(_, idx) in &learned[..learned.len() - max_learned] {
+            self.extra_clauses[idx].deleted = true;
+            self.watchedliterals.unwatch_clause(num_original + idx);
+        }
+
+        self.reductions += 1;
+        if self.reductions == self.config.compaction_interval {
+            self.compact();
         }
     }
-}
 
-#[inline(always)]
-fn check_assignment(cnf: &Cnf, a: &Assignment, stats: &mut Stats) -> bool {
-    let result = cnf.is_satisfied(&a);
-    println!("...Checking {:?}: {}", a, result);
-    stats.tries += 1;
-    result
-}
+    /// Removes the deleted learned clauses, renumbering the remaining learned and derived
+    /// clauses
+    fn compact(&mut self) {
+        self.reductions = 0;
+        let num_original = self.num_original_clauses();
+        let keep = (0..self.cnf.clauses.len())
+            .map(|idx| idx < num_original || !self.extra_clauses[idx - num_original].deleted)
+            .collect::<Vec<_>>();
 
-fn choose_next_var(
-    max: Var,
-    dec_levels: &[DecisionLevel],
-    initial_assignment: &Assignment,
-) -> Option<Var> {
-    // start with 1 + highest from last dl or 0s
-    let mut var = 1 + dec_levels
-        .last()
-        .map(|dl| dl.next_var_at_least)
-        .unwrap_or(0);
-
-    let a = dec_levels
-        .last()
-        .map(|dl| &dl.assignment)
-        .unwrap_or(&initial_assignment);
-
-    // increase picked var while it is already set (due to bcp)
-    let var = loop {
-        let assigned = a.get(var).is_some();
-
-        if assigned {
-            var += 1;
-        } else {
-            break var;
+        let mut idx = 0;
+        self.cnf.clauses.retain(|_| {
+            idx += 1;
+            keep[idx - 1]
+        });
+        self.extra_clauses.retain(|extra| !extra.deleted);
+        self.watchedliterals.retain_clauses(&keep);
+    }
+
+    /// Adds a clause derived outside of the solver, e.g. a lemma of a theory or a symmetry
+    /// breaking clause
+    ///
+    /// The clause is watched right away. If it is unit under the assignment of a running
+    /// search, its literal is propagated at the current decision level, and if it is
+    /// falsified, the search backtracks. It is kept like a learned clause, so
+    /// [Solver::remove_clause] drops it as well.
+    pub fn add_derived_clause(&mut self, clause: Clause) {
+        self.cnf.clauses.push(clause);
+        self.extra_clauses.push(ExtraClause {
+            learned: false,
+            deleted: false,
+            activity: 0.0,
+        });
+        let clause_idx = self.cnf.clauses.len() - 1;
+        self.search.add_clause(
+            &self.cnf,
+            &mut self.watchedliterals,
+            clause_idx,
+            &self.config,
+        );
+    }
+
+    /// The assignment of the current decision level
+    ///
+    /// It contains the unit clauses, the assumptions, all decisions and their propagations.
+    /// After a search has finished, it stays available until the next search starts.
+    pub fn current_assignment(&self) -> &Assignment {
+        self.search.current_assignment()
+    }
+
+    /// The literals of the clause which implied the current value of the variable, except
+    /// the literal of the variable itself
+    ///
+    /// These literals are all false, calling this function for their variables traces the
+    /// implication further back. The result is empty if the variable was decided, assumed
+    /// or is unassigned, as well as if it was implied by a unit clause. It refers to the
+    /// last or current search and its clause indices.
+    pub fn explain(&self, var: Var) -> Vec<LiteralTpl> {
+        if self.current_assignment().get(var).is_none() {
+            return Vec::new();
         }
-    };
+        match self.search.log.reasons.get(&var) {
+            Some(&clause_idx) => self.cnf.clauses[clause_idx]
+                .literals()
+                .filter(|&(lit_var, _)| lit_var != var)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
 
-    if var < max {
-        Some(var)
-    } else {
-        None
+    /// The number of decisions the current assignment is based on
+    pub fn current_decision_level(&self) -> usize {
+        self.search.dec_levels.len()
     }
-}
 
-/// Propagates a decision (new_literal) in the given assignment using the watched literals
-///
-/// The assignment must already contain the new_literal and resulting propagations will mutate it
-/// The watched literals are used for propagations and are updated accordingly
-///
-/// Returns AssignmentDone if the new_literal and all propagations are now reflected in the assignment
-/// and watched literals without encountering a conflict
-/// Returns Unsatisfiable if the new_literal or resulting propagations caused a conflict. In this case
-/// the current decision level should be dropped
-fn propagate_assignment(
-    new_literal: LiteralTpl,
-    assignment: &mut Assignment,
-    cnf: &Cnf,
-    watchedliterals: &mut WatchedLiterals,
-) -> ExecuteAssignmentResult {
-    debug_assert!(matches!(assignment.get_lit(new_literal), Some(true)));
+    /// The decision levels of the last or current search, lowest first
+    ///
+    /// After a model was found, these are the decisions leading to it. An unsatisfiable
+    /// search has undone all of its levels.
+    pub fn decision_levels(&self) -> &[DecisionLevel] {
+        &self.search.dec_levels
+    }
 
-    // Vars to propagate
-    let mut propagations = VecDeque::new();
-    propagations.push_back(new_literal);
+    /// The statistics of the last or current search
+    pub fn stats(&self) -> &Stats {
+        &self.search.stats
+    }
 
-    while let Some(prop) = propagations.pop_front() {
-        let result = watchedliterals.update(&cnf, assignment, prop);
-        match result {
-            UpdateResult::Unsatisfiable => {
-                // Unsatisfiable
-                return ExecuteAssignmentResult::Unsatisfiable;
-            }
+    /// The events of the last or current search, if [SolverConfig::trace] is enabled
+    pub fn trace(&self) -> Option<&[SolveEvent]> {
+        self.search.log.events.as_deref()
+    }
 
-            UpdateResult::Satisfiable {
-                propagations: new_propagations,
-            } => {
-                // Assignment of propagation successful, store all new propagations
-                for (prop_var, prop_val) in new_propagations {
-                    propagations.push_back((prop_var, prop_val));
-                    assignment.change(prop_var, prop_val);
-                }
-            }
+    /// Solves the formula and returns a model containing only the variables in `vars`
+    ///
+    /// The whole formula is solved, only the returned assignment is trimmed. Variables of
+    /// interest which the model leaves unassigned are missing from the result as well.
+    pub fn solve_projected(&mut self, vars: &[Var]) -> SolveResult {
+        match self.solve() {
+            SolveResult::Sat(model) => SolveResult::Sat(
+                Assignment::new().with_all(
+                    vars.iter()
+                        .filter_map(|&var| model.get(var).map(|val| (var, val))),
+                ),
+            ),
+            result => result,
         }
     }
 
-    // All propagations handled without running into unsatisfiability
-    ExecuteAssignmentResult::AssignmentDone
-}
+    /// Enumerates the models of the formula, see [ModelIter]
+    pub fn model_iter(&mut self) -> ModelIter<'_> {
+        ModelIter {
+            solver: self,
+            exhausted: false,
+        }
+    }
 
-#[must_use]
-enum ExecuteAssignmentResult {
-    Unsatisfiable,
-    AssignmentDone,
-}
+    /// Checks whether every satisfying assignment of the formula satisfies `lit`
+    ///
+    /// This is the case iff the formula together with the negated literal is unsatisfiable.
+    /// An unsatisfiable formula entails every literal.
+    pub fn entails(&mut self, lit: LiteralTpl) -> bool {
+        !self.solve_under_assumptions(&[negate(lit)]).is_sat()
+    }
 
-/// Calculates an assignment satisfying all clauses with only a single literal
-///
-/// @return None, if there are two conflicting clauses with a single literal
-fn get_assignment_from_single_clauses(cnf: &Cnf) -> Option<Assignment> {
-    let mut assignment = Assignment::new();
+    /// Assigns the unit clauses and the assumptions and propagates them, without deciding
+    /// any variable
+    ///
+    /// Returns the literals implied this way ordered by variable, including the unit
+    /// clauses but not the assumptions. The learned clauses take part in the propagation,
+    /// so later calls may imply more literals. If a clause is falsified, the formula is
+    /// unsatisfiable under the assumptions and the conflict is returned instead.
+    /// A running search is abandoned like by [Solver::solve].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assumptions contain a literal and its negation
+    pub fn propagate_under(
+        &mut self,
+        assumptions: &[LiteralTpl],
+    ) -> Result<Vec<LiteralTpl>, Conflict> {
+        if !self.search.is_done() {
+            self.search = Search::new(&[]);
+        }
+        if let Some(clause_idx) = self.cnf.clauses.iter().position(Clause::is_empty) {
+            return Err(Conflict {
+                clause_idx,
+                literal: None,
+            });
+        }
 
-    for clause in &cnf.clauses {
-        let mut literals = clause.literals();
-        match (literals.next(), literals.next()) {
-            (Some(lit), None) => {
-                // Clause only contains one literal
+        let mut log = SearchLog::default();
+        let mut assignment = get_assignment_from_single_clauses(&self.cnf, &mut log)?;
+        let mut units = assignment.iter().collect::<Vec<_>>();
+        units.sort_unstable();
+
+        for (idx, &lit) in units.iter().chain(assumptions).enumerate() {
+            if idx >= units.len() {
                 match assignment.get_lit(lit) {
-                    Some(true) => {
-                        // Already satisfying
-                    }
+                    Some(true) => continue,
                     Some(false) => {
-                        // Clause unsat
-                        return None;
+                        let clause_idx = *log
+                            .reasons
+                            .get(&lit.0)
+                            .expect("Assumptions contradict each other");
+                        return Err(Conflict {
+                            clause_idx,
+                            literal: Some(lit),
+                        });
                     }
                     None => {
                         assignment.change(lit.0, lit.1);
+                        record(&mut log, SolveEvent::Assumption(lit));
                     }
                 }
             }
-
-            _ => {}
+            if let ExecuteAssignmentResult::Unsatisfiable(conflict) = propagate_assignment(
+                lit,
+                &mut assignment,
+                &self.cnf,
+                &mut self.watchedliterals,
+                self.config.propagation_order,
+                &mut log,
+                None,
+            ) {
+                return Err(conflict);
+            }
+        }
+
+        let mut implied = assignment
+            .iter()
+            .filter(|lit| !assumptions.contains(lit))
+            .collect::<Vec<_>>();
+        implied.sort_unstable();
+        Ok(implied)
+    }
+
+    /// Checks whether adding the clause would make the formula unsatisfiable, without
+    /// adding it
+    ///
+    /// The clause is added temporarily and the formula solved with the clauses learned so
+    /// far. The clauses learned during this call may depend on the temporary clause, so
+    /// they are dropped, and no learned clauses are deleted meanwhile.
+    /// A running search is abandoned like by [Solver::solve]. Otherwise the last search
+    /// stays available, e.g. for [Solver::current_assignment].
+    pub fn would_be_unsat_with(&mut self, clause: &Clause) -> bool {
+        // not started by start_search, which could delete learned clauses
+        let previous = std::mem::replace(&mut self.search, Search::new(&[]));
+        let num_clauses = self.cnf.clauses.len();
+
+        self.add_derived_clause(clause.clone());
+        // not finished like by Solver::step, which would keep the learned clauses, but they
+        // are still watched right away, a backjump relies on its learned clause
+        let result = loop {
+            if let Some(result) = self.step_search() {
+                break result;
+            }
+        };
+
+        self.truncate_clauses(num_clauses);
+        self.search = if previous.is_done() {
+            previous
+        } else {
+            Search::new(&[])
+        };
+        !result.is_sat()
+    }
+}
+
+/// A clause at the end of the formula of a [Solver] which was not part of it initially
+#[derive(Debug, Clone, Copy)]
+struct ExtraClause {
+    /// Learned clauses may be deleted, derived ones are kept
+    learned: bool,
+    /// Deleted, but not yet removed by a compaction
+    deleted: bool,
+    /// See [SolverConfig::clause_decay]
+    activity: f64,
+}
+
+/// Iterator over the models of the formula of a [Solver]
+///
+/// Every model is blocked by a clause negating the decisions it was found with before the
+/// next one is searched. The other assigned variables were propagated from these decisions,
+/// so the clause rules out exactly the assignments extending the model. The models are
+/// partial assignments which do not overlap, and together they cover every satisfying
+/// assignment. As each blocking clause only contains decision variables, the clauses added
+/// grow in O(models · decisions) memory. The variables of the
+/// [parity constraints](Cnf::xors) which the search left unassigned are chosen like
+/// decisions, so their values in the model are negated by the blocking clause as well.
+///
+/// The blocking clauses are kept by the solver as derived clauses, together with the
+/// clauses learned meanwhile. After the iteration, the formula of the solver only allows
+/// the models not returned yet.
+pub struct ModelIter<'a> {
+    solver: &'a mut Solver,
+    exhausted: bool,
+}
+
+impl Iterator for ModelIter<'_> {
+    type Item = Assignment;
+
+    fn next(&mut self) -> Option<Assignment> {
+        if self.exhausted {
+            return None;
+        }
+
+        match self.solver.solve() {
+            SolveResult::Sat(model) => {
+                let searched = self.solver.current_assignment();
+                let xor_vars = self
+                    .solver
+                    .cnf
+                    .xors
+                    .iter()
+                    .flat_map(|xor| xor.vars.iter().copied())
+                    .collect::<HashSet<_>>();
+                let chosen = model
+                    .iter()
+                    .filter(|&(var, _)| xor_vars.contains(&var) && searched.get(var).is_none());
+                let blocking = self
+                    .solver
+                    .search
+                    .decisions()
+                    .chain(chosen)
+                    .map(negate)
+                    .collect::<Vec<_>>();
+                self.solver
+                    .add_derived_clause(Clause::from_literals(&blocking));
+                Some(model)
+            }
+            SolveResult::Unsat => {
+                self.exhausted = true;
+                None
+            }
+            SolveResult::Unknown => {
+                unreachable!("a solver without a limit always decides the formula")
+            }
+        }
+    }
+}
+
+/// Allocations kept between the calls of [is_satisfiable_with_scratch]
+///
+/// Deciding many small formulas spends much of its time allocating the watched literals
+/// and decision levels of every search. A scratch buffer keeps them, cleared, for the
+/// next formula.
+pub struct SolverScratch {
+    watchedliterals: WatchedLiterals,
+    dec_levels: Vec<DecisionLevel>,
+}
+
+impl Default for SolverScratch {
+    fn default() -> Self {
+        SolverScratch {
+            watchedliterals: WatchedLiterals::new(&Cnf::new()),
+            dec_levels: Vec::new(),
+        }
+    }
+}
+
+/// Checks whether the formula is satisfiable
+///
+/// Formulas which [Cnf::quick_decide] can decide are not searched at all. Before the search,
+/// subsumed clauses and the clauses with pure literals are dropped, see
+/// [Cnf::remove_subsumed] and [Cnf::eliminate_pure_literals], and the outputs of gates are
+/// eliminated by [eliminate_gates] unless the formula has parity constraints. In debug
+/// builds without the `unchecked` feature, this panics if the formula fails
+/// [Cnf::validate].
+pub fn is_satisfiable(cnf: &Cnf) -> (bool, Stats) {
+    is_satisfiable_with_scratch(cnf, &mut SolverScratch::default())
+}
+
+/// Like [is_satisfiable], but reuses the allocations of the given scratch buffer
+pub fn is_satisfiable_with_scratch(cnf: &Cnf, scratch: &mut SolverScratch) -> (bool, Stats) {
+    check_satisfiable(cnf, scratch, || None)
+}
+
+/// Like [is_satisfiable], but decides the variables with the given heuristic instead of
+/// the configured one
+///
+/// Every component of the formula is searched with a fresh clone of `heuristic`.
+pub fn is_satisfiable_with_heuristic<H>(cnf: &Cnf, heuristic: H) -> (bool, Stats)
+where
+    H: DecisionHeuristic + Clone + 'static,
+{
+    check_satisfiable(cnf, &mut SolverScratch::default(), || {
+        Some(Box::new(heuristic.clone()))
+    })
+}
+
+/// Implements [is_satisfiable], `heuristic` is called once per searched component
+fn check_satisfiable(
+    cnf: &Cnf,
+    scratch: &mut SolverScratch,
+    heuristic: impl Fn() -> Option<Box<dyn DecisionHeuristic>>,
+) -> (bool, Stats) {
+    // tautologies are satisfied by every assignment
+    let mut reduced = cnf.clone();
+    reduced.remove_tautologies();
+
+    #[cfg(all(debug_assertions, not(feature = "unchecked")))]
+    if let Err(err) = reduced.validate() {
+        panic!("Invalid formula: {}", err);
+    }
+
+    let mut stats = Stats::default();
+    if let Some(satisfiability) = reduced.quick_decide() {
+        if satisfiability == Satisfiability::Unsatisfiable {
+            stats.unsat_reason = Some(UnsatReason::EmptyClause);
+        }
+        return (satisfiability == Satisfiability::Satisfiable, stats);
+    }
+
+    stats.preprocess.subsumed_clauses = reduced.remove_subsumed();
+    // the clauses with pure literals are satisfied by assigning them, they cannot conflict
+    stats.preprocess.pure_literals = reduced.eliminate_pure_literals().iter().count();
+    // only the satisfiability is needed, so the eliminated outputs are never assigned, but
+    // the parity constraints would be lost
+    if reduced.xors.is_empty() {
+        let (simplified, eliminated) = eliminate_gates(&reduced);
+        stats.preprocess.eliminated_vars = eliminated.len();
+        reduced = simplified;
+    }
+
+    let config = SolverConfig::default();
+
+    // components do not share variables, so each of them can be solved on its own, the
+    // parity constraints would have to be split as well
+    let components = if reduced.xors.is_empty() {
+        reduced.into_components()
+    } else {
+        vec![reduced]
+    };
+    for component in components {
+        let (result, component_stats) =
+            solve(&component, scratch, &config, &[], heuristic(), || false);
+        stats.merge(&component_stats);
+        if !result.is_sat() {
+            return (false, stats);
+        }
+    }
+
+    (true, stats)
+}
+
+/// Enumerates the models of the formula lazily, like [Solver::model_iter] on a solver
+/// owning a copy of it
+///
+/// Each model is searched only when the iterator is advanced, so `all_models(cnf).take(n)`
+/// stops after `n` searches. The models are partial: a variable of the formula missing from
+/// a model is a wildcard, every value of it extends the model to a satisfying assignment.
+/// A model with `k` wildcards therefore stands for `2^k` total assignments, and no total
+/// assignment extends two different models.
+pub fn all_models(cnf: &Cnf) -> impl Iterator<Item = Assignment> {
+    let mut solver = Solver::new(cnf.clone());
+    let mut exhausted = false;
+    std::iter::from_fn(move || {
+        if exhausted {
+            return None;
+        }
+        let model = solver.model_iter().next();
+        exhausted = model.is_none();
+        model
+    })
+}
+
+/// Counts the total assignments of the variables `1..=highest_var` satisfying the formula
+///
+/// The partial models of [all_models] are not expanded, a model with `k` wildcards counts
+/// `2^k` times. The count saturates at `u128::MAX`. Variables not occurring in the formula
+/// below its highest one are wildcards of every model, so each of them doubles the count.
+pub fn count_models(cnf: &Cnf) -> u128 {
+    let num_vars = cnf.highest_var();
+    all_models(cnf).fold(0u128, |count, model| {
+        let assigned = model.iter().filter(|&(var, _)| var >= 1).count() as u32;
+        let extensions = 1u128.checked_shl(num_vars - assigned).unwrap_or(u128::MAX);
+        count.saturating_add(extensions)
+    })
+}
+
+/// Solves the formula with the given literals fixed to true
+///
+/// The assumptions are assigned before the first decision, like unit clauses, and
+/// propagated before the search. If they conflict with each other or with the formula
+/// under unit propagation, the result is [SolveResult::Unsat] without any decision. The
+/// formula itself is not changed.
+///
+/// Every call watches the formula anew. To solve the same formula under many different
+/// assumptions, [Solver::solve_under_assumptions] keeps the watched literals and the
+/// learned clauses between the calls.
+pub fn solve_assuming(cnf: &Cnf, assumptions: &[LiteralTpl]) -> SolveResult {
+    let mut scratch = SolverScratch::default();
+    solve(
+        cnf,
+        &mut scratch,
+        &SolverConfig::default(),
+        assumptions,
+        None,
+        || false,
+    )
+    .0
+}
+
+/// Like [solve_assuming], but returns the assumptions responsible if the formula is
+/// unsatisfiable under them
+///
+/// The returned core is a subset of the assumptions under which the formula is still
+/// unsatisfiable, so the assumptions left out of it do not matter. It consists of the
+/// assumptions in the clause learned from the final conflict, see
+/// [SolverConfig::learn_first_uip], and is usually small, but not necessarily minimal. It
+/// is empty if the formula is unsatisfiable without any assumptions.
+pub fn solve_with_core(
+    cnf: &Cnf,
+    assumptions: &[LiteralTpl],
+) -> Result<Assignment, Vec<LiteralTpl>> {
+    let config = SolverConfig {
+        learn_first_uip: true,
+        ..SolverConfig::default()
+    };
+    let mut solver = Solver::with_config(cnf.clone(), config);
+    match solver.solve_under_assumptions(assumptions) {
+        SolveResult::Sat(model) => Ok(model),
+        SolveResult::Unsat => Err(std::mem::take(&mut solver.search.core)),
+        SolveResult::Unknown => unreachable!("a solver without a limit always decides the formula"),
+    }
+}
+
+/// Solves the formula like [solve_assuming], but returns [SolveResult::Unknown] once the
+/// search has run for longer than `limit`
+///
+/// The clock is only read every [INTERRUPT_INTERVAL] steps of the search, so the limit may
+/// be exceeded slightly, and a formula decided within fewer steps is always decided. The
+/// stats of an interrupted search describe the work done until then.
+pub fn solve_with_timeout(cnf: &Cnf, limit: Duration) -> (SolveResult, Stats) {
+    let deadline = Instant::now() + limit;
+    let mut scratch = SolverScratch::default();
+    let config = SolverConfig::default();
+    solve(cnf, &mut scratch, &config, &[], None, || {
+        Instant::now() >= deadline
+    })
+}
+
+/// Solves the formula like [solve_assuming], but returns [SolveResult::Unknown] soon after
+/// `cancel` is set, e.g. by another thread
+///
+/// The flag is polled every [INTERRUPT_INTERVAL] steps of the search and never reset, so
+/// a set flag cancels every later call as well. A formula decided within fewer steps is
+/// decided regardless of the flag.
+pub fn solve_cancellable(cnf: &Cnf, cancel: &AtomicBool) -> (SolveResult, Stats) {
+    let mut scratch = SolverScratch::default();
+    let config = SolverConfig::default();
+    solve(cnf, &mut scratch, &config, &[], None, || {
+        cancel.load(Ordering::Relaxed)
+    })
+}
+
+/// Solves the formula with the given number of searches running in parallel, each in its
+/// own thread, and returns the result of the first one to finish
+///
+/// The first search branches in numeric order, the others in orders shuffled with their
+/// thread index as seed, every other one trying true first. The remaining searches are
+/// cancelled like by [solve_cancellable] once one has finished. At least one thread is
+/// used.
+pub fn solve_portfolio(cnf: &Cnf, threads: usize) -> (SolveResult, Stats) {
+    let cancel = AtomicBool::new(false);
+    let results = thread::scope(|scope| {
+        let searches = (0..threads.max(1))
+            .map(|idx| {
+                let cancel = &cancel;
+                scope.spawn(move || {
+                    let config = SolverConfig {
+                        polarity: if idx % 2 == 0 {
+                            Polarity::False
+                        } else {
+                            Polarity::True
+                        },
+                        shuffle_seed: Some(idx as u64).filter(|&seed| seed > 0),
+                        ..SolverConfig::default()
+                    };
+                    let mut scratch = SolverScratch::default();
+                    let interrupted = || cancel.load(Ordering::Relaxed);
+                    let (result, stats) = solve(cnf, &mut scratch, &config, &[], None, interrupted);
+                    // only the first search to finish wins, the others are cancelled
+                    let won = result != SolveResult::Unknown
+                        && cancel
+                            .compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed)
+                            .is_ok();
+                    Some((result, stats)).filter(|_| won)
+                })
+            })
+            .collect::<Vec<_>>();
+        searches
+            .into_iter()
+            .map(|search| search.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let (idx, (result, mut stats)) = results
+        .into_iter()
+        .enumerate()
+        .find_map(|(idx, result)| result.map(|result| (idx, result)))
+        .expect("A search is only cancelled after another one finished");
+    stats.winner = Some(idx);
+    (result, stats)
+}
+
+/// The number of steps of a search between two checks whether it should be interrupted
+///
+/// A step is cheap compared to reading the clock, so checking after every step would slow
+/// down the search noticeably.
+pub const INTERRUPT_INTERVAL: u64 = 1024;
+
+/// Runs a search on the formula until it is decided or `interrupted` returns true
+///
+/// `interrupted` is called every [INTERRUPT_INTERVAL] steps. A given `heuristic` replaces
+/// the one configured by [SolverConfig::heuristic].
+fn solve(
+    cnf: &Cnf,
+    scratch: &mut SolverScratch,
+    config: &SolverConfig,
+    assumptions: &[LiteralTpl],
+    heuristic: Option<Box<dyn DecisionHeuristic>>,
+    mut interrupted: impl FnMut() -> bool,
+) -> (SolveResult, Stats) {
+    if !cnf.xors.is_empty() {
+        // the clauses implied by the parity constraints are added to the formula
+        let mut solver = Solver::with_config(cnf.clone(), config.clone());
+        solver.start_search(assumptions);
+        solver.search.custom_heuristic = heuristic;
+        let mut steps = 0u64;
+        let result = loop {
+            if let Some(result) = solver.step() {
+                break result;
+            }
+            steps += 1;
+            if steps.is_multiple_of(INTERRUPT_INTERVAL) && interrupted() {
+                break SolveResult::Unknown;
+            }
+        };
+        return (result, solver.search.stats);
+    }
+
+    watch_clauses(&mut scratch.watchedliterals, cnf, config);
+    let mut search = Search::new(assumptions);
+    search.custom_heuristic = heuristic;
+    search.dec_levels = std::mem::take(&mut scratch.dec_levels);
+    let literal_accesses = scratch.watchedliterals.literal_accesses();
+    let mut steps = 0u64;
+    let result = loop {
+        if let Some(result) = search.step(cnf, &mut scratch.watchedliterals, config) {
+            break result;
+        }
+        steps += 1;
+        if steps.is_multiple_of(INTERRUPT_INTERVAL) && interrupted() {
+            break SolveResult::Unknown;
+        }
+    };
+    search.stats.literal_accesses = scratch.watchedliterals.literal_accesses() - literal_accesses;
+    if let SolveResult::Sat(model) = &result {
+        debug_assert_eq!(cnf.verify(model), Ok(()), "Model violates a clause");
+    }
+    scratch.dec_levels = search.dec_levels;
+    scratch.dec_levels.clear();
+    (result, search.stats)
+}
+
+/// Watches the clauses of the given formula instead of the previous ones, leaving out the
+/// clauses satisfied by its unit clauses if [SolverConfig::skip_satisfied_clauses] is set
+fn watch_clauses(watchedliterals: &mut WatchedLiterals, cnf: &Cnf, config: &SolverConfig) {
+    if config.skip_satisfied_clauses {
+        watchedliterals.reset_skipping_satisfied(cnf, &unit_assignment(cnf));
+    } else {
+        watchedliterals.reset(cnf);
+    }
+}
+
+/// The assignment made by the unit clauses of the formula
+///
+/// It is empty if they conflict, the search finds that conflict on its own.
+fn unit_assignment(cnf: &Cnf) -> Assignment {
+    get_assignment_from_single_clauses(cnf, &mut SearchLog::default()).unwrap_or_default()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum State {
+    /// Nothing has been assigned yet
+    Start,
+    CheckCurrentLevel,
+    AssignNewVar,
+    NewDecLevelWithAssignment(LiteralTpl),
+    PropagateAssignment(LiteralTpl),
+    Backtrack,
+    /// The result has been returned
+    Done,
+}
+
+/// The state of a single search, advanced one transition at a time
+struct Search {
+    assumptions: Vec<LiteralTpl>,
+    state: State,
+    /// Unit clauses, assumptions and their propagations
+    initial_assignment: Assignment,
+    dec_levels: Vec<DecisionLevel>,
+    heuristic: Box<dyn DecisionHeuristic>,
+    /// The highest variable of the formula when the search started, the clauses added
+    /// during the search only contain variables of the formula
+    max_var: Var,
+    /// Used instead of [SolverConfig::heuristic] once the search starts, if set
+    custom_heuristic: Option<Box<dyn DecisionHeuristic>>,
+    polarity_counts: HashMap<Var, (usize, usize)>,
+    stats: Stats,
+    log: SearchLog,
+    /// Clauses learned from the conflicts, only used by later searches unless
+    /// [SolverConfig::learn_first_uip] is set
+    learned: Vec<Clause>,
+    /// The clauses found unsatisfiable in the conflicts so far
+    conflict_clauses: Vec<usize>,
+    satisfied_clauses: SatisfiedClauses,
+    /// Assumptions which the formula contradicts on its own, once the search failed
+    core: Vec<LiteralTpl>,
+}
+
+impl Search {
+    fn new(assumptions: &[LiteralTpl]) -> Self {
+        Search {
+            assumptions: assumptions.to_vec(),
+            state: State::Start,
+            initial_assignment: Assignment::new(),
+            dec_levels: Vec::new(),
+            heuristic: Box::new(NumericOrder),
+            max_var: 0,
+            custom_heuristic: None,
+            polarity_counts: HashMap::new(),
+            stats: Stats::default(),
+            log: SearchLog::default(),
+            learned: Vec::new(),
+            conflict_clauses: Vec::new(),
+            satisfied_clauses: SatisfiedClauses::default(),
+            core: Vec::new(),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// Increments the recorded clause indices from `inserted` on, after a clause was
+    /// inserted at this index
+    fn shift_clause_indices(&mut self, inserted: usize) {
+        let shift = |idx: &mut usize| {
+            if *idx >= inserted {
+                *idx += 1;
+            }
+        };
+        self.log.reasons.values_mut().for_each(shift);
+        self.conflict_clauses.iter_mut().for_each(shift);
+        for event in self.log.events.iter_mut().flatten() {
+            match event {
+                SolveEvent::Propagation { reason_clause, .. } => shift(reason_clause),
+                SolveEvent::Conflict { clause_idx } => shift(clause_idx),
+                _ => {}
+            }
+        }
+    }
+
+    /// The assignment of the highest decision level
+    fn current_assignment(&self) -> &Assignment {
+        self.dec_levels
+            .last()
+            .map(|dl| &dl.assignment)
+            .unwrap_or(&self.initial_assignment)
+    }
+
+    /// Learns that the assumptions and the current decisions together falsify the formula,
+    /// or the clause derived by [Search::analyze_conflict] if
+    /// [SolverConfig::learn_first_uip] is set
+    ///
+    /// The clause with the given index is the one found unsatisfiable.
+    fn learn_conflict(&mut self, cnf: &Cnf, clause_idx: usize, config: &SolverConfig) {
+        self.stats.conflicts += 1;
+        self.conflict_clauses.push(clause_idx);
+        if config.learn_first_uip {
+            let learned = self.analyze_conflict(cnf, clause_idx);
+            self.learned.push(learned);
+            return;
+        }
+        let literals = self
+            .assumptions
+            .iter()
+            .copied()
+            .chain(self.decisions())
+            .map(negate)
+            .collect::<Vec<_>>();
+        self.learned.push(Clause::from_literals(&literals));
+    }
+
+    /// Derives a clause from the falsified clause with the given index by resolving it with
+    /// the reasons of its literals, until a single literal of the conflict level is left
+    ///
+    /// The conflict level is the highest level a literal of the clause was assigned at. The
+    /// remaining literal is its first unique implication point: every path from the decision
+    /// of that level to the conflict passes through it. Literals assigned before the first
+    /// decision are resolved away as well, except for the assumptions. All literals of the
+    /// derived clause are false under the current assignment.
+    fn analyze_conflict(&self, cnf: &Cnf, clause_idx: usize) -> Clause {
+        let conflict = &cnf.clauses[clause_idx];
+        let conflict_level = conflict
+            .literals()
+            .map(|(var, _)| self.level_of(var))
+            .max()
+            .unwrap_or(0);
+
+        let mut seen = HashSet::new();
+        let mut to_visit = conflict.literals().collect::<Vec<_>>();
+        // the literals of the conflict level not resolved yet, the last assigned on top
+        let mut open = BinaryHeap::new();
+        let mut learned = Vec::new();
+        loop {
+            while let Some(lit) = to_visit.pop() {
+                if !seen.insert(lit.0) {
+                    continue;
+                }
+                let level = self.level_of(lit.0);
+                match self.log.reasons.get(&lit.0) {
+                    _ if level == conflict_level && level > 0 => {
+                        open.push((self.log.positions[&lit.0], lit));
+                    }
+                    Some(&reason) if level == 0 => {
+                        let reason = cnf.clauses[reason].literals();
+                        to_visit.extend(reason.filter(|&(var, _)| var != lit.0));
+                    }
+                    _ => learned.push(lit),
+                }
+            }
+
+            let lit = match open.pop() {
+                Some((_, lit)) => lit,
+                None => break,
+            };
+            match self.log.reasons.get(&lit.0) {
+                Some(&reason) if !open.is_empty() => {
+                    let reason = cnf.clauses[reason].literals();
+                    to_visit.extend(reason.filter(|&(var, _)| var != lit.0));
+                }
+                _ => learned.push(lit),
+            }
+        }
+        Clause::from_literals(&learned)
+    }
+
+    /// Jumps back to the highest level at which the clauses learned since the last backtrack
+    /// are all asserting, i.e. at most one of their literals is unassigned
+    ///
+    /// The clause learned from a conflict has a single literal of the conflict level, the
+    /// asserting one, so the search continues at the second highest level of its literals.
+    /// Once the clause is watched, its asserting literal is propagated there. The search
+    /// fails if the clause is falsified before the first decision.
+    fn backjump_to_learned(&mut self) -> Option<SolveResult> {
+        let levels = self
+            .learned
+            .iter()
+            .map(|clause| {
+                let mut levels = clause
+                    .literals()
+                    .map(|(var, _)| self.level_of(var))
+                    .collect::<Vec<_>>();
+                levels.sort_unstable_by(|a, b| b.cmp(a));
+                (levels.first().copied(), levels.get(1).copied())
+            })
+            .collect::<Vec<_>>();
+
+        let falsified = self
+            .learned
+            .iter()
+            .zip(&levels)
+            .find(|(_, &(highest, _))| matches!(highest, None | Some(0)));
+        if let Some((clause, _)) = falsified {
+            verbose_println!("Unsatisfiable!");
+            // literals false before the first decision are resolved away unless assumed
+            self.stats.unsat_reason = Some(if clause.is_empty() {
+                UnsatReason::EmptyClause
+            } else {
+                UnsatReason::Assumptions
+            });
+            self.core = clause.literals().map(negate).collect();
+            self.dec_levels.clear();
+            return Some(SolveResult::Unsat);
+        }
+
+        let from_level = self.dec_levels.len();
+        let level = levels
+            .iter()
+            .map(|&(_, second)| second.unwrap_or(0))
+            .min()
+            .unwrap_or(0);
+        verbose_println!("Backjumping to dl {}", level);
+        backjump(&mut self.dec_levels, level);
+        self.stats.backtracks += 1;
+        self.stats.undone_levels += from_level - level;
+        record(
+            &mut self.log,
+            SolveEvent::Backtrack {
+                from_level,
+                to_level: level,
+            },
+        );
+        // the assignment of the level itself only grows by the asserting literals
+        self.satisfied_clauses.backtrack(level + 1);
+        self.state = State::CheckCurrentLevel;
+        None
+    }
+
+    /// Hands out the clauses learned so far if [SolverConfig::learn_first_uip] is set and
+    /// they can be watched, i.e. the search has started and is not about to backtrack
+    ///
+    /// The clauses have to be passed to [Search::add_clause] once they are appended to the
+    /// formula.
+    fn take_learned_for_search(&mut self, config: &SolverConfig) -> Vec<Clause> {
+        if !config.learn_first_uip
+            || matches!(self.state, State::Start | State::Backtrack | State::Done)
+        {
+            return Vec::new();
+        }
+        std::mem::take(&mut self.learned)
+    }
+
+    /// How unsatisfiability follows from a conflict before the first decision
+    fn level_zero_reason(&self, cnf: &Cnf) -> UnsatReason {
+        let empty_conflict = matches!(
+            self.conflict_clauses.last(),
+            Some(&clause_idx) if cnf.clauses[clause_idx].is_empty()
+        );
+        if self.assumptions.is_empty() || empty_conflict {
+            UnsatReason::EmptyClause
+        } else {
+            UnsatReason::Assumptions
+        }
+    }
+
+    /// Performs a single transition of the state machine
+    ///
+    /// Returns the result once the search is finished.
+    fn step(
+        &mut self,
+        cnf: &Cnf,
+        watchedliterals: &mut WatchedLiterals,
+        config: &SolverConfig,
+    ) -> Option<SolveResult> {
+        let result = self.transition(cnf, watchedliterals, config);
+        self.stats.propagations = self.log.propagations;
+        if result.is_some() {
+            self.state = State::Done;
+        }
+        result
+    }
+
+    fn transition(
+        &mut self,
+        cnf: &Cnf,
+        watchedliterals: &mut WatchedLiterals,
+        config: &SolverConfig,
+    ) -> Option<SolveResult> {
+        let dec_levels = &mut self.dec_levels;
+        let initial_assignment = &self.initial_assignment;
+
+        match self.state {
+            State::Start => return self.start(cnf, watchedliterals, config),
+
+            State::CheckCurrentLevel => {
+                // Check for satisfiability
+                let assignment = dec_levels
+                    .last()
+                    .map(|dl| &dl.assignment)
+                    .unwrap_or(initial_assignment);
+                let level = dec_levels.len();
+                let satisfied = &mut self.satisfied_clauses;
+                if check_assignment(cnf, assignment, level, satisfied, &mut self.stats) {
+                    return Some(SolveResult::Sat(assignment.clone()));
+                }
+                self.state = State::AssignNewVar;
+            }
+
+            State::AssignNewVar => {
+                // pick a new variable to set
+                let assignment = dec_levels
+                    .last()
+                    .map(|dl| &dl.assignment)
+                    .unwrap_or(initial_assignment);
+                let var = self.heuristic.pick(cnf, assignment, self.max_var);
+
+                // Check if the assignment is complete, i.e. no variable to be set could be found
+                self.state = match var {
+                    None => {
+                        // Assignment complete, therefore backtrack
+                        State::Backtrack
+                    }
+                    Some(var) => {
+                        // Assignment incomplete, we found a new variable to set
+                        let saved = if config.phase_saving {
+                            self.log.phases.get(&var)
+                        } else {
+                            None
+                        };
+                        let val = match saved.or_else(|| config.initial_phases.get(&var)) {
+                            Some(&val) => val,
+                            None => self
+                                .heuristic
+                                .polarity(cnf, assignment, var)
+                                .unwrap_or_else(|| {
+                                    first_polarity(var, config.polarity, &self.polarity_counts)
+                                }),
+                        };
+                        State::NewDecLevelWithAssignment((var, val))
+                    }
+                };
+            }
+
+            State::Backtrack if config.learn_first_uip && !self.learned.is_empty() => {
+                return self.backjump_to_learned();
+            }
+
+            State::Backtrack => {
+                verbose_print!("Backtracking... ");
+                let from_level = dec_levels.len();
+                let result = backtrack(dec_levels);
+                match result {
+                    BacktrackResult::UnsatisfiableFormula => {
+                        // Return unsat
+                        verbose_println!("Unsatisfiable!");
+                        self.stats.unsat_reason = Some(if from_level == 0 {
+                            self.level_zero_reason(cnf)
+                        } else {
+                            UnsatReason::SearchExhausted
+                        });
+                        self.core = self.assumptions.clone();
+                        return Some(SolveResult::Unsat);
+                    }
+                    BacktrackResult::ContinueWith(new_assignment) => {
+                        // Backtracking did undo multiple decision levels and the resulting decision level had this assignment
+                        // Skip State::ExecAssignment and jump to PropagateAssignment, because the (now) latest
+                        // decision level already has the expected assignment set due to the call to backtrack
+                        verbose_println!("Continuing with dl {}", dec_levels.len() + 1);
+                        self.stats.backtracks += 1;
+                        self.stats.undone_levels += from_level - dec_levels.len();
+                        record(
+                            &mut self.log,
+                            SolveEvent::Backtrack {
+                                from_level,
+                                to_level: dec_levels.len(),
+                            },
+                        );
+                        self.satisfied_clauses.backtrack(dec_levels.len());
+                        self.log.phases.insert(new_assignment.0, new_assignment.1);
+
+                        // The flipped decision level still contains the propagations of the abandoned branch,
+                        // start over from the assignment of the level below
+                        let restored = dec_levels
+                            .len()
+                            .checked_sub(2)
+                            .map(|idx| &dec_levels[idx].assignment)
+                            .unwrap_or(initial_assignment)
+                            .with(new_assignment.0, new_assignment.1);
+                        dec_levels.last_mut().unwrap().assignment = restored;
+
+                        self.state = State::PropagateAssignment(new_assignment);
+                    }
+                }
+            }
+
+            State::NewDecLevelWithAssignment(new_assigned_lit) => {
+                verbose_println!(
+                    "Trying to assign new var {:?} = {:?}",
+                    new_assigned_lit.0,
+                    new_assigned_lit.1
+                );
+                let new_assignment = dec_levels
+                    .last()
+                    .map(|dl| &dl.assignment)
+                    .unwrap_or(initial_assignment)
+                    .with(new_assigned_lit.0, new_assigned_lit.1);
+
+                let new_dl = DecisionLevel {
+                    assignment: new_assignment,
+                    changed_var: new_assigned_lit.0,
+                    flipped: false,
+                };
+                dec_levels.push(new_dl);
+                self.stats.decisions += 1;
+                self.stats.max_decision_level = self.stats.max_decision_level.max(dec_levels.len());
+                record(&mut self.log, SolveEvent::Decision(new_assigned_lit));
+                self.state = State::PropagateAssignment(new_assigned_lit);
+            }
+
+            State::PropagateAssignment(new_assigned_lit) => {
+                verbose_print!("Propagating assignment {:?}: ", new_assigned_lit);
+                // The current/top decision level already has the assignment set
+                // but it needs to be propagated
+                #[cfg(not(feature = "unchecked"))]
+                debug_assert!(matches!(
+                    dec_levels
+                        .last()
+                        .unwrap()
+                        .assignment
+                        .get_lit(new_assigned_lit),
+                    Some(true)
+                ));
+
+                let level = dec_levels.len();
+                let assignment = &mut dec_levels
+                    .last_mut()
+                    .expect("Encountered State::PropagateAssignment without decision level")
+                    .assignment;
+
+                let result = propagate_assignment(
+                    new_assigned_lit,
+                    assignment,
+                    cnf,
+                    watchedliterals,
+                    config.propagation_order,
+                    &mut self.log,
+                    Some((&mut self.satisfied_clauses, level)),
+                );
+
+                match result {
+                    ExecuteAssignmentResult::Unsatisfiable(conflict) => {
+                        // Assignment caused insatisfiability => backtrack
+                        verbose_println!(
+                            "Unsatisfiable, conflict in clause {}.",
+                            conflict.clause_idx
+                        );
+                        self.learn_conflict(cnf, conflict.clause_idx, config);
+                        self.heuristic
+                            .on_conflict(&cnf.clauses[conflict.clause_idx]);
+                        self.state = State::Backtrack
+                    }
+                    ExecuteAssignmentResult::AssignmentDone => {
+                        verbose_println!("Done.");
+                        self.state = State::CheckCurrentLevel;
+                    }
+                }
+            }
+
+            State::Done => panic!("Search is already finished"),
+        }
+
+        None
+    }
+
+    /// Assigns unit clauses and assumptions and propagates them
+    fn start(
+        &mut self,
+        cnf: &Cnf,
+        watchedliterals: &mut WatchedLiterals,
+        config: &SolverConfig,
+    ) -> Option<SolveResult> {
+        if config.trace {
+            self.log.events = Some(Vec::new());
+        }
+
+        // fast checks
+        if let Some(clause_idx) = cnf.clauses.iter().position(|cls| cls.is_empty()) {
+            record(&mut self.log, SolveEvent::Conflict { clause_idx });
+            self.stats.unsat_reason = Some(UnsatReason::EmptyClause);
+            return Some(SolveResult::Unsat);
+        }
+
+        // first get clauses with single literals, they have to be true
+        let mut assignment = match get_assignment_from_single_clauses(cnf, &mut self.log) {
+            Ok(a) => a,
+            Err(conflict) => {
+                verbose_println!("---Conflicting unit clause {}", conflict.clause_idx);
+                self.stats.unsat_reason = Some(UnsatReason::EmptyClause);
+                return Some(SolveResult::Unsat);
+            }
+        };
+
+        // assumptions are fixed just like clauses with a single literal
+        let mut num_assumed = 0;
+        for &lit in &self.assumptions {
+            match assignment.get_lit(lit) {
+                Some(true) => {}
+                Some(false) => {
+                    self.stats.unsat_reason = Some(UnsatReason::Assumptions);
+                    // the opposite was either assumed as well or is a unit clause
+                    self.core = if self.assumptions.contains(&negate(lit)) {
+                        vec![negate(lit), lit]
+                    } else {
+                        vec![lit]
+                    };
+                    return Some(SolveResult::Unsat);
+                }
+                None => {
+                    num_assumed += 1;
+                    assignment.change(lit.0, lit.1);
+                    record(&mut self.log, SolveEvent::Assumption(lit));
+                }
+            }
+        }
+        let assignments_vec = assignment.iter().collect::<Vec<_>>();
+
+        // propagate these
+        for new_literal in assignments_vec {
+            match propagate_assignment(
+                new_literal,
+                &mut assignment,
+                cnf,
+                watchedliterals,
+                config.propagation_order,
+                &mut self.log,
+                Some((&mut self.satisfied_clauses, 0)),
+            ) {
+                ExecuteAssignmentResult::Unsatisfiable(conflict) => {
+                    // Conflict in DL0
+                    verbose_println!("---Conflict in clause {}", conflict.clause_idx);
+                    self.learn_conflict(cnf, conflict.clause_idx, config);
+                    self.stats.unsat_reason = Some(self.level_zero_reason(cnf));
+                    // nothing was decided, the learned clause only negates assumptions
+                    let learned = self.learned.last().unwrap();
+                    self.core = learned.literals().map(negate).collect();
+                    return Some(SolveResult::Unsat);
+                }
+                ExecuteAssignmentResult::AssignmentDone => {
+                    // left intentionally empty
+                }
+            }
+        }
+
+        // after propagation this assignment contains all clauses with a single literal and their propagations
+        self.stats.preprocess.units = assignment.iter().count() - num_assumed;
+        self.initial_assignment = assignment;
+        verbose_println!("---Initial: {:?}", self.initial_assignment);
+
+        self.max_var = cnf.highest_var();
+        self.heuristic = match (self.custom_heuristic.take(), &config.heuristic) {
+            (Some(heuristic), _) => heuristic,
+            (None, Heuristic::Numeric) => match config.shuffle_seed {
+                None => Box::new(NumericOrder),
+                Some(seed) => Box::new(StaticOrder::shuffled(self.max_var, seed)),
+            },
+            (None, Heuristic::Static(order)) => Box::new(StaticOrder(order.clone())),
+            (None, Heuristic::Greedy) => Box::new(Greedy::default()),
+            (None, Heuristic::Vsids(decay)) => Box::new(Vsids::new(*decay)),
+        };
+        if let Polarity::OccurrenceBased { .. } = config.polarity {
+            self.polarity_counts = cnf.polarity_counts();
+        }
+
+        // checked like any other level, a solver reduces the parity constraints first
+        self.state = State::CheckCurrentLevel;
+        None
+    }
+
+    /// The decided literals of all decision levels, the flipped ones with their new value
+    fn decisions(&self) -> impl Iterator<Item = LiteralTpl> + '_ {
+        self.dec_levels.iter().map(DecisionLevel::decision)
+    }
+
+    /// The decision level the given assigned variable was assigned at
+    fn level_of(&self, var: Var) -> usize {
+        if self.initial_assignment.get(var).is_some() {
+            return 0;
+        }
+        self.dec_levels
+            .iter()
+            .position(|dl| dl.assignment.get(var).is_some())
+            .map_or(0, |idx| idx + 1)
+    }
+
+    /// Watches the clause with the given index, which was just appended to the formula
+    ///
+    /// While the search is running, the clause is propagated at the current decision level
+    /// if it is unit and causes a backtrack if it is falsified.
+    fn add_clause(
+        &mut self,
+        cnf: &Cnf,
+        watchedliterals: &mut WatchedLiterals,
+        clause_idx: usize,
+        config: &SolverConfig,
+    ) {
+        let clause = &cnf.clauses[clause_idx];
+        if matches!(self.state, State::Start | State::Done) {
+            // nothing is assigned which the watches have to respect, an empty clause is
+            // found by the next start
+            watchedliterals.add_clause(clause);
+            return;
+        }
+        if clause.is_empty() {
+            // no decision can satisfy it, so the search ends with the next backtrack
+            watchedliterals.add_clause(clause);
+            record(&mut self.log, SolveEvent::Conflict { clause_idx });
+            self.learn_conflict(cnf, clause_idx, config);
+            self.dec_levels.clear();
+            self.state = State::Backtrack;
+            return;
+        }
+
+        // watch the literals which are not false, then the false ones assigned last
+        let assignment = self.current_assignment();
+        let mut literals = clause
+            .literals()
+            .map(|lit| match assignment.get_lit(lit) {
+                Some(false) => (Some(std::cmp::Reverse(self.level_of(lit.0))), lit),
+                _ => (None, lit),
+            })
+            .collect::<Vec<_>>();
+        literals.sort_by_key(|&(false_at, _)| false_at);
+        match literals.as_slice() {
+            [(_, lit0), (_, lit1), ..] => watchedliterals.add_clause_watching(*lit0, *lit1),
+            _ => watchedliterals.add_clause(clause),
+        }
+
+        if self.state == State::Backtrack {
+            // the current assignment is abandoned anyway
+            return;
+        }
+        let open = literals
+            .iter()
+            .filter(|(false_at, _)| false_at.is_none())
+            .map(|&(_, lit)| lit)
+            .collect::<Vec<_>>();
+        let unit = match open.as_slice() {
+            [] => {
+                record(&mut self.log, SolveEvent::Conflict { clause_idx });
+                self.learn_conflict(cnf, clause_idx, config);
+                self.state = State::Backtrack;
+                return;
+            }
+            [lit] if assignment.get_lit(*lit).is_none() => *lit,
+            _ => return,
+        };
+
+        let level = self.dec_levels.len();
+        let assignment = match self.dec_levels.last_mut() {
+            Some(dl) => &mut dl.assignment,
+            None => &mut self.initial_assignment,
+        };
+        assignment.change(unit.0, unit.1);
+        record(
+            &mut self.log,
+            SolveEvent::Propagation {
+                lit: unit,
+                reason_clause: clause_idx,
+            },
+        );
+        let result = propagate_assignment(
+            unit,
+            assignment,
+            cnf,
+            watchedliterals,
+            config.propagation_order,
+            &mut self.log,
+            Some((&mut self.satisfied_clauses, level)),
+        );
+
+        match result {
+            ExecuteAssignmentResult::Unsatisfiable(conflict) => {
+                self.learn_conflict(cnf, conflict.clause_idx, config);
+                self.state = State::Backtrack;
+            }
+            ExecuteAssignmentResult::AssignmentDone => {
+                // a pending propagation stays pending, otherwise the grown assignment is
+                // checked again and a pending decision may have been propagated already
+                if !matches!(self.state, State::PropagateAssignment(_)) {
+                    self.state = State::CheckCurrentLevel;
+                }
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn check_assignment(
+    cnf: &Cnf,
+    a: &Assignment,
+    level: usize,
+    satisfied_clauses: &mut SatisfiedClauses,
+    stats: &mut Stats,
+) -> bool {
+    let result = satisfied_clauses.check(cnf, a, level);
+    verbose_println!("...Checking {:?}: {}", a, result);
+    stats.tries += 1;
+    result
+}
+
+/// Remembers which clauses are satisfied by the assignment of a decision level
+///
+/// The assignment of a decision level extends the assignments of all levels below, so a
+/// clause satisfied at some level stays satisfied in the levels above it until the search
+/// backtracks below that level. Checking the assignment of a level thus only needs to look
+/// at the clauses not marked yet.
+///
+/// The propagation marks the clauses containing every literal it assigns, so usually all
+/// satisfied clauses are marked before the check, and the propagation can stop marking as
+/// soon as the last clause is satisfied.
+#[derive(Debug, Default)]
+struct SatisfiedClauses {
+    /// The lowest decision level each clause is known to be satisfied at
+    levels: Vec<Option<usize>>,
+    /// The number of marked clauses
+    num_satisfied: usize,
+    /// The indices of the clauses containing each literal
+    occurrences: HashMap<LiteralTpl, Vec<usize>>,
+}
+
+impl SatisfiedClauses {
+    /// Returns whether the assignment of the given decision level satisfies every clause
+    ///
+    /// Stops at the first clause which is not satisfied, like [Cnf::is_satisfied].
+    fn check(&mut self, cnf: &Cnf, assignment: &Assignment, level: usize) -> bool {
+        self.index_new_clauses(cnf);
+
+        let num_satisfied = &mut self.num_satisfied;
+        cnf.clauses
+            .iter()
+            .zip(self.levels.iter_mut())
+            .filter(|(_, satisfied_at)| satisfied_at.is_none())
+            .all(|(clause, satisfied_at)| {
+                let satisfied = clause.is_satisfied(assignment);
+                if satisfied {
+                    *satisfied_at = Some(level);
+                    *num_satisfied += 1;
+                }
+                satisfied
+            })
+    }
+
+    /// Marks the clauses containing the literal, which was assigned at the given decision
+    /// level, and returns whether all clauses are satisfied now
+    fn assign(&mut self, cnf: &Cnf, lit: LiteralTpl, level: usize) -> bool {
+        self.index_new_clauses(cnf);
+
+        for &clause_idx in self.occurrences.get(&lit).into_iter().flatten() {
+            let satisfied_at = &mut self.levels[clause_idx];
+            if satisfied_at.is_none() {
+                *satisfied_at = Some(level);
+                self.num_satisfied += 1;
+            }
+        }
+        self.num_satisfied == self.levels.len()
+    }
+
+    /// Adds the clauses added to the formula since the last call to the occurrences
+    ///
+    /// Clauses are only appended during a search, so the indices of the known ones stay
+    /// valid.
+    fn index_new_clauses(&mut self, cnf: &Cnf) {
+        for clause_idx in self.levels.len()..cnf.clauses.len() {
+            for lit in cnf.clauses[clause_idx].literals() {
+                self.occurrences.entry(lit).or_default().push(clause_idx);
+            }
+            self.levels.push(None);
+        }
+    }
+
+    /// Forgets all marks of the given decision level and the levels above
+    ///
+    /// Called when the assignment of the given level changes.
+    fn backtrack(&mut self, level: usize) {
+        for satisfied_at in &mut self.levels {
+            if matches!(*satisfied_at, Some(at) if at >= level) {
+                *satisfied_at = None;
+                self.num_satisfied -= 1;
+            }
+        }
+    }
+}
+
+/// Returns the value the given variable is tried with first
+fn first_polarity(
+    var: Var,
+    polarity: Polarity,
+    polarity_counts: &HashMap<Var, (usize, usize)>,
+) -> bool {
+    match polarity {
+        Polarity::False => false,
+        Polarity::True => true,
+        Polarity::OccurrenceBased { prefer_frequent } => {
+            let (positive, negative) = polarity_counts.get(&var).copied().unwrap_or_default();
+            if prefer_frequent {
+                positive > negative
+            } else {
+                positive < negative
+            }
+        }
+    }
+}
+
+/// Propagates a decision (new_literal) in the given assignment using the watched literals
+///
+/// The assignment must already contain the new_literal and resulting propagations will mutate it
+/// The watched literals are used for propagations and are updated accordingly
+///
+/// Returns AssignmentDone if the new_literal and all propagations are now reflected in the assignment
+/// and watched literals without encountering a conflict
+/// Returns Unsatisfiable if the new_literal or resulting propagations caused a conflict. In this case
+/// the current decision level should be dropped. The conflict names the clause which became unsatisfiable
+///
+/// If the satisfied clauses of a search are given with the current decision level, every handled
+/// literal is marked there. Once all clauses are satisfied, no conflict can follow and the marking
+/// stops, the watched literals of the remaining literals are still updated.
+fn propagate_assignment(
+    new_literal: LiteralTpl,
+    assignment: &mut Assignment,
+    cnf: &Cnf,
+    watchedliterals: &mut WatchedLiterals,
+    order: PropagationOrder,
+    log: &mut SearchLog,
+    mut satisfied: Option<(&mut SatisfiedClauses, usize)>,
+) -> ExecuteAssignmentResult {
+    #[cfg(not(feature = "unchecked"))]
+    debug_assert!(matches!(assignment.get_lit(new_literal), Some(true)));
+
+    // Vars to propagate, with the index of the clause implying them
+    let mut propagations = VecDeque::new();
+    propagations.push_back((new_literal, 0));
+
+    loop {
+        let next = match order {
+            PropagationOrder::Fifo => propagations.pop_front(),
+            PropagationOrder::Lifo => propagations.pop_back(),
+            PropagationOrder::ClauseIndex => propagations
+                .iter()
+                .enumerate()
+                .min_by_key(|&(_, &(_, reason_clause))| reason_clause)
+                .map(|(pos, _)| pos)
+                .and_then(|pos| propagations.remove(pos)),
+        };
+        let prop = match next {
+            Some((prop, _)) => prop,
+            None => break,
+        };
+        if let Some((satisfied_clauses, level)) = &mut satisfied {
+            if satisfied_clauses.assign(cnf, prop, *level) {
+                // no conflict can follow, but the watches are still updated for clauses
+                // added on this level later
+                satisfied = None;
+            }
+        }
+
+        let result = watchedliterals.update(cnf, assignment, prop);
+        match result {
+            UpdateResult::Unsatisfiable { clause_idx } => {
+                // Unsatisfiable
+                record(log, SolveEvent::Conflict { clause_idx });
+                return ExecuteAssignmentResult::Unsatisfiable(Conflict {
+                    clause_idx,
+                    literal: Some(prop),
+                });
+            }
+
+            UpdateResult::Satisfiable {
+                propagations: new_propagations,
+            } => {
+                // Assignment of propagation successful, store all new propagations
+                for ((prop_var, prop_val), reason_clause) in new_propagations {
+                    match assignment.get_lit((prop_var, prop_val)) {
+                        Some(true) => {
+                            // Already propagated by another clause
+                        }
+                        Some(false) => {
+                            // Another clause propagated the opposite literal in the same update.
+                            // This clause keeps watching the literal, so handling the queued
+                            // opposite literal reports it as unsatisfiable
+                        }
+                        None => {
+                            propagations.push_back(((prop_var, prop_val), reason_clause));
+                            assignment.change(prop_var, prop_val);
+                            record(
+                                log,
+                                SolveEvent::Propagation {
+                                    lit: (prop_var, prop_val),
+                                    reason_clause,
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // All propagations handled without running into unsatisfiability
+    ExecuteAssignmentResult::AssignmentDone
+}
+
+#[must_use]
+enum ExecuteAssignmentResult {
+    Unsatisfiable(Conflict),
+    AssignmentDone,
+}
+
+/// Calculates an assignment satisfying all clauses with only a single literal
+///
+/// @return the conflict, if there are two conflicting clauses with a single literal.
+/// It names the later of the two clauses, the opposite literal being the conflicting one
+fn get_assignment_from_single_clauses(
+    cnf: &Cnf,
+    log: &mut SearchLog,
+) -> Result<Assignment, Conflict> {
+    let mut assignment = Assignment::new();
+
+    for (clause_idx, clause) in cnf.clauses.iter().enumerate() {
+        let mut literals = clause.literals();
+        if let (Some(lit), None) = (literals.next(), literals.next()) {
+            // Clause only contains one literal
+            match assignment.get_lit(lit) {
+                Some(true) => {
+                    // Already satisfying
+                }
+                Some(false) => {
+                    // Clause unsat
+                    record(log, SolveEvent::Conflict { clause_idx });
+                    return Err(Conflict {
+                        clause_idx,
+                        literal: Some(negate(lit)),
+                    });
+                }
+                None => {
+                    assignment.change(lit.0, lit.1);
+                    record(
+                        log,
+                        SolveEvent::Propagation {
+                            lit,
+                            reason_clause: clause_idx,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(assignment)
+}
+
+/// Everything a search records about its steps
+#[derive(Debug, Default)]
+struct SearchLog {
+    /// The events, if [SolverConfig::trace] is enabled
+    events: Option<Vec<SolveEvent>>,
+    /// The index of the clause each propagated variable was implied by
+    ///
+    /// Decided and assumed variables have no entry. The entries of variables unassigned by
+    /// a backtrack are only replaced once the variable is assigned again.
+    reasons: HashMap<Var, usize>,
+    /// The number of the assignment which last assigned each variable
+    ///
+    /// A flipped decision keeps the number of the original decision, which still comes before
+    /// the propagations of its level.
+    positions: HashMap<Var, usize>,
+    /// The number of assignments recorded so far
+    num_assignments: usize,
+    /// The number of propagations recorded so far, see [Stats::propagations]
+    propagations: usize,
+    /// The value each variable was assigned last, see [SolverConfig::phase_saving]
+    phases: HashMap<Var, bool>,
+}
+
+/// Notes the reason, position and phase of an assignment and appends the event to the trace if tracing is enabled
+fn record(log: &mut SearchLog, event: SolveEvent) {
+    let assigned = match event {
+        SolveEvent::Propagation { lit, reason_clause } => {
+            log.reasons.insert(lit.0, reason_clause);
+            log.propagations += 1;
+            Some(lit)
+        }
+        SolveEvent::Decision(lit) | SolveEvent::Assumption(lit) => {
+            log.reasons.remove(&lit.0);
+            Some(lit)
+        }
+        _ => None,
+    };
+    if let Some(lit) = assigned {
+        log.positions.insert(lit.0, log.num_assignments);
+        log.num_assignments += 1;
+        log.phases.insert(lit.0, lit.1);
+    }
+    if let Some(events) = &mut log.events {
+        events.push(event);
+    }
+}
+
+/// Undoes the decision levels above the given one without flipping any decision
+///
+/// The remaining levels keep their assignments, and the watched literals stay valid as
+/// they do for [backtrack].
+fn backjump(dec_levels: &mut Vec<DecisionLevel>, level: usize) {
+    debug_assert!(level <= dec_levels.len());
+    dec_levels.truncate(level);
+}
+
+/// Backtracks the given decision levels,
+/// until a new possible assignment is found or every assignment has been tried
+fn backtrack(dec_levels: &mut Vec<DecisionLevel>) -> BacktrackResult {
+    loop {
+        match dec_levels.last_mut() {
+            Some(dl) => {
+                if !dl.flipped {
+                    // This dl has not been flipped yet, so try it out
+                    dl.flipped = true;
+                    let old_assignment = dl.assignment.get(dl.changed_var).unwrap();
+                    let new_assignment = (dl.changed_var, !old_assignment);
+                    dl.assignment.change(new_assignment.0, new_assignment.1);
+                    return BacktrackResult::ContinueWith(new_assignment);
+                } else {
+                    // This dl has already been flipped, backtrack further
+                    dec_levels.pop();
+                    continue;
+                }
+            }
+            None => {
+                // We backtracked beyond all decision-levels
+                // this means we tried all assignments
+                return BacktrackResult::UnsatisfiableFormula;
+            }
+        }
+    }
+}
+#[derive(Debug, PartialEq, Eq)]
+enum BacktrackResult {
+    UnsatisfiableFormula,
+    ContinueWith(LiteralTpl),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cnf::XorClause;
+    use crate::input::parse_cnf_from_str;
+    use crate::rng::{random_cnf, Rng};
+
+    use super::*;
+
+    #[test]
+    fn test_sat_sanity() {
+        assert!(is_satisfiable(&Cnf::new()).0);
+        assert!(!is_satisfiable(&parse_cnf_from_str("false").unwrap()).0);
+        assert!(!is_satisfiable(&parse_cnf_from_str("1\nfalse").unwrap()).0);
+        assert!(!is_satisfiable(&parse_cnf_from_str("-1\nfalse").unwrap()).0);
+        assert!(!is_satisfiable(&parse_cnf_from_str("false\n1").unwrap()).0);
+        assert!(!is_satisfiable(&parse_cnf_from_str("false\n-1").unwrap()).0);
+        assert!(is_satisfiable(&parse_cnf_from_str("1").unwrap()).0);
+    }
+
+    #[test]
+    fn test_sat_deep_dl() {
+        assert!(is_satisfiable(&parse_cnf_from_str("1 2 3\n4 5 6\n7 8 9").unwrap()).0);
+        assert!(is_satisfiable(&parse_cnf_from_str("-1 -2 -3\n-4 -5 -6\n-7 -8 -9").unwrap()).0);
+        assert!(is_satisfiable(&parse_cnf_from_str("-1 -2 -3 4\n1\n2\n3").unwrap()).0);
+        assert!(!is_satisfiable(&parse_cnf_from_str("1 2 3\n-1\n-2\n-3").unwrap()).0);
+        assert!(!is_satisfiable(&parse_cnf_from_str("-1 2 -3\n1\n-2\n3").unwrap()).0);
+    }
+
+    #[test]
+    fn test_is_satisfiable_with_scratch() {
+        let inputs = [
+            "1 2 3\n4 5 6\n7 8 9",
+            "1 2 3\n-1\n-2\n-3",
+            "false",
+            "-1 -2 -3 4\n1\n2\n3",
+            "",
+            "1 2\n-1 2\n1 -2\n-1 -2",
+            "1 2 3\n-2 -3\n-3 2\n-1",
+        ];
+
+        let mut scratch = SolverScratch::default();
+        for input in inputs.iter() {
+            let cnf = parse_cnf_from_str(input).unwrap();
+            let (expected, _) = is_satisfiable(&cnf);
+            assert_eq!(is_satisfiable_with_scratch(&cnf, &mut scratch).0, expected);
+        }
+    }
+
+    #[test]
+    fn test_tautologies() {
+        let tautology = Clause::from_literals(&[(1, true), (1, false)]);
+        let cnf = Cnf::new_with(vec![
+            tautology.clone(),
+            Clause::from_literals(&[(1, false)]),
+        ]);
+        assert!(is_satisfiable(&cnf).0);
+        assert!(is_satisfiable_with_heuristic(&cnf, NumericOrder).0);
+
+        // a tautology may watch both literals of a variable
+        let mut rng = Rng::new(759);
+        for _ in 0..200 {
+            let mut cnf = Cnf::new();
+            for _ in 0..rng.below(20) {
+                let literals = (0..2 + rng.below(3))
+                    .map(|_| (rng.below(6) as Var + 1, rng.below(2) == 0))
+                    .collect::<Vec<_>>();
+                cnf.clauses.push(Clause::from_literals(&literals));
+            }
+            let expected = (0..1u32 << 6).any(|bits| {
+                let assignment = Assignment::new()
+                    .with_all((1..=6).map(|var| (var, bits & 1 << (var - 1) != 0)));
+                cnf.is_satisfied(&assignment)
+            });
+
+            assert_eq!(is_satisfiable(&cnf).0, expected, "{:?}", cnf);
+            match Solver::new(cnf.clone()).solve() {
+                SolveResult::Sat(model) => assert!(expected && cnf.is_satisfied(&model)),
+                SolveResult::Unsat => assert!(!expected, "{:?}", cnf),
+                SolveResult::Unknown => panic!("the search gave up"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_sat() {
+        assert!(is_satisfiable(&parse_cnf_from_str("1 2 3\n-2 -3\n-3\n-1").unwrap()).0);
+        assert!(is_satisfiable(&parse_cnf_from_str("1 2 3 4\n-2 -3\n-3\n-1").unwrap()).0);
+        assert!(is_satisfiable(&parse_cnf_from_str("1 2 3\n-2 -3\n-3 2\n-1").unwrap()).0);
+    }
+
+    #[test]
+    fn test_sat_large() {
+        let input = r"-02 -08 -10 +11 -12 -13 -14 +17 -19
+        -01 -02 -03 -04 -05 -06 +07 -08 +09 +10 -11 -12 -13 +14 -15 -16 +17 -18 -19
+        -15
+        -01 -02 -06 -09 +10 +14 -15 -16 +17
+        +01 +02 -03 -04 +05 -06 -07 +09 -10 -11 +12 -13 -14 -15 +16 +17 -18 -19
+        -01 +02 -04 -06 -07 +08 -09 +12 +13 +15 -16 -19
+        +01 -02 +03 +04 -05 +06 +07 -08 +09 -10 +12 +13 +14 +15 -17 +18 +19
+        +01 -02 -03 -04 +05 +06 -07 -08 -09 +10 +11 +12 +13 +14 -15 -16 -17 -18 +19
+        +01 +15 +18
+        -09 -12 +17
+        -01 +09 +19
+        -06 +09 +17
+        +01 -11 +14
+        +02 +05 -07 -10 +14
+        +02 +03 +04 -05 -06 -07 -08 -09 +10 +14 -15 +16 +17 +19
+        +02 -03 +04 +08 +09 +12 +15 -17
+        -01 -02 +03 +04 +05 +06 -07 +08 -09 +10 +11 +12 -13 -14 +15 +16 +17 +18 -19
+        -01 -02 -03 +04 -05 +07 +08 -09 +10 -12 -13 -15 +18 +19
+        -13 -16 +18
+        -05 +14
+        -01 +02 +03 -04 -05 +06 -07 +08 +09 +10 +11 -12 +13 +14 +15 +16 +17 -18
+        +01 +02 -03 +04 +05 +06 -07 +08 +10 +11 -12 -13 +14 +15 +18 -19
+        +01 +04 -07 +10 -11 +12 -18 +19
+        -01 -06 -08 +11 -14 -18
+        -01 +05 -06 -07 +08 -12 +13 +14 +15 +16 -19
+        +01 +02 +03 -04 +05 -08 +09 -11 -12 +13 -14 -15 +16 -17 -18 -19
+        -02 +05 +06 -08 +11 +14 -17 -18 +19
+        -01 +02 -03 +04 +05 -06 -07 +08 +09 +11 -13 +14 +15 +16 +18 -19
+        +01 +02 +04 +05 -06 +08 -09 +14 +15 -16 +17
+        -04 +09 -11 -14 +17 -18
+        -03 -08 +09 +15 -18
+        +01 -02 +04 -05 -06 +07 +08 -11 +12 +13 +14 +15 +16 -17 +19
+        -02 +03 -04 +05 -06 -07 +08 -10 +11 -12 -13 +14 +15 -18 -19
+        +01 +02 -03 +04 +05 +06 -07 +08 +09 +10 -11 -12 -13 -14 +15 -16 +18 -19
+        +03 +04 +05 -07 +09 +10 -12 -13 +15 -16 +19
+        -01 +02 +04 -05 +06 -07 +08 -09 -11 +12 -13 +16 +17 -18 -19
+        +07 -18
+        +01 +02 +03 -04 +05 +06 -07 -09 -10 +11 -13 +14 +15 -16 -17 -18 +19
+        +01 -02 +05 +09 +12 +14 -15
+        +08 +16
+        +02 -04 -06 -07 +08 +09 +10 +13 +14 +16 +17 -19
+        +01 +03 +06 +10 -13 +16 -18
+        +01 +02 -03 +04 -05 -06 +07 -08 +09 +10 -11 -12 -13 +14 -15 -16 -17 -18
+        +01 +02 +03 -04 +05 +06 +07 +08 -09 +10 -11 +12 +13 +14 -15 +16 +17 +18
+        +02 +06 -10 -11 +13 +14 +15 +19
+        -01 +02 +03 +04 -05 -06 +07 +08 +10 +11 -12 +13 +14 +15 +16 +18 +19
+        +01 -05 -06 +07 +08 -09 -10 -11 +12 -13 -14 +15 +16 +17 -18 -19
+        -02 +03 -04 -05 -06 -07 +10 +13 -14 -18 +19
+        -01 -02 +03 +05 +06 -07 -08 -09 +10 -12 -13 -14 +15 +16 +18 -19
+        +01 +03 -04 +05 -08 -09 +11 -12 +13 -14 +15 -16 -17 +19
+        -04 -12 +19
+        -03 -11 +12
+        +01 -07 -19
+        -01 +10 -11 +15 +18
+        -01 -03 +04 -05 +07 -08 +11 -12 +14 +15 +16 +18
+        +01 -02 +03 +08 -10 +11 -14 +16
+        +02 +07 -08 +10 +15 +17
+        -01 +05 +07 -08 -09 +12 -15 +18
+        -07 +18
+        -01 -02 -03 -04 -05 -06 -07 -08 -10 -12 +15 +16 +17 +19
+        +11
+        -02 -04 +06 +08 -12 -16 -18
+        -01 +02 +03 -04 +05 +06 +07 -09 -10 +11 +12 +16 -17 +19
+        +07 +08 -12
+        -02 -11 +15
+        +02 -03 +05 +06 -09 -10 +11 -14 +15 +17 -18 +19
+        +01 -02 +04 -05 -06 +07 -08 -09 +10 -12 +14 -15 +16 -17 +18 +19
+        -01 +02 -03 +04 +05 -06 -07 +08 -09 +10 +11 +12 -13 +14 -15 +16 -17 +18 +19"
+            .trim_start();
+
+        let cnf = parse_cnf_from_str(input).unwrap();
+        // e.g.: -1 -2 -3 -4 -5 -6 7 -8 -9 -10 11 12 -13 14 -15 16 -17 18 -19
+        assert!(is_satisfiable(&cnf).0);
+    }
+
+    #[test]
+    fn test_sat_flipped_level_drops_old_propagations() {
+        // Deciding 2 = false propagates both 3 and -3, the flipped level must not keep either
+        assert!(
+            is_satisfiable(&parse_cnf_from_str("1 2 3\n-2 -3\n-3 2\n-1\n4 5\n-4 5").unwrap()).0
+        );
+    }
+
+    #[test]
+    fn test_solver_assumptions() {
+        let mut solver = Solver::new(parse_cnf_from_str("1 2\n-1 3").unwrap());
+
+        assert!(matches!(
+            solver.solve_under_assumptions(&[(2, false)]),
+            SolveResult::Sat(a) if a.get(1) == Some(true) && a.get(3) == Some(true)
+        ));
+        assert_eq!(
+            solver.solve_under_assumptions(&[(2, false), (3, false)]),
+            SolveResult::Unsat
+        );
+        assert_eq!(
+            solver.solve_under_assumptions(&[(1, true), (1, false)]),
+            SolveResult::Unsat
+        );
+        // assumptions do not stick
+        assert!(solver.solve().is_sat());
+    }
+
+    #[test]
+    fn test_solve_assuming() {
+        let cnf = parse_cnf_from_str("1 2\n-1 3\n-3 4").unwrap();
+        assert!(matches!(
+            solve_assuming(&cnf, &[(2, false)]),
+            SolveResult::Sat(a) if a.get(1) == Some(true) && a.get(4) == Some(true)
+        ));
+        assert_eq!(
+            solve_assuming(&cnf, &[(2, false), (4, false)]),
+            SolveResult::Unsat
+        );
+        assert_eq!(
+            solve_assuming(&cnf, &[(1, true), (1, false)]),
+            SolveResult::Unsat
+        );
+        assert!(solve_assuming(&cnf, &[]).is_sat());
+
+        let mut rng = Rng::new(765);
+        for _ in 0..30 {
+            let cnf = random_cnf(&mut rng, 8, 30, 3..=3);
+            let assumptions = [(1, rng.below(2) == 0), (2, rng.below(2) == 0)];
+
+            let mut restricted = cnf.clone();
+            for &lit in &assumptions {
+                restricted.clauses.push(Clause::from_literals(&[lit]));
+            }
+            let expected = is_satisfiable(&restricted).0;
+            match solve_assuming(&cnf, &assumptions) {
+                SolveResult::Sat(model) => assert!(expected && restricted.is_satisfied(&model)),
+                SolveResult::Unsat => assert!(!expected),
+                SolveResult::Unknown => panic!("the search gave up"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_with_core() {
+        let cnf = parse_cnf_from_str("1 2\n-1 3\n-2 3\n4 5").unwrap();
+        assert_eq!(
+            solve_with_core(&cnf, &[(4, true), (3, false), (5, true)]),
+            Err(vec![(3, false)])
+        );
+        assert!(matches!(
+            solve_with_core(&cnf, &[(3, true)]),
+            Ok(a) if a.get(3) == Some(true)
+        ));
+        assert_eq!(
+            solve_with_core(&cnf, &[(4, true), (1, true), (1, false)]),
+            Err(vec![(1, true), (1, false)])
+        );
+        assert_eq!(
+            solve_with_core(
+                &parse_cnf_from_str("1\n-1 2").unwrap(),
+                &[(3, true), (2, false)]
+            ),
+            Err(vec![(2, false)])
+        );
+        assert_eq!(
+            solve_with_core(&parse_cnf_from_str("1 2\n1 -2\n-1").unwrap(), &[(3, true)]),
+            Err(vec![])
+        );
+
+        let mut rng = Rng::new(766);
+        for _ in 0..50 {
+            let cnf = random_cnf(&mut rng, 8, 30, 3..=3);
+            let assumptions = (1..=4)
+                .map(|var| (var, rng.below(2) == 0))
+                .collect::<Vec<_>>();
+
+            match solve_with_core(&cnf, &assumptions) {
+                Ok(model) => assert!(cnf.is_satisfied(&model)),
+                Err(core) => {
+                    assert!(core.iter().all(|lit| assumptions.contains(lit)));
+                    assert_eq!(solve_assuming(&cnf, &core), SolveResult::Unsat);
+                }
+            }
+        }
+    }
+
+    /// The formula that `holes + 1` pigeons sit in `holes` holes, no two in the same one
+    fn pigeonhole(holes: Var) -> Cnf {
+        let var = |pigeon: Var, hole: Var| pigeon * holes + hole + 1;
+        let mut cnf = Cnf::new();
+        for pigeon in 0..=holes {
+            let literals = (0..holes)
+                .map(|hole| (var(pigeon, hole), true))
+                .collect::<Vec<_>>();
+            cnf.clauses.push(Clause::from_literals(&literals));
+        }
+        for hole in 0..holes {
+            for first in 0..=holes {
+                for second in first + 1..=holes {
+                    cnf.clauses.push(Clause::from_literals(&[
+                        (var(first, hole), false),
+                        (var(second, hole), false),
+                    ]));
+                }
+            }
+        }
+        cnf
+    }
+
+    #[test]
+    fn test_solve_with_timeout() {
+        let (result, stats) = solve_with_timeout(&pigeonhole(8), Duration::ZERO);
+        assert_eq!(result, SolveResult::Unknown);
+        assert_eq!(stats.unsat_reason, None);
+        assert!(stats.tries > 0 && stats.backtracks > 0);
+
+        let (result, _) = solve_with_timeout(&pigeonhole(3), Duration::from_secs(60));
+        assert_eq!(result, SolveResult::Unsat);
+        let cnf = parse_cnf_from_str("1 2\n-1 3").unwrap();
+        assert!(matches!(
+            solve_with_timeout(&cnf, Duration::ZERO).0,
+            SolveResult::Sat(model) if cnf.is_satisfied(&model)
+        ));
+    }
+
+    #[test]
+    fn test_solve_cancellable() {
+        let cancel = AtomicBool::new(true);
+        let (result, stats) = solve_cancellable(&pigeonhole(8), &cancel);
+        assert_eq!(result, SolveResult::Unknown);
+        assert!(stats.tries > 0);
+
+        let cnf = parse_cnf_from_str("1 2\n-1 3").unwrap();
+        assert!(solve_cancellable(&cnf, &cancel).0.is_sat());
+
+        let cancel = AtomicBool::new(false);
+        assert_eq!(
+            solve_cancellable(&pigeonhole(3), &cancel).0,
+            SolveResult::Unsat
+        );
+
+        // cancelled from another thread while searching
+        std::thread::scope(|scope| {
+            let search = scope.spawn(|| solve_cancellable(&pigeonhole(10), &cancel).0);
+            std::thread::sleep(Duration::from_millis(10));
+            cancel.store(true, Ordering::Relaxed);
+            assert_eq!(search.join().unwrap(), SolveResult::Unknown);
+        });
+    }
+
+    #[test]
+    fn test_solve_portfolio() {
+        let (result, stats) = solve_portfolio(&pigeonhole(4), 4);
+        assert_eq!(result, SolveResult::Unsat);
+        assert!(stats.winner.unwrap() < 4);
+        assert_eq!(solve_portfolio(&Cnf::new(), 0).1.winner, Some(0));
+
+        let mut rng = Rng::new(771);
+        for _ in 0..20 {
+            let cnf = random_cnf(&mut rng, 8, 30, 3..=3);
+
+            let expected = is_satisfiable(&cnf).0;
+            match solve_portfolio(&cnf, 3).0 {
+                SolveResult::Sat(model) => assert!(expected && cnf.is_satisfied(&model)),
+                SolveResult::Unsat => assert!(!expected),
+                SolveResult::Unknown => panic!("the search gave up"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_solver_entails() {
+        let mut solver = Solver::new(parse_cnf_from_str("1 2\n-1 2\n-2 3 4").unwrap());
+        assert!(solver.entails((2, true)));
+        assert!(!solver.entails((2, false)));
+        assert!(!solver.entails((1, true)));
+        assert!(!solver.entails((1, false)));
+        assert!(!solver.entails((3, true)));
+
+        let mut solver = Solver::new(parse_cnf_from_str("1\n-1").unwrap());
+        assert!(solver.entails((5, true)));
+        assert!(solver.entails((5, false)));
+    }
+
+    #[test]
+    fn test_solver_remove_clause() {
+        let mut solver = Solver::new(parse_cnf_from_str("1 2\n-1\n-2\n3 -1").unwrap());
+        assert!(!solver.solve().is_sat());
+
+        let removed = solver.remove_clause(2);
+        assert_eq!(removed, parse_cnf_from_str("-2").unwrap().clauses[0]);
+        assert_eq!(solver.cnf().clauses.len(), 3);
+        match solver.solve() {
+            SolveResult::Sat(model) => {
+                assert_eq!(model.get(1), Some(false));
+                assert_eq!(model.get(2), Some(true));
+            }
+            _ => panic!("formula is satisfiable after the removal"),
+        }
+
+        // the watches of the shifted clause still work
+        solver.remove_clause(0);
+        assert!(solver.entails((1, false)));
+        assert!(!solver.solve_under_assumptions(&[(1, true)]).is_sat());
+    }
+
+    #[test]
+    fn test_solver_add_clause() {
+        let mut cnf = pigeonhole(3);
+        let first = cnf.clauses.remove(0);
+        let mut solver = Solver::new(cnf);
+        assert!(solver.solve().is_sat());
+        let learned = solver.learned_clauses().cloned().collect::<Vec<_>>();
+
+        solver.add_clause(&first.literals().collect::<Vec<_>>());
+        assert_eq!(
+            solver.cnf().clauses[solver.num_original_clauses() - 1],
+            first
+        );
+        assert_eq!(
+            solver.learned_clauses().cloned().collect::<Vec<_>>(),
+            learned
+        );
+        assert!(!solver.solve().is_sat());
+
+        // a running search starts over with the new clauses
+        let mut solver = Solver::new(parse_cnf_from_str("1 2\n-1 3").unwrap());
+        while solver.current_decision_level() < 1 {
+            assert!(solver.step().is_none());
+        }
+        solver.add_clause(&[(3, false)]);
+        solver.add_clause(&[(2, false)]);
+        assert_eq!(solver.current_decision_level(), 0);
+        assert!(!solver.solve().is_sat());
+        assert_eq!(solver.num_original_clauses(), 4);
+    }
+
+    #[test]
+    fn test_solver_propagate_under() {
+        let cnf = parse_cnf_from_str("-1 2\n-2 3\n-4 -3\n5\n-5 6 7\n1 4 -8").unwrap();
+        let mut solver = Solver::new(cnf);
+        assert_eq!(solver.propagate_under(&[]), Ok(vec![(5, true)]));
+        assert_eq!(
+            solver.propagate_under(&[(1, true)]),
+            Ok(vec![(2, true), (3, true), (4, false), (5, true)])
+        );
+        assert_eq!(
+            solver.propagate_under(&[(6, false), (8, true), (1, false)]),
+            Ok(vec![
+                (2, false),
+                (3, false),
+                (4, true),
+                (5, true),
+                (7, true)
+            ])
+        );
+
+        // 1 implies ¬4, which clause 2 is falsified by
+        assert_eq!(
+            solver.propagate_under(&[(1, true), (4, true)]),
+            Err(Conflict {
+                clause_idx: 2,
+                literal: Some((4, true)),
+            })
+        );
+        assert_eq!(
+            solver.propagate_under(&[(4, true), (2, true)]),
+            Err(Conflict {
+                clause_idx: 1,
+                literal: Some((2, true)),
+            })
+        );
+
+        // a running search is abandoned
+        solver.start_search(&[(1, false)]);
+        while solver.current_decision_level() < 1 {
+            assert!(solver.step().is_none());
+        }
+        assert_eq!(solver.propagate_under(&[(8, true)]), Ok(vec![(5, true)]));
+        assert!(solver.solve_under_assumptions(&[(8, true)]).is_sat());
+
+        let mut solver = Solver::new(parse_cnf_from_str("1 2\nfalse").unwrap());
+        assert_eq!(
+            solver.propagate_under(&[(1, true)]),
+            Err(Conflict {
+                clause_idx: 1,
+                literal: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_solver_solve_bounded() {
+        let pigeons = "1 2\n3 4\n5 6\n-1 -3\n-1 -5\n-3 -5\n-2 -4\n-2 -6\n-4 -6";
+        let cnf = parse_cnf_from_str(pigeons).unwrap();
+        let (_, stats) = is_satisfiable(&cnf);
+        assert!(stats.literal_accesses > 0);
+
+        let mut solver = Solver::new(cnf.clone());
+        assert_eq!(solver.solve(), SolveResult::Unsat);
+        assert_eq!(solver.stats().literal_accesses, stats.literal_accesses);
+
+        // the budget is checked after every step, which looks up few literals here
+        let mut solver = Solver::new(cnf);
+        assert_eq!(solver.solve_bounded(&[], 5), None);
+        assert!(solver.stats().literal_accesses > 5);
+        assert!(solver.stats().literal_accesses < stats.literal_accesses);
+        let result = loop {
+            if let Some(result) = solver.step() {
+                break result;
+            }
+        };
+        assert_eq!(result, SolveResult::Unsat);
+        assert_eq!(solver.stats().literal_accesses, stats.literal_accesses);
+
+        let mut solver = Solver::new(parse_cnf_from_str(pigeons).unwrap());
+        let result = solver.solve_bounded(&[], stats.literal_accesses);
+        assert_eq!(result, Some(SolveResult::Unsat));
+    }
+
+    #[test]
+    fn test_solver_would_be_unsat_with() {
+        let cnf = parse_cnf_from_str("1 2\n-1 3\n-2 3\n-3 4 5").unwrap();
+        let mut solver = Solver::new(cnf.clone());
+        let model = match solver.solve() {
+            SolveResult::Sat(model) => model,
+            _ => panic!("formula is satisfiable"),
+        };
+
+        let clause = |input: &str| parse_cnf_from_str(input).unwrap().clauses.remove(0);
+        assert!(solver.would_be_unsat_with(&clause("-3")));
+        assert!(!solver.would_be_unsat_with(&clause("-4")));
+        assert!(!solver.would_be_unsat_with(&clause("-1 -2")));
+
+        // neither the clauses nor the last search changed
+        assert_eq!(solver.cnf(), &cnf);
+        assert_eq!(solver.current_assignment(), &model);
+        assert!(!solver.entails((1, true)));
+        assert!(solver.entails((3, true)));
+
+        // the learned clauses are used, but those learned meanwhile are not kept
+        let pigeons = "1 2\n3 4\n5 6\n-1 -3\n-1 -5\n-3 -5\n-2 -4\n-2 -6\n-4 -6";
+        let mut solver = Solver::new(parse_cnf_from_str(pigeons).unwrap());
+        solver.solve_under_assumptions(&[(1, true)]);
+        let learned = solver.learned_clauses().cloned().collect::<Vec<_>>();
+        assert!(solver.would_be_unsat_with(&clause("7")));
+        assert!(solver.would_be_unsat_with(&Clause::new()));
+        assert_eq!(
+            solver.learned_clauses().cloned().collect::<Vec<_>>(),
+            learned
+        );
+    }
+
+    #[test]
+    fn test_solver_would_be_unsat_with_first_uip() {
+        let config = SolverConfig {
+            learn_first_uip: true,
+            polarity: Polarity::True,
+            ..SolverConfig::default()
+        };
+        let cnf = parse_cnf_from_str(
+            "-2 -4\n-2 -3 4\n3 4\n1 -2\n1 2 3 -4\n-1 -2 -4\n-1 3 4\n2 -3\n-2 3 4\n3 4",
+        )
+        .unwrap();
+        let unit = Clause::from_literals(&[(1, true)]);
+        let mut with_unit = cnf.clone();
+        with_unit.clauses.push(unit.clone());
+
+        // the backjumps need the learned clauses to be watched
+        let mut solver = Solver::with_config(cnf.clone(), config);
+        assert_eq!(
+            solver.would_be_unsat_with(&unit),
+            !is_satisfiable(&with_unit).0
+        );
+        assert_eq!(solver.cnf(), &cnf);
+        assert_eq!(solver.learned_clauses().count(), 0);
+    }
+
+    #[test]
+    fn test_solver_reuses_learned_clauses() {
+        // three pigeons do not fit into two holes, but only if 7 is true
+        let pigeons =
+            "-7 1 2\n-7 3 4\n-7 5 6\n-7 -1 -3\n-7 -1 -5\n-7 -3 -5\n-7 -2 -4\n-7 -2 -6\n-7 -4 -6";
+        let cnf = parse_cnf_from_str(pigeons).unwrap();
+        let conflicts_from_scratch = |assumptions: &[LiteralTpl]| {
+            let mut solver = Solver::new(cnf.clone());
+            assert!(!solver.solve_under_assumptions(assumptions).is_sat());
+            solver.stats().conflicts
+        };
+
+        let mut solver = Solver::new(cnf.clone());
+        assert!(solver.solve_under_assumptions(&[(7, false)]).is_sat());
+        assert!(!solver.solve_under_assumptions(&[(7, true)]).is_sat());
+        assert_eq!(
+            solver.stats().conflicts,
+            conflicts_from_scratch(&[(7, true)])
+        );
+        assert!(solver.cnf().clauses.len() > cnf.clauses.len());
+
+        // the same and tighter assumptions run into fewer conflicts
+        assert!(!solver.solve_under_assumptions(&[(7, true)]).is_sat());
+        assert!(solver.stats().conflicts < conflicts_from_scratch(&[(7, true)]));
+        let tighter = [(7, true), (1, false)];
+        assert!(!solver.solve_under_assumptions(&tighter).is_sat());
+        assert!(solver.stats().conflicts < conflicts_from_scratch(&tighter));
+
+        // learned clauses do not change the models
+        match solver.solve_under_assumptions(&[(7, false), (1, true)]) {
+            SolveResult::Sat(model) => assert!(cnf.is_satisfied(&model)),
+            _ => panic!("formula is satisfiable without 7"),
+        }
+    }
+
+    #[test]
+    fn test_solver_with_learned() {
+        let pigeons =
+            "-7 1 2\n-7 3 4\n-7 5 6\n-7 -1 -3\n-7 -1 -5\n-7 -3 -5\n-7 -2 -4\n-7 -2 -6\n-7 -4 -6";
+        let cnf = parse_cnf_from_str(pigeons).unwrap();
+        let mut solver = Solver::new(cnf.clone());
+        assert!(!solver.solve_under_assumptions(&[(7, true)]).is_sat());
+        let learned = solver.learned_clauses().cloned().collect::<Vec<_>>();
+        assert_eq!(learned.len(), solver.stats().conflicts);
+        assert!(learned.iter().all(|clause| clause.get(7) == Some(false)));
+
+        // a related formula with an additional clause starts from the learned clauses
+        let mut related = cnf.clone();
+        related
+            .clauses
+            .push(Clause::from_literals(&[(8, true), (9, true)]));
+        let mut from_scratch = Solver::new(related.clone());
+        assert!(!from_scratch.solve_under_assumptions(&[(7, true)]).is_sat());
+        let mut solver = Solver::with_learned(related, learned.clone());
+        assert!(solver.learned_clauses().eq(learned.iter()));
+        assert!(!solver.solve_under_assumptions(&[(7, true)]).is_sat());
+        assert!(solver.stats().conflicts < from_scratch.stats().conflicts);
+    }
+
+    #[test]
+    fn test_solver_decision_levels() {
+        let cnf = parse_cnf_from_str("1 5\n1 -5\n-1 2 3\n-2 -3").unwrap();
+        let mut solver = Solver::new(cnf);
+        assert!(solver.solve().is_sat());
+        let levels = solver.decision_levels();
+        assert_eq!(levels.len(), solver.current_decision_level());
+        assert!(!levels.is_empty());
+        for (idx, level) in levels.iter().enumerate() {
+            let (var, val) = level.decision();
+            assert_eq!(var, level.changed_var());
+            assert_eq!(level.assignment().get(var), Some(val));
+            // every level extends the ones below
+            if idx > 0 {
+                assert!(levels[idx - 1].assignment().agrees_with(level.assignment()));
+            }
+        }
+        assert_eq!(
+            levels.last().unwrap().assignment(),
+            solver.current_assignment()
+        );
+
+        // 1 = false is decided first and conflicts, so it is flipped to true
+        let decisions = levels
+            .iter()
+            .map(|level| (level.decision(), level.flipped()));
+        assert_eq!(decisions.collect::<Vec<_>>()[0], ((1, true), true));
+
+        let mut solver = Solver::new(parse_cnf_from_str("1 2\n-1 2\n1 -2\n-1 -2").unwrap());
+        assert!(!solver.solve().is_sat());
+        assert!(solver.decision_levels().is_empty());
+    }
+
+    #[test]
+    fn test_solver_learned_subsumption() {
+        let clause = |input: &str| parse_cnf_from_str(input).unwrap().clauses.remove(0);
+        let cnf = parse_cnf_from_str("1 2\n-3 4 5").unwrap();
+
+        // subsumed by a clause of the formula or an earlier learned clause
+        let learned = vec![
+            clause("1 2 3"),
+            clause("-3 4"),
+            clause("4 -3 -6"),
+            clause("-3 4"),
+        ];
+        let solver = Solver::with_learned(cnf.clone(), learned);
+        assert!(solver.learned_clauses().eq([clause("-3 4")].iter()));
+
+        // subsuming learned clauses, which are deleted
+        let learned = vec![
+            clause("-3 4 -6"),
+            clause("5 -6 7"),
+            clause("-6 -3"),
+            clause("-6"),
+        ];
+        let solver = Solver::with_learned(cnf.clone(), learned);
+        assert!(solver.learned_clauses().eq([clause("-6")].iter()));
+        assert_eq!(solver.cnf().clauses.len(), 6);
+
+        // the formula itself is kept
+        let mut solver = Solver::with_learned(cnf, vec![clause("1")]);
+        assert_eq!(solver.cnf().clauses.len(), 3);
+        assert!(solver.entails((1, true)));
+    }
+
+    #[test]
+    fn test_solver_num_vars() {
+        let cnf = parse_cnf_from_str("1 2\n-1 4").unwrap();
+        let config = SolverConfig {
+            polarity: Polarity::True,
+            num_vars: Some(6),
+            ..SolverConfig::default()
+        };
+        let mut solver = Solver::with_config(cnf.clone(), config);
+        match solver.solve_under_assumptions(&[(1, false)]) {
+            SolveResult::Sat(model) => {
+                assert!(cnf.is_satisfied(&model));
+                // 1 and 2 are needed, 3 to 6 are free
+                assert_eq!(solver.stats().free_vars, 4);
+                assert_eq!(model.iter().count(), 6);
+                assert_eq!(model.get(5), Some(true));
+                assert_eq!(model.get(6), Some(true));
+            }
+            _ => panic!("formula is satisfiable"),
+        }
+
+        // without the option only the needed variables are assigned
+        let mut solver = Solver::new(cnf);
+        match solver.solve_under_assumptions(&[(1, false)]) {
+            SolveResult::Sat(model) => assert_eq!(model.get(5), None),
+            _ => panic!("formula is satisfiable"),
+        }
+        assert_eq!(solver.stats().free_vars, 2);
+        assert!(!solver
+            .solve_under_assumptions(&[(1, true), (4, false)])
+            .is_sat());
+        assert_eq!(solver.stats().free_vars, 0);
+    }
+
+    #[test]
+    fn test_solver_add_empty_clause() {
+        let cnf = parse_cnf_from_str("1 2 3\n-1 4\n3 4 5").unwrap();
+        let mut solver = Solver::new(cnf.clone());
+        assert!(solver.solve().is_sat());
+        solver.add_derived_clause(Clause::new());
+        assert_eq!(solver.solve(), SolveResult::Unsat);
+        assert_eq!(solver.stats().unsat_reason, Some(UnsatReason::EmptyClause));
+
+        // during a search, even under assumptions
+        let mut solver = Solver::new(cnf);
+        solver.start_search(&[(5, true)]);
+        while solver.current_decision_level() < 2 {
+            assert!(solver.step().is_none());
+        }
+        solver.add_derived_clause(Clause::new());
+        assert_eq!(solver.step(), Some(SolveResult::Unsat));
+        assert_eq!(solver.stats().unsat_reason, Some(UnsatReason::EmptyClause));
+        assert_eq!(solver.solve(), SolveResult::Unsat);
+    }
+
+    #[test]
+    fn test_solver_add_derived_clause() {
+        let cnf = parse_cnf_from_str("1 2 3\n-1 4\n3 4 5").unwrap();
+
+        // step until 1 = false and 2 = false are decided and propagated
+        let mut solver = Solver::new(cnf.clone());
+        while solver.current_decision_level() < 2 {
+            assert!(solver.step().is_none());
+        }
+        assert!(solver.step().is_none());
+        assert_eq!(solver.current_assignment().get(3), Some(true));
+        let level = solver.current_decision_level();
+
+        // unit under the current assignment
+        solver.add_derived_clause(Clause::from_literals(&[(2, true), (5, false)]));
+        assert_eq!(solver.current_decision_level(), level);
+        assert_eq!(solver.current_assignment().get(5), Some(false));
+        let model = loop {
+            if let Some(result) = solver.step() {
+                break result;
+            }
+        };
+        match model {
+            SolveResult::Sat(model) => {
+                assert!(solver.cnf().is_satisfied(&model));
+                assert_eq!(model.get(5), Some(false));
+            }
+            _ => panic!("formula is satisfiable"),
+        }
+        assert_eq!(solver.cnf().clauses.len(), 4);
+
+        // falsified under the current assignment
+        let mut solver = Solver::new(cnf.clone());
+        while solver.current_decision_level() < 2 {
+            assert!(solver.step().is_none());
+        }
+        solver.add_derived_clause(Clause::from_literals(&[(1, true), (2, true)]));
+        match solver.solve_under_assumptions(&[]) {
+            SolveResult::Sat(model) => assert!(solver.cnf().is_satisfied(&model)),
+            _ => panic!("formula is satisfiable"),
+        }
+
+        // before the search and at level 0, making the formula unsatisfiable
+        let mut solver = Solver::new(cnf);
+        solver.add_derived_clause(Clause::from_literals(&[(4, false)]));
+        solver.add_derived_clause(Clause::from_literals(&[(1, true)]));
+        assert!(!solver.solve().is_sat());
+        let mut solver = Solver::new(parse_cnf_from_str("1 2\n-1 3").unwrap());
+        assert!(solver.step().is_none());
+        assert_eq!(solver.current_decision_level(), 0);
+        solver.add_derived_clause(Clause::from_literals(&[(1, false)]));
+        solver.add_derived_clause(Clause::from_literals(&[(2, false)]));
+        assert_eq!(solver.step(), Some(SolveResult::Unsat));
+    }
+
+    #[test]
+    fn test_solver_model_iter() {
+        let cnf = parse_cnf_from_str("1 2 3\n-1 -2\n-3 4").unwrap();
+        let mut solver = Solver::new(cnf.clone());
+        let models = solver.model_iter().collect::<Vec<_>>();
+
+        // the partial models cover the satisfying assignments exactly once
+        let mut covered = 0;
+        for bits in 0..16u32 {
+            let assignment =
+                Assignment::new().with_all((1..=4).map(|var| (var, bits & (1 << (var - 1)) != 0)));
+            let extended = models
+                .iter()
+                .filter(|model| {
+                    model
+                        .iter()
+                        .all(|(var, val)| assignment.get(var) == Some(val))
+                })
+                .count();
+            assert_eq!(
+                extended,
+                cnf.is_satisfied(&assignment) as usize,
+                "{:?}",
+                assignment
+            );
+            covered += extended;
+        }
+        assert!(covered > models.len());
+
+        assert!(!solver.solve().is_sat());
+        assert_eq!(solver.model_iter().next(), None);
+        assert_eq!(
+            Solver::new(parse_cnf_from_str("1\n-2").unwrap())
+                .model_iter()
+                .count(),
+            1
+        );
+        assert_eq!(
+            Solver::new(parse_cnf_from_str("1\n-1").unwrap())
+                .model_iter()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_all_models() {
+        let cnf = parse_cnf_from_str("1 2\n-1 -2\n2 3 4").unwrap();
+        let models = all_models(&cnf).collect::<Vec<_>>();
+        let total = models
+            .iter()
+            .map(|model| 1 << (4 - model.iter().count()))
+            .sum::<usize>();
+        let satisfying = (0..16u32)
+            .filter(|bits| {
+                let assignment = Assignment::new()
+                    .with_all((1..=4).map(|var| (var, bits & (1 << (var - 1)) != 0)));
+                cnf.is_satisfied(&assignment)
+            })
+            .count();
+        assert_eq!(total, satisfying);
+
+        assert_eq!(all_models(&cnf).take(1).count(), 1);
+        assert_eq!(all_models(&parse_cnf_from_str("1\n-1").unwrap()).count(), 0);
+        assert_eq!(
+            all_models(&Cnf::new()).collect::<Vec<_>>(),
+            vec![Assignment::new()]
+        );
+    }
+
+    #[test]
+    fn test_count_models() {
+        assert_eq!(count_models(&parse_cnf_from_str("1 2\n-1 -2").unwrap()), 2);
+        assert_eq!(
+            count_models(&parse_cnf_from_str("1 2\n-1 -2\n2 3 4").unwrap()),
+            7
+        );
+        // 2 and 3 do not occur, but are below the highest variable
+        assert_eq!(count_models(&parse_cnf_from_str("1 4").unwrap()), 12);
+        assert_eq!(count_models(&parse_cnf_from_str("1\n-1").unwrap()), 0);
+        assert_eq!(count_models(&Cnf::new()), 1);
+
+        assert_eq!(
+            count_models(&parse_cnf_from_str("1 200").unwrap()),
+            u128::MAX
+        );
+    }
+
+    #[test]
+    fn test_solver_learned_clause_deletion() {
+        let pigeons =
+            "-7 1 2\n-7 3 4\n-7 5 6\n-7 -1 -3\n-7 -1 -5\n-7 -3 -5\n-7 -2 -4\n-7 -2 -6\n-7 -4 -6";
+        let cnf = parse_cnf_from_str(pigeons).unwrap();
+        let config = SolverConfig {
+            max_learned_clauses: Some(1),
+            ..SolverConfig::default()
+        };
+        let mut solver = Solver::with_config(cnf.clone(), config);
+        assert!(!solver.solve_under_assumptions(&[(7, true)]).is_sat());
+        assert!(solver.stats().conflicts > 1);
+        // the clauses are only deleted when the next search starts
+        assert_eq!(solver.cnf().clauses.len(), cnf.clauses.len() + 2);
+
+        // derived clauses are never deleted
+        solver.add_derived_clause(Clause::from_literals(&[(1, true), (8, true)]));
+        assert!(!solver
+            .solve_under_assumptions(&[(7, true), (1, false)])
+            .is_sat());
+        solver.start_search(&[]);
+        assert_eq!(solver.cnf().clauses.len(), cnf.clauses.len() + 2);
+        assert_eq!(
+            solver
+                .extra_clauses
+                .iter()
+                .filter(|extra| !extra.learned)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_solver_reduce_learned_clauses() {
+        let mut solver = Solver::new(parse_cnf_from_str("1 2").unwrap());
+        for (var, activity) in [(3, 2.0), (4, 5.0), (5, 1.0), (6, 2.0)].iter() {
+            solver.add_derived_clause(Clause::from_literals(&[(1, true), (*var, true)]));
+            let extra = solver.extra_clauses.last_mut().unwrap();
+            extra.learned = true;
+            extra.activity = *activity;
+        }
+
+        // the older of the clauses with activity 2 is deleted first
+        solver.reduce_learned_clauses(2);
+        let kept = parse_cnf_from_str("1 2\n1 4\n1 6").unwrap();
+        assert_eq!(solver.cnf(), &kept);
+        assert_eq!(solver.extra_clauses.len(), 2);
+        assert!(solver.solve_under_assumptions(&[(1, false)]).is_sat());
+    }
+
+    #[test]
+    fn test_solver_compaction() {
+        let config = SolverConfig {
+            compaction_interval: 2,
+            ..SolverConfig::default()
+        };
+        let mut solver = Solver::with_config(parse_cnf_from_str("1 2").unwrap(), config);
+        for (var, activity) in [(3, 2.0), (4, 5.0), (5, 1.0), (6, 2.0)].iter() {
+            solver.add_derived_clause(Clause::from_literals(&[(1, true), (*var, true)]));
+            let extra = solver.extra_clauses.last_mut().unwrap();
+            extra.learned = true;
+            extra.activity = *activity;
+        }
+
+        // the first reduction only marks the clause deleted
+        solver.reduce_learned_clauses(3);
+        assert_eq!(solver.cnf().clauses.len(), 5);
+        assert_eq!(solver.extra_clauses.iter().filter(|e| e.deleted).count(), 1);
+        assert!(solver.solve_under_assumptions(&[(1, false)]).is_sat());
+
+        // the second one compacts
+        solver.reduce_learned_clauses(2);
+        let kept = parse_cnf_from_str("1 2\n1 4\n1 6").unwrap();
+        assert_eq!(solver.cnf(), &kept);
+        assert_eq!(solver.extra_clauses.len(), 2);
+        assert!(solver.solve_under_assumptions(&[(1, false)]).is_sat());
+    }
+
+    #[test]
+    fn test_preprocess_stats() {
+        let cnf = parse_cnf_from_str("1\n-1 2\n-2 3 4\n5 6\n-5 6").unwrap();
+        let mut solver = Solver::new(cnf.clone());
+        assert!(solver
+            .solve_under_assumptions(&[(3, false), (5, true)])
+            .is_sat());
+        // 1 and 2 by clauses, 4 and 6 propagated from the assumptions
+        assert_eq!(solver.stats().preprocess.units, 4);
+
+        // 3, 4 and 6 are pure, which makes 2 and then 1 pure as well
+        let stats = is_satisfiable(&cnf).1;
+        assert_eq!(stats.preprocess.pure_literals, 5);
+        assert_eq!(stats.preprocess.units, 0);
+
+        // the components are counted together
+        let cnf =
+            parse_cnf_from_str("1\n-1 2\n-2 -1 3\n-3 -2 4\n-4 3\n5\n-5 6\n-6 -5 7\n-7 -6 8\n-8 7")
+                .unwrap();
+        let stats = is_satisfiable(&cnf).1;
+        assert_eq!(stats.preprocess.pure_literals, 0);
+        assert_eq!(stats.preprocess.units, 8);
+
+        // removing the subsumed clauses removes 3 instead of making it pure
+        let cnf = parse_cnf_from_str("1 2\n1 2 -3\n-1 -2\n-1 -2 3").unwrap();
+        let stats = is_satisfiable(&cnf).1;
+        assert_eq!(stats.preprocess.subsumed_clauses, 2);
+        assert_eq!(stats.preprocess.pure_literals, 0);
+
+        // 3 is the output of an AND gate of 1 and 2
+        let cnf =
+            parse_cnf_from_str("-3 1\n-3 2\n3 -1 -2\n3 4\n-3 -4\n1 4\n-1 -4\n2 4\n-2 -4").unwrap();
+        let stats = is_satisfiable(&cnf).1;
+        assert_eq!(stats.preprocess.eliminated_vars, 1);
+        assert_eq!(stats.preprocess.subsumed_clauses, 0);
+
+        // the gates are not eliminated along with parity constraints
+        let mut cnf = cnf;
+        cnf.xors.push(XorClause::new(vec![1, 4], true));
+        assert_eq!(is_satisfiable(&cnf).1.preprocess.eliminated_vars, 0);
+    }
+
+    #[test]
+    fn test_solver_explain() {
+        let cnf = parse_cnf_from_str("1\n-1 2\n-2 -3 4\n3 5\n-5 -6").unwrap();
+        let mut solver = Solver::new(cnf);
+        match solver.solve_under_assumptions(&[(3, true)]) {
+            SolveResult::Sat(model) => assert_eq!(model.get(4), Some(true)),
+            _ => panic!("formula is satisfiable"),
+        }
+
+        let mut explanation = solver.explain(4);
+        explanation.sort_unstable();
+        assert_eq!(explanation, vec![(2, false), (3, false)]);
+        assert_eq!(solver.explain(2), vec![(1, false)]);
+        // unit clause, assumption, unassigned
+        assert_eq!(solver.explain(1), vec![]);
+        assert_eq!(solver.explain(3), vec![]);
+        assert_eq!(solver.explain(7), vec![]);
+
+        // decisions have no reason, 3 is decided false before 5 is
+        assert!(solver.solve().is_sat());
+        assert_eq!(solver.explain(3), vec![]);
+        assert_eq!(solver.explain(5), vec![(3, true)]);
+
+        // 4 is implied by a clause derived from the constraint, which follows the inserted
+        // clause
+        let mut cnf = parse_cnf_from_str("4 5 6\n-4 5 6").unwrap();
+        cnf.xors.push(XorClause::new(vec![1, 2, 4], true));
+        let config = SolverConfig {
+            trace: true,
+            ..SolverConfig::default()
+        };
+        let mut solver = Solver::with_config(cnf, config);
+        assert!(solver.solve().is_sat());
+        let explanations = (1..=6).map(|var| solver.explain(var)).collect::<Vec<_>>();
+        assert_eq!(explanations[3], vec![(1, true), (2, true)]);
+        solver.add_clause(&[(7, true), (8, true)]);
+        assert_eq!(
+            (1..=6).map(|var| solver.explain(var)).collect::<Vec<_>>(),
+            explanations
+        );
+        for event in solver.trace().unwrap() {
+            if let SolveEvent::Propagation { lit, reason_clause } = *event {
+                assert!(solver.cnf().clauses[reason_clause]
+                    .literals()
+                    .any(|l| l == lit));
+            }
+        }
+    }
+
+    #[test]
+    fn test_solver_step() {
+        // 1 = false is decided first and fails after propagating 2
+        let mut solver = Solver::new(parse_cnf_from_str("1 2\n1 -2\n3 4\n-1 5").unwrap());
+        assert_eq!(solver.current_decision_level(), 0);
+
+        let mut levels = Vec::new();
+        let result = loop {
+            if let Some(result) = solver.step() {
+                break result;
+            }
+            levels.push(solver.current_decision_level());
+            let assignment = solver.current_assignment();
+            assert!(assignment.iter().count() >= solver.current_decision_level());
+        };
+
+        let model = match result {
+            SolveResult::Sat(model) => model,
+            _ => panic!("formula is satisfiable"),
+        };
+        assert_eq!(solver.current_assignment(), &model);
+        assert_eq!(model.get(1), Some(true));
+        assert_eq!(model.get(5), Some(true));
+        assert_eq!(levels.first(), Some(&0));
+        assert!(levels.contains(&1));
+
+        // a finished search is started over
+        assert!(solver.step().is_none());
+        assert_eq!(solver.current_decision_level(), 0);
+    }
+
+    #[test]
+    fn test_solver_decides_highest_var() {
+        // var 3 is only constrained by a tautology, which is never unit, so it is not
+        // propagated but has to be decided after the others
+        let config = SolverConfig {
+            trace: true,
+            ..SolverConfig::default()
+        };
+        let mut solver =
+            Solver::with_config(parse_cnf_from_str("1 2\n-1 2\n3 -3").unwrap(), config);
+        let model = match solver.solve() {
+            SolveResult::Sat(model) => model,
+            _ => panic!("formula is satisfiable"),
+        };
+        assert!(solver.cnf().is_satisfied(&model));
+        assert!(solver
+            .trace()
+            .unwrap()
+            .contains(&SolveEvent::Decision((3, false))));
+    }
+
+    #[test]
+    fn test_solver_trace() {
+        let cnf = parse_cnf_from_str("1 2\n1 -2\n3 4\n-1 5").unwrap();
+        let mut solver = Solver::new(cnf.clone());
+        solver.solve();
+        assert_eq!(solver.trace(), None);
+
+        let config = SolverConfig {
+            trace: true,
+            ..SolverConfig::default()
+        };
+        let mut solver = Solver::with_config(cnf, config);
+        assert!(solver.solve().is_sat());
+        assert_eq!(
+            solver.trace().unwrap(),
+            &[
+                SolveEvent::Decision((1, false)),
+                SolveEvent::Propagation {
+                    lit: (2, true),
+                    reason_clause: 0
+                },
+                SolveEvent::Conflict { clause_idx: 1 },
+                SolveEvent::Backtrack {
+                    from_level: 1,
+                    to_level: 1
+                },
+                SolveEvent::Propagation {
+                    lit: (5, true),
+                    reason_clause: 3
+                },
+                SolveEvent::Decision((2, false)),
+                SolveEvent::Decision((3, false)),
+                SolveEvent::Propagation {
+                    lit: (4, true),
+                    reason_clause: 2
+                },
+            ]
+        );
+
+        // unit clauses and assumptions before the search
+        let mut solver = Solver::with_config(
+            parse_cnf_from_str("1\n-1 2\n2 3 4").unwrap(),
+            SolverConfig {
+                trace: true,
+                ..SolverConfig::default()
+            },
+        );
+        assert!(solver.solve_under_assumptions(&[(3, true)]).is_sat());
+        assert_eq!(
+            solver.trace().unwrap(),
+            &[
+                SolveEvent::Propagation {
+                    lit: (1, true),
+                    reason_clause: 0
+                },
+                SolveEvent::Assumption((3, true)),
+                SolveEvent::Propagation {
+                    lit: (2, true),
+                    reason_clause: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_solver_backtrack_levels() {
+        // once 1 is false, both values of 2 lead to a conflict on 3
+        let cnf = parse_cnf_from_str("1 2 3\n1 2 -3\n1 -2 3\n1 -2 -3").unwrap();
+        let config = SolverConfig {
+            trace: true,
+            ..SolverConfig::default()
+        };
+        let mut solver = Solver::with_config(cnf, config);
+        assert!(solver.solve().is_sat());
+
+        let backtracks = solver
+            .trace()
+            .unwrap()
+            .iter()
+            .filter(|event| matches!(event, SolveEvent::Backtrack { .. }))
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            backtracks,
+            vec![
+                SolveEvent::Backtrack {
+                    from_level: 2,
+                    to_level: 2
+                },
+                SolveEvent::Backtrack {
+                    from_level: 2,
+                    to_level: 1
+                },
+            ]
+        );
+        assert_eq!(solver.stats().backtracks, 2);
+        assert_eq!(solver.stats().undone_levels, 1);
+    }
+
+    #[test]
+    fn test_unsat_reason() {
+        let reason = |cnf: &str, assumptions: &[LiteralTpl]| {
+            let mut solver = Solver::new(parse_cnf_from_str(cnf).unwrap());
+            assert_eq!(
+                solver.solve_under_assumptions(assumptions),
+                SolveResult::Unsat
+            );
+            solver.stats().unsat_reason
+        };
+
+        assert_eq!(reason("1\n-1 2\n-2", &[]), Some(UnsatReason::EmptyClause));
+        assert_eq!(
+            reason("1 2\n-2", &[(1, false)]),
+            Some(UnsatReason::Assumptions)
+        );
+        assert_eq!(
+            reason("1 2\n1 -2\n-1 2\n-1 -2", &[]),
+            Some(UnsatReason::SearchExhausted)
+        );
+
+        let (_, stats) = is_satisfiable(&parse_cnf_from_str("1\n-1").unwrap());
+        assert_eq!(stats.unsat_reason, Some(UnsatReason::EmptyClause));
+        let (_, stats) = is_satisfiable(&parse_cnf_from_str("1 2\n-1").unwrap());
+        assert_eq!(stats.unsat_reason, None);
+    }
+
+    #[test]
+    fn test_solver_projected() {
+        let cnf = parse_cnf_from_str("1 2\n-1 -2\n-2 3\n3 4").unwrap();
+        let mut solver = Solver::new(cnf.clone());
+
+        let full = match solver.solve() {
+            SolveResult::Sat(model) => model,
+            _ => panic!("formula is satisfiable"),
+        };
+        match solver.solve_projected(&[2, 3]) {
+            SolveResult::Sat(model) => {
+                assert!(model.iter().all(|(var, _)| var == 2 || var == 3));
+                assert_eq!(model.get(2), full.get(2));
+                assert_eq!(model.get(3), full.get(3));
+            }
+            _ => panic!("formula is satisfiable"),
+        }
+
+        let mut solver = Solver::new(parse_cnf_from_str("1\n-1").unwrap());
+        assert_eq!(solver.solve_projected(&[1]), SolveResult::Unsat);
+    }
+
+    #[test]
+    fn test_satisfied_clauses() {
+        let cnf = parse_cnf_from_str("1 2\n-1 3\n2 3").unwrap();
+        let mut satisfied = SatisfiedClauses::default();
+
+        let level1 = Assignment::new_with(2, true);
+        assert!(!satisfied.check(&cnf, &level1, 1));
+        assert_eq!(satisfied.levels, vec![Some(1), None, None]);
+
+        let level2 = level1.with(3, true);
+        assert!(satisfied.check(&cnf, &level2, 2));
+        // the third clause was not checked at level 1, as the second one is not satisfied
+        assert_eq!(satisfied.levels, vec![Some(1), Some(2), Some(2)]);
+
+        // flipping the decision of level 2 keeps the marks of level 1
+        satisfied.backtrack(2);
+        assert_eq!(satisfied.levels, vec![Some(1), None, None]);
+        assert!(!satisfied.check(&cnf, &level1.with(1, true), 2));
+        satisfied.backtrack(1);
+        assert_eq!(satisfied.levels, vec![None; 3]);
+        assert_eq!(satisfied.num_satisfied, 0);
+
+        // assigned literals mark their clauses right away
+        assert!(!satisfied.assign(&cnf, (3, true), 1));
+        assert_eq!(satisfied.levels, vec![None, Some(1), Some(1)]);
+        assert!(satisfied.assign(&cnf, (1, true), 2));
+        assert!(satisfied.check(&cnf, &level2.with(1, true), 2));
+        satisfied.backtrack(2);
+        assert_eq!(satisfied.num_satisfied, 2);
+    }
+
+    #[test]
+    fn test_propagate_until_satisfied() {
+        let cnf = parse_cnf_from_str("9 2\n-9 3\n-3 4\n4 5\n-4 6\n-4 7 6\n-6 8 9").unwrap();
+        let mut wl = WatchedLiterals::new(&cnf);
+        let mut assignment = Assignment::new_with(9, true);
+        let mut satisfied = SatisfiedClauses::default();
+
+        let result = propagate_assignment(
+            (9, true),
+            &mut assignment,
+            &cnf,
+            &mut wl,
+            PropagationOrder::Fifo,
+            &mut SearchLog::default(),
+            Some((&mut satisfied, 1)),
+        );
+        assert!(matches!(result, ExecuteAssignmentResult::AssignmentDone));
+        assert_eq!(satisfied.num_satisfied, cnf.clauses.len());
+        // 6 satisfies the last clause not marked yet, so 7 is left unassigned
+        assert_eq!(assignment.get(6), Some(true));
+        assert_eq!(assignment.get(7), None);
+        assert!(cnf.is_satisfied(&assignment));
+
+        // the watch of -6 is still moved, like without the satisfied clauses
+        let mut unmarked_wl = WatchedLiterals::new(&cnf);
+        let result = propagate_assignment(
+            (9, true),
+            &mut Assignment::new_with(9, true),
+            &cnf,
+            &mut unmarked_wl,
+            PropagationOrder::Fifo,
+            &mut SearchLog::default(),
+            None,
+        );
+        assert!(matches!(result, ExecuteAssignmentResult::AssignmentDone));
+        assert_eq!(format!("{:?}", wl), format!("{:?}", unmarked_wl));
+    }
+
+    #[test]
+    fn test_propagate_conflict() {
+        let cnf = parse_cnf_from_str("1 2\n-1 3\n-3 4\n-2 -4\n5 6").unwrap();
+        let mut wl = WatchedLiterals::new(&cnf);
+        let mut assignment = Assignment::new_with(1, true);
+
+        match propagate_assignment(
+            (1, true),
+            &mut assignment,
+            &cnf,
+            &mut wl,
+            PropagationOrder::default(),
+            &mut SearchLog::default(),
+            None,
+        ) {
+            ExecuteAssignmentResult::AssignmentDone => {}
+            ExecuteAssignmentResult::Unsatisfiable(conflict) => panic!("{:?}", conflict),
+        }
+        assert_eq!(assignment.get(4), Some(true));
+
+        let mut wl = WatchedLiterals::new(&cnf);
+        let mut assignment = Assignment::new_with(2, true).with(4, true);
+        match propagate_assignment(
+            (2, true),
+            &mut assignment,
+            &cnf,
+            &mut wl,
+            PropagationOrder::default(),
+            &mut SearchLog::default(),
+            None,
+        ) {
+            ExecuteAssignmentResult::AssignmentDone => panic!("Conflict not detected"),
+            ExecuteAssignmentResult::Unsatisfiable(conflict) => assert_eq!(
+                conflict,
+                Conflict {
+                    clause_idx: 3,
+                    literal: Some((2, true))
+                }
+            ),
+        }
+    }
+
+    #[test]
+    fn test_propagation_order() {
+        let conflict_clause = |cnf: &str, assignment: Assignment, order| {
+            let cnf = parse_cnf_from_str(cnf).unwrap();
+            let mut wl = WatchedLiterals::new(&cnf);
+            let mut assignment = assignment;
+            match propagate_assignment(
+                (1, true),
+                &mut assignment,
+                &cnf,
+                &mut wl,
+                order,
+                &mut SearchLog::default(),
+                None,
+            ) {
+                ExecuteAssignmentResult::AssignmentDone => panic!("Conflict not detected"),
+                ExecuteAssignmentResult::Unsatisfiable(conflict) => conflict.clause_idx,
+            }
+        };
+
+        // 1 implies 2 and 3, which are in conflict with the literals implied by each other
+        let cnf = "-1 2\n-1 3\n-2 4\n-3 -4";
+        let assignment = Assignment::new_with(1, true);
+        let conflict = |order| conflict_clause(cnf, assignment.clone(), order);
+        assert_eq!(conflict(PropagationOrder::Fifo), 3);
+        assert_eq!(conflict(PropagationOrder::Lifo), 2);
+
+        // 2 implies 5 by clause 0, which is handled before 3 implied by clause 2
+        let cnf = "-2 5\n-1 2\n-1 3\n-3 -9\n-5 -9";
+        let assignment = Assignment::new_with(1, true).with(9, true);
+        let conflict = |order| conflict_clause(cnf, assignment.clone(), order);
+        assert_eq!(conflict(PropagationOrder::Fifo), 3);
+        assert_eq!(conflict(PropagationOrder::ClauseIndex), 4);
+    }
+
+    #[test]
+    fn test_propagate_opposite_literals() {
+        // 2 propagates 1 by the first and -1 by the second clause at once
+        let cnf = parse_cnf_from_str("-2 1\n-2 -1\n3 4").unwrap();
+        let mut wl = WatchedLiterals::new(&cnf);
+        let mut assignment = Assignment::new_with(2, true);
+
+        match propagate_assignment(
+            (2, true),
+            &mut assignment,
+            &cnf,
+            &mut wl,
+            PropagationOrder::default(),
+            &mut SearchLog::default(),
+            None,
+        ) {
+            ExecuteAssignmentResult::AssignmentDone => panic!("Conflict not detected"),
+            ExecuteAssignmentResult::Unsatisfiable(conflict) => {
+                assert_eq!(conflict.clause_idx, 1);
+                assert_eq!(conflict.literal, Some((1, true)));
+            }
         }
     }
 
-    Some(assignment)
-}
+    #[test]
+    fn test_single_clauses_conflict() {
+        let cnf = parse_cnf_from_str("1\n2 3\n-2\n1\n-1").unwrap();
+        assert_eq!(
+            get_assignment_from_single_clauses(&cnf, &mut SearchLog::default()),
+            Err(Conflict {
+                clause_idx: 4,
+                literal: Some((1, true))
+            })
+        );
 
-/// Backtracks the given decision levels,
-/// until a new possible assignment is found or every assignment has been tried
-fn backtrack(dec_levels: &mut Vec<DecisionLevel>) -> BacktrackResult {
-    loop {
-        match dec_levels.last_mut() {
-            Some(dl) => {
-                if !dl.flipped {
-                    // This dl has not been flipped yet, so try it out
-                    dl.flipped = true;
-                    let old_assignment = dl.assignment.get(dl.changed_var).unwrap();
-                    let new_assignment = (dl.changed_var, !old_assignment);
-                    dl.assignment.change(new_assignment.0, new_assignment.1);
-                    return BacktrackResult::ContinueWith(new_assignment);
-                } else {
-                    // This dl has already been flipped, backtrack further
-                    dec_levels.pop();
-                    continue;
+        let cnf = parse_cnf_from_str("1\n2 3\n-2\n1").unwrap();
+        assert_eq!(
+            get_assignment_from_single_clauses(&cnf, &mut SearchLog::default()),
+            Ok(Assignment::new_with(1, true).with(2, false))
+        );
+    }
+
+    #[test]
+    fn test_solver_shuffled() {
+        for seed in 0..5 {
+            let config = SolverConfig {
+                shuffle_seed: Some(seed),
+                ..SolverConfig::default()
+            };
+            let cnf = parse_cnf_from_str("1 2 3\n-1 -2\n-2 -3\n-1 -3\n4 -2\n-4 5").unwrap();
+            match Solver::with_config(cnf.clone(), config.clone()).solve() {
+                SolveResult::Sat(model) => assert!(cnf.is_satisfied(&model)),
+                _ => panic!("seed {} missed a model", seed),
+            }
+
+            let cnf = parse_cnf_from_str("1 2\n-1 2\n1 -2\n-1 -2 3\n-3").unwrap();
+            assert_eq!(Solver::with_config(cnf, config).solve(), SolveResult::Unsat);
+        }
+    }
+
+    #[test]
+    fn test_solver_initial_phases() {
+        let cnf = parse_cnf_from_str("1 2 3\n-1 4\n-3 -4").unwrap();
+        let solve_with = |initial_phases: &[(Var, bool)]| {
+            let config = SolverConfig {
+                initial_phases: initial_phases.iter().copied().collect(),
+                ..SolverConfig::default()
+            };
+            match Solver::with_config(cnf.clone(), config).solve() {
+                SolveResult::Sat(model) => model,
+                _ => panic!("Formula is satisfiable"),
+            }
+        };
+
+        assert_eq!(solve_with(&[]).get(1), Some(false));
+        let model = solve_with(&[(1, true)]);
+        assert_eq!(model.get(1), Some(true));
+        assert_eq!(model.get(4), Some(true));
+
+        // greedy decides 1 = false first
+        let cnf = parse_cnf_from_str("-1 2\n-1 3\n1 4").unwrap();
+        let config = SolverConfig {
+            initial_phases: vec![(1, true)].into_iter().collect(),
+            heuristic: Heuristic::Greedy,
+            ..SolverConfig::default()
+        };
+        match Solver::with_config(cnf.clone(), config).solve() {
+            SolveResult::Sat(model) => {
+                assert_eq!(model.get(1), Some(true));
+                assert!(cnf.is_satisfied(&model));
+            }
+            _ => panic!("Formula is satisfiable"),
+        }
+    }
+
+    #[test]
+    fn test_solver_phase_saving() {
+        // deciding 1 false implies 2 before the conflict, 2 is decided after flipping 1
+        let cnf = parse_cnf_from_str("1 2\n1 3\n1 -3\n2 4").unwrap();
+        let solve_with = |phase_saving| {
+            let config = SolverConfig {
+                polarity: Polarity::False,
+                phase_saving,
+                ..SolverConfig::default()
+            };
+            match Solver::with_config(cnf.clone(), config).solve() {
+                SolveResult::Sat(model) => model,
+                _ => panic!("formula is satisfiable"),
+            }
+        };
+
+        assert_eq!(solve_with(false).get(2), Some(false));
+        let model = solve_with(true);
+        assert_eq!(model.get(2), Some(true));
+        assert_eq!(model.get(4), None);
+        assert!(cnf.is_satisfied(&model));
+    }
+
+    #[test]
+    fn test_stats() {
+        let (result, stats) = solve_with_timeout(&pigeonhole(3), Duration::from_secs(60));
+        assert_eq!(result, SolveResult::Unsat);
+        assert!(stats.decisions > 0 && stats.conflicts > 0);
+        assert!(stats.propagations >= stats.conflicts);
+        assert!((1..=12).contains(&stats.max_decision_level));
+        assert_eq!(stats.restarts, 0);
+
+        // 1 is decided false, which implies 2 and then 3
+        let mut solver = Solver::new(parse_cnf_from_str("1 2\n-2 3\n4").unwrap());
+        assert!(solver.solve().is_sat());
+        assert_eq!(
+            solver.stats().to_string(),
+            "1 decisions, 3 propagations, 0 conflicts, 0 restarts, max decision level 1"
+        );
+    }
+
+    #[test]
+    fn test_solve_xors() {
+        // 1 ⊕ 2 ⊕ 3 = 1 and 2 ⊕ 3 ⊕ 4 = 0 imply 1 ≠ 4, which the clauses forbid
+        let mut cnf = parse_cnf_from_str("1 -4\n-1 4 5\n-5").unwrap();
+        cnf.xors.push(XorClause::new(vec![1, 2, 3], true));
+        cnf.xors.push(XorClause::new(vec![2, 3, 4], false));
+        match Solver::new(cnf.clone()).solve() {
+            SolveResult::Sat(model) => panic!("formula is unsatisfiable, got {:?}", model),
+            result => assert_eq!(result, SolveResult::Unsat),
+        }
+
+        // the variables 2 and 3 occur in no clause, but are assigned by the model
+        cnf.clauses.pop();
+        match Solver::new(cnf.clone()).solve() {
+            SolveResult::Sat(model) => {
+                assert!(cnf.is_satisfied(&model));
+                assert!(model.get(2).is_some() && model.get(3).is_some());
+            }
+            _ => panic!("formula is satisfiable"),
+        }
+        assert!(is_satisfiable(&cnf).0);
+        assert_eq!(count_models(&cnf), 2);
+    }
+
+    #[test]
+    fn test_solve_xors_initial_assignment() {
+        // the unit clause implies 3 by the constraint, no clause is left to decide
+        let mut cnf = parse_cnf_from_str("1").unwrap();
+        cnf.xors.push(XorClause::new(vec![1, 3], true));
+        let heuristics = [
+            Heuristic::Numeric,
+            Heuristic::Static(vec![3, 1]),
+            Heuristic::Greedy,
+            Heuristic::Vsids(0.9),
+        ];
+        for heuristic in heuristics.iter() {
+            let config = SolverConfig {
+                heuristic: heuristic.clone(),
+                ..SolverConfig::default()
+            };
+            match Solver::with_config(cnf.clone(), config).solve() {
+                SolveResult::Sat(model) => {
+                    assert!(cnf.is_satisfied(&model), "{:?}", heuristic);
                 }
+                result => panic!("{:?} got {:?}", heuristic, result),
             }
-            None => {
-                // We backtracked beyond all decision-levels
-                // this means we tried all assignments
-                return BacktrackResult::UnsatisfiableFormula;
+        }
+        assert!(is_satisfiable(&cnf).0);
+        assert_eq!(count_models(&cnf), 2);
+    }
+
+    #[test]
+    fn test_solver_would_be_unsat_with_xors() {
+        // 1 ⊕ 2 = 1 and the unit clause 1 force 2 to false
+        let mut cnf = parse_cnf_from_str("1\n2 3").unwrap();
+        cnf.xors.push(XorClause::new(vec![1, 2], true));
+        let mut solver = Solver::new(cnf.clone());
+        assert!(solver.would_be_unsat_with(&Clause::from_literals(&[(2, true)])));
+        assert!(!solver.would_be_unsat_with(&Clause::from_literals(&[(3, true)])));
+        assert_eq!(solver.cnf(), &cnf);
+        assert!(solver.entails((2, false)));
+
+        // an empty constraint with an odd parity is never satisfied
+        let mut cnf = parse_cnf_from_str("1 2").unwrap();
+        cnf.xors.push(XorClause::new(vec![], true));
+        let mut solver = Solver::new(cnf);
+        assert!(solver.would_be_unsat_with(&Clause::from_literals(&[(1, true)])));
+    }
+
+    #[test]
+    fn test_solve_xors_random() {
+        let configs = [
+            SolverConfig::default(),
+            SolverConfig {
+                learn_first_uip: true,
+                ..SolverConfig::default()
+            },
+            SolverConfig {
+                heuristic: Heuristic::Vsids(0.9),
+                ..SolverConfig::default()
+            },
+        ];
+        let num_vars = 6;
+        let mut rng = Rng::new(780);
+        for _ in 0..200 {
+            let num_clauses = rng.below(5) as usize;
+            let mut cnf = random_cnf(&mut rng, num_vars, num_clauses, 1..=3);
+            let mut vars = (1..=num_vars).collect::<Vec<Var>>();
+            for _ in 0..1 + rng.below(4) {
+                rng.shuffle(&mut vars);
+                let len = 1 + rng.below(4) as usize;
+                cnf.xors
+                    .push(XorClause::new(vars[..len].to_vec(), rng.below(2) == 0));
+            }
+
+            let highest_var = cnf.highest_var();
+            let num_models = (0..1u32 << highest_var)
+                .filter(|bits| {
+                    let assignment = Assignment::new()
+                        .with_all((1..=highest_var).map(|var| (var, bits & (1 << (var - 1)) != 0)));
+                    cnf.is_satisfied(&assignment)
+                })
+                .count();
+            assert_eq!(count_models(&cnf), num_models as u128, "{:?}", cnf);
+            let expected = num_models > 0;
+            assert_eq!(is_satisfiable(&cnf).0, expected, "{:?}", cnf);
+            for config in &configs {
+                match Solver::with_config(cnf.clone(), config.clone()).solve() {
+                    SolveResult::Sat(model) => assert!(cnf.is_satisfied(&model), "{:?}", cnf),
+                    result => assert!(!expected, "{:?} for {:?}", result, cnf),
+                }
             }
         }
     }
-}
-#[derive(Debug, PartialEq, Eq)]
-enum BacktrackResult {
-    UnsatisfiableFormula,
-    ContinueWith(LiteralTpl),
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::input::parse_cnf_from_str;
+    #[test]
+    fn test_solver_greedy() {
+        let config = SolverConfig {
+            heuristic: Heuristic::Greedy,
+            ..SolverConfig::default()
+        };
 
-    use super::*;
+        // deciding 3 = true first satisfies everything without a conflict
+        let cnf = parse_cnf_from_str("1 3\n2 3\n-1 -2 3\n-3 4 5").unwrap();
+        let mut solver = Solver::with_config(cnf.clone(), config.clone());
+        match solver.solve() {
+            SolveResult::Sat(model) => {
+                assert!(cnf.is_satisfied(&model));
+                assert_eq!(model.get(3), Some(true));
+            }
+            _ => panic!("formula is satisfiable"),
+        }
+
+        let cnf = parse_cnf_from_str("1 2\n-1 2\n1 -2\n-1 -2 3\n-3").unwrap();
+        assert_eq!(Solver::with_config(cnf, config).solve(), SolveResult::Unsat);
+    }
 
     #[test]
-    fn test_sat_sanity() {
-        assert!(is_satisfiable(&Cnf::new()).0);
-        assert!(!is_satisfiable(&parse_cnf_from_str("false").unwrap()).0);
-        assert!(!is_satisfiable(&parse_cnf_from_str("1\nfalse").unwrap()).0);
-        assert!(!is_satisfiable(&parse_cnf_from_str("-1\nfalse").unwrap()).0);
-        assert!(!is_satisfiable(&parse_cnf_from_str("false\n1").unwrap()).0);
-        assert!(!is_satisfiable(&parse_cnf_from_str("false\n-1").unwrap()).0);
-        assert!(is_satisfiable(&parse_cnf_from_str("1").unwrap()).0);
+    fn test_solver_vsids() {
+        let config = SolverConfig {
+            heuristic: Heuristic::Vsids(0.9),
+            ..SolverConfig::default()
+        };
+
+        let mut rng = Rng::new(761);
+        for _ in 0..30 {
+            let cnf = random_cnf(&mut rng, 10, 40, 3..=3);
+
+            let expected = is_satisfiable(&cnf).0;
+            match Solver::with_config(cnf.clone(), config.clone()).solve() {
+                SolveResult::Sat(model) => assert!(expected && cnf.is_satisfied(&model)),
+                SolveResult::Unsat => assert!(!expected),
+                SolveResult::Unknown => panic!("the search gave up"),
+            }
+        }
     }
 
     #[test]
-    fn test_sat_deep_dl() {
-        assert!(is_satisfiable(&parse_cnf_from_str("1 2 3\n4 5 6\n7 8 9").unwrap()).0);
-        assert!(is_satisfiable(&parse_cnf_from_str("-1 -2 -3\n-4 -5 -6\n-7 -8 -9").unwrap()).0);
-        assert!(is_satisfiable(&parse_cnf_from_str("-1 -2 -3 4\n1\n2\n3").unwrap()).0);
-        assert!(!is_satisfiable(&parse_cnf_from_str("1 2 3\n-1\n-2\n-3").unwrap()).0);
-        assert!(!is_satisfiable(&parse_cnf_from_str("-1 2 -3\n1\n-2\n3").unwrap()).0);
+    fn test_is_satisfiable_with_heuristic() {
+        use std::{cell::Cell, rc::Rc};
+
+        /// Decides the highest unassigned variable and counts the conflicts
+        #[derive(Clone, Default)]
+        struct Descending(Rc<Cell<usize>>);
+
+        impl DecisionHeuristic for Descending {
+            fn pick(&mut self, _cnf: &Cnf, assignment: &Assignment, max: Var) -> Option<Var> {
+                (1..=max).rev().find(|&var| assignment.get(var).is_none())
+            }
+
+            fn on_conflict(&mut self, _clause: &Clause) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let mut rng = Rng::new(786);
+        for _ in 0..30 {
+            let cnf = random_cnf(&mut rng, 10, 40, 3..=3);
+
+            let expected = is_satisfiable(&cnf).0;
+            let descending = Descending::default();
+            let (result, stats) = is_satisfiable_with_heuristic(&cnf, descending.clone());
+            assert_eq!(result, expected);
+            assert_eq!(descending.0.get(), stats.conflicts);
+            assert_eq!(
+                is_satisfiable_with_heuristic(&cnf, Vsids::new(0.9)).0,
+                expected
+            );
+        }
     }
 
     #[test]
-    fn test_sat() {
-        assert!(is_satisfiable(&parse_cnf_from_str("1 2 3\n-2 -3\n-3\n-1").unwrap()).0);
-        assert!(is_satisfiable(&parse_cnf_from_str("1 2 3 4\n-2 -3\n-3\n-1").unwrap()).0);
-        assert!(is_satisfiable(&parse_cnf_from_str("1 2 3\n-2 -3\n-3 2\n-1").unwrap()).0);
+    fn test_solver_learn_first_uip() {
+        let config = SolverConfig {
+            polarity: Polarity::True,
+            learn_first_uip: true,
+            ..SolverConfig::default()
+        };
+
+        // deciding 1 implies 3, deciding 2 then implies both 4 and 5, which conflict
+        let cnf = parse_cnf_from_str("-1 3\n-2 -3 4\n-2 -3 5\n-4 -5").unwrap();
+        let mut solver = Solver::with_config(cnf, config.clone());
+        assert!(solver.solve().is_sat());
+        assert_eq!(
+            solver.learned_clauses().collect::<Vec<_>>(),
+            vec![&Clause::from_literals(&[(2, false), (3, false)])]
+        );
+
+        let mut rng = Rng::new(762);
+        for _ in 0..30 {
+            let cnf = random_cnf(&mut rng, 10, 40, 3..=3);
+
+            let mut solver = Solver::with_config(cnf.clone(), config.clone());
+            for _ in 0..3 {
+                let assumptions = [(rng.below(10) as Var + 1, rng.below(2) == 0)];
+                let mut restricted = cnf.clone();
+                restricted.clauses.push(Clause::from_literals(&assumptions));
+
+                let expected = is_satisfiable(&restricted).0;
+                match solver.solve_under_assumptions(&assumptions) {
+                    SolveResult::Sat(model) => assert!(expected && restricted.is_satisfied(&model)),
+                    SolveResult::Unsat => assert!(!expected),
+                    SolveResult::Unknown => panic!("the search gave up"),
+                }
+            }
+
+            // every learned clause follows from the formula
+            for clause in solver.learned_clauses() {
+                let mut refuted = cnf.clone();
+                for lit in clause.literals() {
+                    refuted.clauses.push(Clause::from_literals(&[negate(lit)]));
+                }
+                assert!(!is_satisfiable(&refuted).0, "{:?} does not follow", clause);
+            }
+        }
     }
 
     #[test]
-    fn test_sat_large() {
-        let input = r"-02 -08 -10 +11 -12 -13 -14 +17 -19
-        -01 -02 -03 -04 -05 -06 +07 -08 +09 +10 -11 -12 -13 +14 -15 -16 +17 -18 -19
-        -15
-        -01 -02 -06 -09 +10 +14 -15 -16 +17
-        +01 +02 -03 -04 +05 -06 -07 +09 -10 -11 +12 -13 -14 -15 +16 +17 -18 -19
-        -01 +02 -04 -06 -07 +08 -09 +12 +13 +15 -16 -19
-        +01 -02 +03 +04 -05 +06 +07 -08 +09 -10 +12 +13 +14 +15 -17 +18 +19
-        +01 -02 -03 -04 +05 +06 -07 -08 -09 +10 +11 +12 +13 +14 -15 -16 -17 -18 +19
-        +01 +15 +18
-        -09 -12 +17
-        -01 +09 +19
-        -06 +09 +17
-        +01 -11 +14
-        +02 +05 -07 -10 +14
-        +02 +03 +04 -05 -06 -07 -08 -09 +10 +14 -15 +16 +17 +19
-        +02 -03 +04 +08 +09 +12 +15 -17
-        -01 -02 +03 +04 +05 +06 -07 +08 -09 +10 +11 +12 -13 -14 +15 +16 +17 +18 -19
-        -01 -02 -03 +04 -05 +07 +08 -09 +10 -12 -13 -15 +18 +19
-        -13 -16 +18
-        -05 +14
-        -01 +02 +03 -04 -05 +06 -07 +08 +09 +10 +11 -12 +13 +14 +15 +16 +17 -18
-        +01 +02 -03 +04 +05 +06 -07 +08 +10 +11 -12 -13 +14 +15 +18 -19
-        +01 +04 -07 +10 -11 +12 -18 +19
-        -01 -06 -08 +11 -14 -18
-        -01 +05 -06 -07 +08 -12 +13 +14 +15 +16 -19
-        +01 +02 +03 -04 +05 -08 +09 -11 -12 +13 -14 -15 +16 -17 -18 -19
-        -02 +05 +06 -08 +11 +14 -17 -18 +19
-        -01 +02 -03 +04 +05 -06 -07 +08 +09 +11 -13 +14 +15 +16 +18 -19
-        +01 +02 +04 +05 -06 +08 -09 +14 +15 -16 +17
-        -04 +09 -11 -14 +17 -18
-        -03 -08 +09 +15 -18
-        +01 -02 +04 -05 -06 +07 +08 -11 +12 +13 +14 +15 +16 -17 +19
-        -02 +03 -04 +05 -06 -07 +08 -10 +11 -12 -13 +14 +15 -18 -19
-        +01 +02 -03 +04 +05 +06 -07 +08 +09 +10 -11 -12 -13 -14 +15 -16 +18 -19
-        +03 +04 +05 -07 +09 +10 -12 -13 +15 -16 +19
-        -01 +02 +04 -05 +06 -07 +08 -09 -11 +12 -13 +16 +17 -18 -19
-        +07 -18
-        +01 +02 +03 -04 +05 +06 -07 -09 -10 +11 -13 +14 +15 -16 -17 -18 +19
-        +01 -02 +05 +09 +12 +14 -15
-        +08 +16
-        +02 -04 -06 -07 +08 +09 +10 +13 +14 +16 +17 -19
-        +01 +03 +06 +10 -13 +16 -18
-        +01 +02 -03 +04 -05 -06 +07 -08 +09 +10 -11 -12 -13 +14 -15 -16 -17 -18
-        +01 +02 +03 -04 +05 +06 +07 +08 -09 +10 -11 +12 +13 +14 -15 +16 +17 +18
-        +02 +06 -10 -11 +13 +14 +15 +19
-        -01 +02 +03 +04 -05 -06 +07 +08 +10 +11 -12 +13 +14 +15 +16 +18 +19
-        +01 -05 -06 +07 +08 -09 -10 -11 +12 -13 -14 +15 +16 +17 -18 -19
-        -02 +03 -04 -05 -06 -07 +10 +13 -14 -18 +19
-        -01 -02 +03 +05 +06 -07 -08 -09 +10 -12 -13 -14 +15 +16 +18 -19
-        +01 +03 -04 +05 -08 -09 +11 -12 +13 -14 +15 -16 -17 +19
-        -04 -12 +19
-        -03 -11 +12
-        +01 -07 -19
-        -01 +10 -11 +15 +18
-        -01 -03 +04 -05 +07 -08 +11 -12 +14 +15 +16 +18
-        +01 -02 +03 +08 -10 +11 -14 +16
-        +02 +07 -08 +10 +15 +17
-        -01 +05 +07 -08 -09 +12 -15 +18
-        -07 +18
-        -01 -02 -03 -04 -05 -06 -07 -08 -10 -12 +15 +16 +17 +19
-        +11
-        -02 -04 +06 +08 -12 -16 -18
-        -01 +02 +03 -04 +05 +06 +07 -09 -10 +11 +12 +16 -17 +19
-        +07 +08 -12
-        -02 -11 +15
-        +02 -03 +05 +06 -09 -10 +11 -14 +15 +17 -18 +19
-        +01 -02 +04 -05 -06 +07 -08 -09 +10 -12 +14 -15 +16 -17 +18 +19
-        -01 +02 -03 +04 +05 -06 -07 +08 -09 +10 +11 +12 -13 +14 -15 +16 -17 +18 +19"
-            .trim_start();
+    fn test_solver_backjump() {
+        let config = SolverConfig {
+            polarity: Polarity::True,
+            learn_first_uip: true,
+            trace: true,
+            ..SolverConfig::default()
+        };
 
-        let cnf = parse_cnf_from_str(&input).unwrap();
-        // e.g.: -1 -2 -3 -4 -5 -6 7 -8 -9 -10 11 12 -13 14 -15 16 -17 18 -19
-        assert!(is_satisfiable(&cnf).0);
+        // the conflict after deciding 3 only depends on the decision of 1, not of 2
+        let cnf = parse_cnf_from_str("-1 -3 4\n-1 -3 -4\n2 -3").unwrap();
+        let mut solver = Solver::with_config(cnf.clone(), config.clone());
+        match solver.solve() {
+            SolveResult::Sat(model) => assert!(cnf.is_satisfied(&model)),
+            _ => panic!("Formula is satisfiable"),
+        }
+        let trace = solver.trace().unwrap();
+        let conflict = trace
+            .iter()
+            .position(|event| matches!(event, SolveEvent::Conflict { .. }))
+            .unwrap();
+        assert_eq!(
+            trace[conflict + 1..conflict + 3],
+            [
+                SolveEvent::Backtrack {
+                    from_level: 3,
+                    to_level: 1
+                },
+                SolveEvent::Propagation {
+                    lit: (3, false),
+                    reason_clause: 3
+                },
+            ]
+        );
+        assert_eq!(solver.stats().undone_levels, 2);
+
+        let mut solver = Solver::with_config(
+            parse_cnf_from_str("1 2\n1 -2\n-1 2\n-1 -2").unwrap(),
+            config.clone(),
+        );
+        assert_eq!(solver.solve(), SolveResult::Unsat);
+        assert_eq!(solver.stats().unsat_reason, Some(UnsatReason::EmptyClause));
+        assert_eq!(solver.decision_levels(), &[]);
+
+        let mut solver = Solver::with_config(parse_cnf_from_str("1 2\n1 -2\n3").unwrap(), config);
+        assert_eq!(
+            solver.solve_under_assumptions(&[(1, false)]),
+            SolveResult::Unsat
+        );
+        assert_eq!(solver.stats().unsat_reason, Some(UnsatReason::Assumptions));
+        assert!(solver.solve().is_sat());
+    }
+
+    #[test]
+    fn test_solver_skip_satisfied_clauses() {
+        let config = SolverConfig {
+            skip_satisfied_clauses: true,
+            ..SolverConfig::default()
+        };
+        let cnf = parse_cnf_from_str("1\n-1 2\n1 3\n1 -3").unwrap();
+        let mut solver = Solver::with_config(cnf, config.clone());
+        assert!(solver.solve().is_sat());
+
+        // without the unit clause, the clauses with 1 and 3 have to be watched again
+        solver.remove_clause(0);
+        match solver.solve() {
+            SolveResult::Sat(model) => {
+                assert!(solver.cnf().is_satisfied(&model));
+                assert_eq!(model.get(1), Some(true));
+            }
+            _ => panic!("Formula is satisfiable"),
+        }
+
+        let cnf = parse_cnf_from_str("1\n-2\n1 2 3\n-1 -3\n3 4\n-4 2").unwrap();
+        assert_eq!(Solver::with_config(cnf, config).solve(), SolveResult::Unsat);
+    }
+
+    #[test]
+    fn test_solver_static_order() {
+        let cnf = parse_cnf_from_str("1 2\n-1 -2\n3 4").unwrap();
+        let solve_with = |heuristic| {
+            let config = SolverConfig {
+                heuristic,
+                ..SolverConfig::default()
+            };
+            match Solver::with_config(cnf.clone(), config).solve() {
+                SolveResult::Sat(model) => model,
+                _ => panic!("Formula is satisfiable"),
+            }
+        };
+
+        // numeric order decides 1 = false, forcing 2 = true
+        assert_eq!(solve_with(Heuristic::Numeric).get(2), Some(true));
+        // deciding 2 = false first forces 1 = true, 3 is decided afterwards
+        let model = solve_with(Heuristic::Static(vec![2]));
+        assert_eq!(model.get(1), Some(true));
+        assert_eq!(model.get(2), Some(false));
+        assert_eq!(model.get(3), Some(false));
+    }
+
+    #[test]
+    fn test_solver_polarity() {
+        // 1 occurs twice positive and once negative, deciding it first satisfies the formula
+        let cnf = parse_cnf_from_str("1 2\n1 3\n-1 4").unwrap();
+        let first_decision = |polarity| {
+            let config = SolverConfig {
+                polarity,
+                ..SolverConfig::default()
+            };
+            match Solver::with_config(cnf.clone(), config).solve() {
+                SolveResult::Sat(model) => model.get(1),
+                _ => panic!("Formula is satisfiable"),
+            }
+        };
+
+        assert_eq!(first_decision(Polarity::False), Some(false));
+        assert_eq!(first_decision(Polarity::True), Some(true));
+        assert_eq!(
+            first_decision(Polarity::OccurrenceBased {
+                prefer_frequent: true
+            }),
+            Some(true)
+        );
+        assert_eq!(
+            first_decision(Polarity::OccurrenceBased {
+                prefer_frequent: false
+            }),
+            Some(false)
+        );
     }
 
     #[test]
@@ -483,7 +4382,6 @@ mod tests {
         let mut dls = vec![DecisionLevel {
             assignment: Assignment::new_with(100, true),
             changed_var: 100,
-            next_var_at_least: 0,
             flipped: true,
         }];
 
@@ -500,25 +4398,21 @@ mod tests {
             DecisionLevel {
                 assignment: Assignment::new_with(100, true),
                 changed_var: 100,
-                next_var_at_least: 0,
                 flipped: true,
             },
             DecisionLevel {
                 assignment: Assignment::new_with(10, true),
                 changed_var: 10,
-                next_var_at_least: 0,
                 flipped: true,
             },
             DecisionLevel {
                 assignment: Assignment::new_with(50, true),
                 changed_var: 50,
-                next_var_at_least: 0,
                 flipped: true,
             },
             DecisionLevel {
                 assignment: Assignment::new_with(120, true),
                 changed_var: 120,
-                next_var_at_least: 0,
                 flipped: true,
             },
         ];
@@ -536,7 +4430,6 @@ mod tests {
         let mut dls = vec![DecisionLevel {
             assignment: Assignment::new_with(100, true),
             changed_var: 100,
-            next_var_at_least: 0,
             flipped: false,
         }];
 
@@ -549,7 +4442,6 @@ mod tests {
             vec![DecisionLevel {
                 assignment: Assignment::new_with(100, false),
                 changed_var: 100,
-                next_var_at_least: 0,
                 flipped: true,
             }]
         );
@@ -561,13 +4453,11 @@ mod tests {
             DecisionLevel {
                 assignment: Assignment::new_with(100, true),
                 changed_var: 100,
-                next_var_at_least: 0,
                 flipped: false,
             },
             DecisionLevel {
                 assignment: Assignment::new_with(100, true).with(50, false),
                 changed_var: 50,
-                next_var_at_least: 0,
                 flipped: false,
             },
             DecisionLevel {
@@ -575,7 +4465,6 @@ mod tests {
                     .with(50, false)
                     .with(120, true),
                 changed_var: 120,
-                next_var_at_least: 0,
                 flipped: true,
             },
         ];
@@ -590,13 +4479,11 @@ mod tests {
                 DecisionLevel {
                     assignment: Assignment::new_with(100, true),
                     changed_var: 100,
-                    next_var_at_least: 0,
                     flipped: false,
                 },
                 DecisionLevel {
                     assignment: Assignment::new_with(100, true).with(50, true), // this true now
                     changed_var: 50,
-                    next_var_at_least: 0,
                     flipped: true, // this now flipped
                 },
                 /* popped off:
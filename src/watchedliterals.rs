@@ -1,31 +1,68 @@
-use std::collections::HashMap;
+use std::ops::Index;
 
 use crate::{
     assignment::Assignment,
-    cnf::{Clause, Cnf, LiteralTpl},
+    cnf::{Clause, Cnf, LiteralTpl, Var},
 };
 
 use std::fmt::Debug;
 
+/// The two watched literals of every clause of a formula
+///
+/// # Invariants
+///
+/// * a clause with at least two literals watches two different literals of itself unless
+///   it has been unwatched or skipped as satisfied, a unit clause watches its literal as
+///   both watches and the empty clause watches nothing
+/// * `access_map[lit]` contains a clause index iff `lit` is watched in that clause, a unit
+///   clause is contained only once
+///
+/// The panics in this module only fire if one of these is violated. They do not depend on
+/// the formula: a [Clause] never contains a literal twice, so its first two literals are
+/// always different and a replacement literal is never one of the watched ones. A
+/// tautology may watch both literals of a variable, so the watches are told apart by
+/// their sign as well. The invariants only rely on [WatchedLiterals::update] always being
+/// called with the formula this instance was created for.
+///
+/// Backtracking needs no undo: an update keeps a false watch only if the other watch is
+/// true or implied by the same update. The other watch is thus assigned on the same or an
+/// earlier decision level, and a backtrack never unassigns it while keeping the false one.
 pub struct WatchedLiterals {
     /// contains all watched literals indexed by the clause index
     watched_literals: Vec<Option<(LiteralTpl, LiteralTpl)>>,
 
     /// maps from a literal to all clause indices that watch this literal
-    access_map: HashMap<LiteralTpl, Vec<usize>>, // TODO: more efficient data structure than vec
+    access_map: WatchLists,
+
+    /// the position in each clause where the last replacement literal was found, the next
+    /// search for a replacement starts there and wraps around at the end of the clause
+    scan_positions: Vec<usize>,
+
+    /// the number of literals looked up in an assignment by all updates so far
+    literal_accesses: u64,
+
+    /// the unit clauses watched since the last update with their literal, the next update
+    /// propagates them
+    pending_units: Vec<(LiteralTpl, usize)>,
 }
 
 #[derive(Debug)]
 pub enum UpdateResult {
-    Unsatisfiable,
-    Satisfiable { propagations: Vec<LiteralTpl> },
+    /// The clause with the given index has become unsatisfiable
+    Unsatisfiable { clause_idx: usize },
+    /// No clause has become unsatisfiable, the clauses with the given indices have become unit
+    /// and propagate the given literals
+    Satisfiable { propagations: Vec<(LiteralTpl, usize)> },
 }
 
 #[cfg(test)]
 impl PartialEq for UpdateResult {
     fn eq(&self, other: &Self) -> bool {
         match self {
-            Self::Unsatisfiable => matches!(other, Self::Unsatisfiable),
+            Self::Unsatisfiable { clause_idx } => matches!(
+                other,
+                Self::Unsatisfiable { clause_idx: other_idx } if clause_idx == other_idx
+            ),
             Self::Satisfiable { propagations: prp } => match other {
                 Self::Satisfiable {
                     propagations: other_prp,
@@ -44,32 +81,235 @@ impl WatchedLiterals {
     /// Returns a new WatchedLiterals instance for the specified formula
     pub fn new(cnf: &Cnf) -> Self {
         let mut watched_literals = WatchedLiterals {
-            watched_literals: vec![None; cnf.clauses.len()],
-            access_map: HashMap::new(),
+            watched_literals: Vec::with_capacity(cnf.clauses.len()),
+            access_map: WatchLists::default(),
+            scan_positions: Vec::with_capacity(cnf.clauses.len()),
+            literal_accesses: 0,
+            pending_units: Vec::new(),
         };
 
-        for (clause_idx, clause) in cnf.clauses.iter().enumerate() {
-            let mut literals = clause.literals();
-            match (literals.next(), literals.next()) {
-                (Some(lit0), Some(lit1)) => {
-                    watched_literals.set_watch(clause_idx, lit0, lit1);
-                }
-                _ => {
-                    // The clause contains less than two literals
-                    // So there is nothing to watch here
+        watched_literals.add_clauses(cnf, None);
+        watched_literals
+    }
+
+    /// Like [new](WatchedLiterals::new), but does not watch the clauses satisfied by the given
+    /// assignment, usually the one made by the unit clauses of the formula
+    ///
+    /// [update](WatchedLiterals::update) never looks at a skipped clause again, so this is
+    /// only correct as long as the assignment holds in every search.
+    pub fn new_skipping_satisfied(cnf: &Cnf, assignment: &Assignment) -> Self {
+        let mut watched_literals = Self::new(&Cnf::new());
+        watched_literals.add_clauses(cnf, Some(assignment));
+        watched_literals
+    }
+
+    /// The number of literals looked up in an assignment by all updates so far
+    ///
+    /// Every literal checked while searching a replacement watch counts once, which is a
+    /// measure of the propagation effort independent of the machine.
+    pub fn literal_accesses(&self) -> u64 {
+        self.literal_accesses
+    }
+
+    /// Drops all watches and watches the clauses of the given formula instead, like
+    /// [new](WatchedLiterals::new) but keeping the allocations of this instance
+    pub fn reset(&mut self, cnf: &Cnf) {
+        self.clear();
+        self.add_clauses(cnf, None);
+    }
+
+    /// Like [reset](WatchedLiterals::reset), but skips the clauses satisfied by the given
+    /// assignment like [new_skipping_satisfied](WatchedLiterals::new_skipping_satisfied)
+    pub fn reset_skipping_satisfied(&mut self, cnf: &Cnf, assignment: &Assignment) {
+        self.clear();
+        self.add_clauses(cnf, Some(assignment));
+    }
+
+    fn clear(&mut self) {
+        self.watched_literals.clear();
+        self.scan_positions.clear();
+        self.pending_units.clear();
+        for clause_indices in self.access_map.values_mut() {
+            clause_indices.clear();
+        }
+    }
+
+    /// Watches all clauses of the formula, except for the ones satisfied by `skip_satisfied`
+    fn add_clauses(&mut self, cnf: &Cnf, skip_satisfied: Option<&Assignment>) {
+        for clause in &cnf.clauses {
+            match skip_satisfied {
+                Some(assignment) if clause.is_satisfied(assignment) => {
+                    self.watched_literals.push(None);
+                    self.scan_positions.push(0);
                 }
+                _ => self.add_clause(clause),
             }
         }
+    }
 
-        watched_literals
+    /// Watches the given clause, which is appended to the clause list of the formula
+    ///
+    /// The first two literals are watched, so no literal of the clause may be assigned in
+    /// an ongoing search. A unit clause is an exception: the next
+    /// [update](WatchedLiterals::update) propagates its literal, or reports the clause as
+    /// unsatisfiable if the literal is false by then.
+    pub fn add_clause(&mut self, clause: &Clause) {
+        let mut literals = clause.literals();
+        match (literals.next(), literals.next()) {
+            (Some(lit0), Some(lit1)) => {
+                self.add_clause_watching(lit0, lit1);
+            }
+            (Some(lit), None) => {
+                let clause_idx = self.watched_literals.len();
+                self.watched_literals.push(None);
+                self.scan_positions.push(0);
+                self.watch_unit(clause_idx, lit);
+            }
+            (None, _) => {
+                // The empty clause has nothing to watch
+                self.watched_literals.push(None);
+                self.scan_positions.push(0);
+            }
+        }
+    }
+
+    /// Like [add_clause](WatchedLiterals::add_clause), but watches the two given literals,
+    /// which have to be different literals of the clause
+    ///
+    /// During a search, these should be literals which are not false, if there are any.
+    pub fn add_clause_watching(&mut self, lit0: LiteralTpl, lit1: LiteralTpl) {
+        let clause_idx = self.watched_literals.len();
+        self.watched_literals.push(None);
+        self.scan_positions.push(0);
+        self.set_watch(clause_idx, lit0, lit1);
     }
 
     /// Adds the given literal in the given clause to the watched list, without any further updates
     fn set_watch(&mut self, clause_idx: usize, lit0: LiteralTpl, lit1: LiteralTpl) {
         self.watched_literals[clause_idx] = Some((lit0, lit1));
 
-        self.access_map.entry(lit0).or_default().push(clause_idx);
-        self.access_map.entry(lit1).or_default().push(clause_idx);
+        self.access_map.push(lit0, clause_idx);
+        self.access_map.push(lit1, clause_idx);
+    }
+
+    /// Watches the only literal of a unit clause and marks it for propagation
+    ///
+    /// Once the literal is false, updating the clause finds no replacement for either watch
+    /// and reports it as unsatisfiable.
+    fn watch_unit(&mut self, clause_idx: usize, lit: LiteralTpl) {
+        self.watched_literals[clause_idx] = Some((lit, lit));
+        self.access_map.push(lit, clause_idx);
+        self.pending_units.push((lit, clause_idx));
+    }
+
+    /// Stops watching the clause with the given index, keeping the indices of all clauses
+    pub fn unwatch_clause(&mut self, clause_idx: usize) {
+        self.pending_units.retain(|&(_, ci)| ci != clause_idx);
+        if let Some((lit0, lit1)) = self.watched_literals[clause_idx].take() {
+            for lit in [lit0, lit1].iter() {
+                let clause_indices = self
+                    .access_map
+                    .get_mut(lit)
+                    .expect("Watched literal has no access map entry");
+                clause_indices.retain(|&ci| ci != clause_idx);
+            }
+        }
+    }
+
+    /// Drops the clauses whose entry in `keep` is false in a single pass
+    ///
+    /// The remaining clauses are renumbered consecutively, matching a `retain` on the clause
+    /// list of the formula. `keep` has an entry for every clause.
+    pub fn retain_clauses(&mut self, keep: &[bool]) {
+        let mut new_indices = Vec::with_capacity(keep.len());
+        let mut next_idx = 0;
+        for &kept in keep {
+            new_indices.push(next_idx);
+            if kept {
+                next_idx += 1;
+            }
+        }
+
+        let mut idx = 0;
+        self.watched_literals.retain(|_| {
+            idx += 1;
+            keep[idx - 1]
+        });
+        let mut idx = 0;
+        self.scan_positions.retain(|_| {
+            idx += 1;
+            keep[idx - 1]
+        });
+        for clause_indices in self.access_map.values_mut() {
+            clause_indices.retain(|&ci| keep[ci]);
+            for ci in clause_indices.iter_mut() {
+                *ci = new_indices[*ci];
+            }
+        }
+        self.pending_units.retain(|&(_, ci)| keep[ci]);
+        for (_, ci) in self.pending_units.iter_mut() {
+            *ci = new_indices[*ci];
+        }
+    }
+
+    /// Watches the given clause, which is inserted into the clause list of the formula at
+    /// the given index
+    ///
+    /// The indices of all following clauses are incremented by one. Like
+    /// [add_clause](WatchedLiterals::add_clause), the first two literals are watched.
+    pub fn insert_clause(&mut self, clause_idx: usize, clause: &Clause) {
+        for clause_indices in self.access_map.values_mut() {
+            for ci in clause_indices.iter_mut().filter(|ci| **ci >= clause_idx) {
+                *ci += 1;
+            }
+        }
+        for (_, ci) in self
+            .pending_units
+            .iter_mut()
+            .filter(|(_, ci)| *ci >= clause_idx)
+        {
+            *ci += 1;
+        }
+
+        self.watched_literals.insert(clause_idx, None);
+        self.scan_positions.insert(clause_idx, 0);
+        let mut literals = clause.literals();
+        match (literals.next(), literals.next()) {
+            (Some(lit0), Some(lit1)) => self.set_watch(clause_idx, lit0, lit1),
+            (Some(lit), None) => self.watch_unit(clause_idx, lit),
+            (None, _) => {}
+        }
+    }
+
+    /// Drops the watches of the clause with the given index
+    ///
+    /// The indices of all following clauses are decremented by one, matching a removal
+    /// from the clause list of the formula.
+    pub fn remove_clause(&mut self, clause_idx: usize) {
+        self.scan_positions.remove(clause_idx);
+        self.pending_units.retain(|&(_, ci)| ci != clause_idx);
+        for (_, ci) in self
+            .pending_units
+            .iter_mut()
+            .filter(|(_, ci)| *ci > clause_idx)
+        {
+            *ci -= 1;
+        }
+        if let Some((lit0, lit1)) = self.watched_literals.remove(clause_idx) {
+            for lit in [lit0, lit1].iter() {
+                let clause_indices = self
+                    .access_map
+                    .get_mut(lit)
+                    .expect("Watched literal has no access map entry");
+                clause_indices.retain(|&ci| ci != clause_idx);
+            }
+        }
+
+        for clause_indices in self.access_map.values_mut() {
+            for ci in clause_indices.iter_mut().filter(|ci| **ci > clause_idx) {
+                *ci -= 1;
+            }
+        }
     }
 
     fn replace_watched_literal(
@@ -79,19 +319,15 @@ impl WatchedLiterals {
         new_wl: LiteralTpl,
     ) {
         // Delete old_wl in access map
-        match self.access_map.get_mut(&old_wl) {
-            Some(clause_indices) => {
-                let position = clause_indices
-                    .iter()
-                    .position(|&ci| ci == clause_idx)
-                    .expect("Cannot remove clause from list which does not contain it");
-
-                clause_indices.swap_remove(position);
-            }
-            None => {
-                unreachable!("Cannot remove watched literal without access map entry")
-            }
-        }
+        let clause_indices = self
+            .access_map
+            .get_mut(&old_wl)
+            .expect("Watched literal has no access map entry");
+        let position = clause_indices
+            .iter()
+            .position(|&ci| ci == clause_idx)
+            .expect("Access map entry of watched literal does not contain the clause");
+        clause_indices.swap_remove(position);
 
         // Replace watched literal in self.watched_literals
         let wls = self.watched_literals[clause_idx]
@@ -103,11 +339,11 @@ impl WatchedLiterals {
         } else if wls.1 == old_wl {
             *wls = (wls.0, new_wl);
         } else {
-            unreachable!("Specified clause index does not contain this watched literal");
+            panic!("Specified clause index does not watch this literal");
         }
 
         // Add new entry to access map
-        self.access_map.entry(new_wl).or_default().push(clause_idx);
+        self.access_map.push(new_wl, clause_idx);
     }
 
     pub fn update(
@@ -119,14 +355,25 @@ impl WatchedLiterals {
         let (var, val) = new_assignment;
 
         // Assert the new assignment does in fact contain the new assigned literal
-        assert!(matches!(
-            assignment.get(var),
-            Some(val) if val == val
-        ));
+        invariant!(assignment.get(var) == Some(val));
 
         // All learned propagations
         let mut propagations = Vec::new();
 
+        // The unit clauses watched since the last update propagate their literal
+        if let Some(&(_, clause_idx)) = self
+            .pending_units
+            .iter()
+            .find(|&&(lit, _)| assignment.get_lit(lit) == Some(false))
+        {
+            return UpdateResult::Unsatisfiable { clause_idx };
+        }
+        for (lit, clause_idx) in self.pending_units.drain(..) {
+            if assignment.get_lit(lit).is_none() {
+                propagations.push((lit, clause_idx));
+            }
+        }
+
         // Find all watched literals made unsatisfying due to the new assignment
         let watched_literal = (var, !val);
         match self.access_map.get_mut(&watched_literal) {
@@ -150,17 +397,17 @@ impl WatchedLiterals {
                         }
                         CheckClauseAfterUpdateResult::UnsatisfiableClause => {
                             // The clause has become unsatisfiable
-                            return UpdateResult::Unsatisfiable;
+                            return UpdateResult::Unsatisfiable { clause_idx };
                         }
                     };
                 }
 
                 // No clause has become unsatisfiable, the old watched literal could be replaced
-                return UpdateResult::Satisfiable { propagations };
+                UpdateResult::Satisfiable { propagations }
             }
             None => {
                 // There is no conflict as there is no clause with the opposite literal
-                return UpdateResult::Satisfiable { propagations };
+                UpdateResult::Satisfiable { propagations }
             }
         }
     }
@@ -172,20 +419,28 @@ impl WatchedLiterals {
         clause: &Clause,
         assignment: &Assignment,
         new_assignment: LiteralTpl,
-        propagations: &mut Vec<LiteralTpl>,
+        propagations: &mut Vec<(LiteralTpl, usize)>,
     ) -> CheckClauseAfterUpdateResult {
         let (wl0, wl1) = self.watched_literals[clause_idx]
             .expect("Cannot update clause not having watched literals");
 
-        let other_wl = if wl0.0 == new_assignment.0 {
+        let falsified = (new_assignment.0, !new_assignment.1);
+        let other_wl = if wl0 == falsified {
             wl1
-        } else if wl1.0 == new_assignment.0 {
+        } else if wl1 == falsified {
             wl0
         } else {
             panic!("Old watched literal not contained in watched literal list")
         };
 
-        match Self::find_replacement_literal(clause, assignment, other_wl) {
+        let scan_position = &mut self.scan_positions[clause_idx];
+        match Self::find_replacement_literal(
+            clause,
+            assignment,
+            other_wl,
+            scan_position,
+            &mut self.literal_accesses,
+        ) {
             FindOtherSuitableLiteral::GivenLiteralSatisfying => {
                 // The watched literal one_wl is satisfying, so no changes needed
                 CheckClauseAfterUpdateResult::KeepLiteral
@@ -203,7 +458,7 @@ impl WatchedLiterals {
             FindOtherSuitableLiteral::UnitClauseWithGiven => {
                 // The other_wl has become unit, so propagate it and keep the watched literals as is
                 // because other_wl becomes valid
-                propagations.push(other_wl);
+                propagations.push((other_wl, clause_idx));
                 CheckClauseAfterUpdateResult::KeepLiteral
             }
             FindOtherSuitableLiteral::UnitClause(other_lit) => {
@@ -211,7 +466,7 @@ impl WatchedLiterals {
                 // This is a unit clause, so we can propagate this literal
                 // We have to swap the old watched literal (which is false) with this one
                 // so it keeps getting watched
-                propagations.push(other_lit);
+                propagations.push((other_lit, clause_idx));
                 CheckClauseAfterUpdateResult::SwapTo(other_lit)
             }
             FindOtherSuitableLiteral::UnsatisfiableClause => {
@@ -226,28 +481,36 @@ impl WatchedLiterals {
     /// # Arguments
     /// 
     /// * `second_wl` - the other literal that is already watched
+    /// * `scan_position` - the position of the literal to start the search at, the search wraps
+    ///   around at the end of the clause. Set to the position of the returned literal, if any.
+    ///   Starting where the last replacement was found avoids rescanning the false literals at
+    ///   the start of long clauses over and over.
+    /// * `literal_accesses` - incremented for every literal looked up in the assignment
     fn find_replacement_literal(
         cls: &Clause,
         assignment: &Assignment,
         second_wl: LiteralTpl,
+        scan_position: &mut usize,
+        literal_accesses: &mut u64,
     ) -> FindOtherSuitableLiteral {
         // First check if second_wl is valid and thus no replacement needed
+        *literal_accesses += 1;
         if let Some(true) = assignment.get_lit(second_wl) {
             return FindOtherSuitableLiteral::GivenLiteralSatisfying;
         }
 
         let mut unassigned_literal = None;
 
-        for lit in cls.literals() {
+        let start = *scan_position;
+        let literals = || cls.literals().enumerate();
+        for (position, lit) in literals().skip(start).chain(literals().take(start)) {
+            *literal_accesses += 1;
             match assignment.get_lit(lit) {
                 Some(true) => {
-                    // A satisfying watched literal was found
-                    return if lit == second_wl {
-                        // This case is already checked in the beginning
-                        unreachable!();
-                    } else {
-                        FindOtherSuitableLiteral::OtherLiteralSatisfying(lit)
-                    };
+                    // A satisfying literal was found, it is not second_wl as this case
+                    // is already checked in the beginning
+                    *scan_position = position;
+                    return FindOtherSuitableLiteral::OtherLiteralSatisfying(lit);
                 }
 
                 Some(false) => {
@@ -259,19 +522,21 @@ impl WatchedLiterals {
                     // Literal is unassigned
 
                     match unassigned_literal {
-                        Some(stored_lit) => {
+                        Some((stored_position, stored_lit)) => {
                             // Multiple unassigned literals found
                             // Return the one that is not second_wl
                             return if lit == second_wl {
-                                assert!(stored_lit != second_wl);
+                                invariant!(stored_lit != second_wl);
+                                *scan_position = stored_position;
                                 FindOtherSuitableLiteral::MultipleUnassigned(stored_lit)
                             } else {
+                                *scan_position = position;
                                 FindOtherSuitableLiteral::MultipleUnassigned(lit)
                             };
                         }
                         None => {
                             // No other unassigned literal found (yet)
-                            unassigned_literal = Some(lit)
+                            unassigned_literal = Some((position, lit))
                         }
                     }
                 }
@@ -280,19 +545,20 @@ impl WatchedLiterals {
 
         // No true literal found and not multiple unassigned
         match unassigned_literal {
-            Some(lit) => {
+            Some((position, lit)) => {
                 // One (and only one!) unassigned literal was found
                 // Check if this is second_wl
                 if lit == second_wl {
                     FindOtherSuitableLiteral::UnitClauseWithGiven
                 } else {
+                    *scan_position = position;
                     FindOtherSuitableLiteral::UnitClause(lit)
                 }
             }
             None => {
                 // No unassigned and no true literal was found
                 // This clause is unsatisfiable
-                return FindOtherSuitableLiteral::UnsatisfiableClause;
+                FindOtherSuitableLiteral::UnsatisfiableClause
             }
         }
     }
@@ -301,7 +567,11 @@ impl WatchedLiterals {
 impl Debug for WatchedLiterals {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{{")?;
-        for (literal, watched_in) in self.access_map.iter() {
+        let watched = self
+            .access_map
+            .iter()
+            .filter(|(_, watched_in)| !watched_in.is_empty());
+        for (literal, watched_in) in watched {
             write!(
                 f,
                 "{:+02}: {:?},",
@@ -315,6 +585,59 @@ impl Debug for WatchedLiterals {
     }
 }
 
+/// The indices of the clauses watching each literal
+///
+/// The lists are stored contiguously at the [index](literal_index) of their literal, so
+/// looking up the clauses of a literal during propagation needs no hashing. A literal
+/// without a list, e.g. one above the highest watched variable, is watched nowhere.
+#[derive(Debug, Default)]
+struct WatchLists(Vec<Vec<usize>>);
+
+/// The position of the watch list of the given literal, `2 * var + val`
+fn literal_index((var, val): LiteralTpl) -> usize {
+    2 * var as usize + val as usize
+}
+
+impl WatchLists {
+    fn get_mut(&mut self, lit: &LiteralTpl) -> Option<&mut Vec<usize>> {
+        self.0.get_mut(literal_index(*lit))
+    }
+
+    /// Appends the clause index to the list of the literal, growing the lists as needed
+    fn push(&mut self, lit: LiteralTpl, clause_idx: usize) {
+        let idx = literal_index(lit);
+        if idx >= self.0.len() {
+            self.0.resize_with(idx + 1, Vec::new);
+        }
+        self.0[idx].push(clause_idx);
+    }
+
+    /// The lists of all literals up to the highest one watched so far, including empty ones
+    fn iter(&self) -> impl Iterator<Item = (LiteralTpl, &Vec<usize>)> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(idx, clause_indices)| (((idx / 2) as Var, idx % 2 == 1), clause_indices))
+    }
+
+    #[cfg(test)]
+    fn values(&self) -> std::slice::Iter<'_, Vec<usize>> {
+        self.0.iter()
+    }
+
+    fn values_mut(&mut self) -> impl Iterator<Item = &mut Vec<usize>> + '_ {
+        self.0.iter_mut()
+    }
+}
+
+impl Index<&LiteralTpl> for WatchLists {
+    type Output = [usize];
+
+    fn index(&self, lit: &LiteralTpl) -> &[usize] {
+        self.0.get(literal_index(*lit)).map_or(&[], Vec::as_slice)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 enum FindOtherSuitableLiteral {
     /// The given (second) watched literal is already satisfying, so no second watched literal is needed
@@ -340,7 +663,14 @@ enum CheckClauseAfterUpdateResult {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::input::parse_cnf_from_str;
+    use std::collections::HashMap;
+
+    use crate::{
+        cnf::negate,
+        input::parse_cnf_from_str,
+        rng::{random_cnf, Rng},
+        satsolve::is_satisfiable,
+    };
 
     fn two_literal_eq((a0, a1): (LiteralTpl, LiteralTpl), b0: LiteralTpl, b1: LiteralTpl) -> bool {
         (a0 == b0 && a1 == b1) || (a0 == b1 && a1 == b0)
@@ -352,7 +682,15 @@ mod tests {
         let wl = WatchedLiterals::new(&cnf);
 
         // WatchedLiteral#watched_literals
-        assert_eq!(&wl.watched_literals[0..3], &[None, None, None]);
+        assert_eq!(
+            &wl.watched_literals[0..3],
+            &[
+                None,
+                Some(((1, true), (1, true))),
+                Some(((15, false), (15, false)))
+            ]
+        );
+        assert_eq!(wl.pending_units, [((1, true), 1), ((15, false), 2)]);
         assert!(two_literal_eq(
             wl.watched_literals[3].unwrap(),
             (2, true),
@@ -376,12 +714,18 @@ mod tests {
 
         // WatchedLiteral#access_map
         let mut map = HashMap::new();
-        map.insert((1, true), vec![4, 5]);
+        map.insert((1, true), vec![1, 4, 5]);
+        map.insert((15, false), vec![2]);
         map.insert((2, true), vec![3, 5]);
         map.insert((3, true), vec![3]);
         map.insert((4, false), vec![4, 6]);
         map.insert((5, true), vec![6]);
-        assert_eq!(wl.access_map, map);
+        let watched = wl
+            .access_map
+            .iter()
+            .filter(|(_, clause_indices)| !clause_indices.is_empty())
+            .map(|(lit, clause_indices)| (lit, clause_indices.clone()));
+        assert_eq!(watched.collect::<HashMap<_, _>>(), map);
     }
 
     #[test]
@@ -392,7 +736,9 @@ mod tests {
             WatchedLiterals::find_replacement_literal(
                 &cnf.clauses[0],
                 &Assignment::new().with(2, false),
-                (3, true)
+                (3, true),
+                &mut 0,
+                &mut 0
             ),
             FindOtherSuitableLiteral::UnitClauseWithGiven
         );
@@ -401,7 +747,9 @@ mod tests {
             WatchedLiterals::find_replacement_literal(
                 &cnf.clauses[3],
                 &Assignment::new().with(4, true).with(6, true),
-                (5, true)
+                (5, true),
+                &mut 0,
+                &mut 0
             ),
             FindOtherSuitableLiteral::UnitClauseWithGiven
         );
@@ -410,7 +758,9 @@ mod tests {
             WatchedLiterals::find_replacement_literal(
                 &cnf.clauses[3],
                 &Assignment::new().with(4, true).with(6, true),
-                (6, false)
+                (6, false),
+                &mut 0,
+                &mut 0
             ),
             FindOtherSuitableLiteral::UnitClause((5, true))
         );
@@ -419,7 +769,9 @@ mod tests {
             WatchedLiterals::find_replacement_literal(
                 &cnf.clauses[3],
                 &Assignment::new().with(4, true).with(6, false),
-                (6, false)
+                (6, false),
+                &mut 0,
+                &mut 0
             ),
             FindOtherSuitableLiteral::GivenLiteralSatisfying
         );
@@ -428,7 +780,9 @@ mod tests {
             WatchedLiterals::find_replacement_literal(
                 &cnf.clauses[3],
                 &Assignment::new().with(4, true).with(6, false),
-                (5, true)
+                (5, true),
+                &mut 0,
+                &mut 0
             ),
             FindOtherSuitableLiteral::OtherLiteralSatisfying((6, false))
         );
@@ -437,7 +791,9 @@ mod tests {
             WatchedLiterals::find_replacement_literal(
                 &cnf.clauses[3],
                 &Assignment::new().with(6, true),
-                (5, true)
+                (5, true),
+                &mut 0,
+                &mut 0
             ),
             FindOtherSuitableLiteral::MultipleUnassigned((4, false))
         );
@@ -446,12 +802,68 @@ mod tests {
             WatchedLiterals::find_replacement_literal(
                 &cnf.clauses[3],
                 &Assignment::new().with(4, true).with(5, false).with(6, true),
-                (5, true)
+                (5, true),
+                &mut 0,
+                &mut 0
             ),
             FindOtherSuitableLiteral::UnsatisfiableClause
         );
     }
 
+    #[test]
+    fn test_watchedliteral_replacement_scan_position() {
+        let cnf = parse_cnf_from_str("1 2 3 4 5").unwrap();
+        let clause = &cnf.clauses[0];
+
+        // the scan starts at the given position
+        let mut scan_position = 3;
+        let mut literal_accesses = 0;
+        assert_eq!(
+            WatchedLiterals::find_replacement_literal(
+                clause,
+                &Assignment::new(),
+                (1, true),
+                &mut scan_position,
+                &mut literal_accesses
+            ),
+            FindOtherSuitableLiteral::MultipleUnassigned((5, true))
+        );
+        assert_eq!(scan_position, 4);
+        // the other watch, 4 and 5
+        assert_eq!(literal_accesses, 3);
+
+        // and wraps around at the end of the clause
+        let assignment = Assignment::new()
+            .with(3, false)
+            .with(4, false)
+            .with(5, false);
+        assert_eq!(
+            WatchedLiterals::find_replacement_literal(
+                clause,
+                &assignment,
+                (1, true),
+                &mut scan_position,
+                &mut 0
+            ),
+            FindOtherSuitableLiteral::MultipleUnassigned((2, true))
+        );
+        assert_eq!(scan_position, 1);
+
+        // no replacement leaves the position as is
+        let assignment = assignment.with(2, false);
+        assert_eq!(
+            WatchedLiterals::find_replacement_literal(
+                clause,
+                &assignment,
+                (1, true),
+                &mut scan_position,
+                &mut 0
+            ),
+            FindOtherSuitableLiteral::UnitClauseWithGiven
+        );
+        assert_eq!(scan_position, 1);
+    }
+
     #[test]
     fn test_watchedliteral_checkclauseafterupdate_simple() {
         let cnf = parse_cnf_from_str("2 3\n1 -4\n1 2 3\n-4 5 -6").unwrap();
@@ -469,7 +881,7 @@ mod tests {
         assert_eq!(result, CheckClauseAfterUpdateResult::KeepLiteral);
 
         // Test correct propagations
-        assert_eq!(propagations, vec![(3, true)]);
+        assert_eq!(propagations, vec![((3, true), 0)]);
     }
 
     #[test]
@@ -482,7 +894,7 @@ mod tests {
         result,
         UpdateResult::Satisfiable {
             propagations 
-        } if propagations == vec![(3, true)]));
+        } if propagations == vec![((3, true), 0)]));
     }
 
     #[test]
@@ -515,23 +927,441 @@ mod tests {
         assert!([lit0, lit1].contains(&(4, false)) || [lit0, lit1].contains(&(6, false)));
     }
 
+    #[test]
+    fn test_watchedliteral_update_unsatisfiable() {
+        let cnf = parse_cnf_from_str("1 2 3\n-1 -2\n-2 -3").unwrap();
+        let mut wl = WatchedLiterals::new(&cnf);
+        let mut assignment = Assignment::new();
+
+        assignment.change(1, true);
+        assert_eq!(
+            wl.update(&cnf, &assignment, (1, true)),
+            UpdateResult::Satisfiable {
+                propagations: vec![((2, false), 1)]
+            }
+        );
+
+        assignment.change(2, true);
+        assert_eq!(
+            wl.update(&cnf, &assignment, (2, true)),
+            UpdateResult::Unsatisfiable { clause_idx: 1 }
+        );
+    }
+
     #[test]
     fn test_watchedliteral_replace() {
         let mut wl = WatchedLiterals::new(&parse_cnf_from_str("1 2 3").unwrap());
         wl.replace_watched_literal(0, (2, true), (3, true));
 
         assert_eq!(wl.watched_literals, vec![Some(((1, true), (3, true)))]);
-        assert_eq!(wl.access_map, {
-            let mut map = HashMap::new();
-            map.insert((1, true), vec![0]);
-            map.insert((2, true), vec![]);
-            map.insert((3, true), vec![0]);
-            map
-        });
+        assert_eq!(wl.access_map[&(1, true)], [0]);
+        assert_eq!(wl.access_map[&(2, true)], Vec::<usize>::new());
+        assert_eq!(wl.access_map[&(3, true)], [0]);
     }
 
     #[test]
     fn test_watchedliterals_prop() {
-        let wl = WatchedLiterals::new(&parse_cnf_from_str("1 2 3\n-2 4\n-3\n-4 -1 -5").unwrap());
+        let _wl = WatchedLiterals::new(&parse_cnf_from_str("1 2 3\n-2 4\n-3\n-4 -1 -5").unwrap());
+    }
+
+    /// Checks the invariants documented on [WatchedLiterals]
+    fn assert_invariants(wl: &WatchedLiterals, cnf: &Cnf) {
+        for (clause_idx, clause) in cnf.clauses.iter().enumerate() {
+            match wl.watched_literals[clause_idx] {
+                Some((lit0, lit1)) => {
+                    assert_eq!(lit0 == lit1, clause.len() == 1);
+                    assert!(clause.literals().any(|lit| lit == lit0));
+                    assert!(clause.literals().any(|lit| lit == lit1));
+                }
+                None => assert!(clause.literals().nth(1).is_none()),
+            }
+        }
+
+        for (lit, clause_indices) in wl.access_map.iter() {
+            for &clause_idx in clause_indices {
+                let (lit0, lit1) = wl.watched_literals[clause_idx].unwrap();
+                assert!(lit == lit0 || lit == lit1);
+            }
+        }
+        let watches = wl
+            .watched_literals
+            .iter()
+            .flatten()
+            .map(|(lit0, lit1)| if lit0 == lit1 { 1 } else { 2 })
+            .sum::<usize>();
+        assert_eq!(wl.access_map.values().map(Vec::len).sum::<usize>(), watches);
+    }
+
+    #[test]
+    fn test_watchedliterals_skipping_satisfied() {
+        let cnf = parse_cnf_from_str("3\n3 1 2\n-3 1 2\n-1 -2\n2 3").unwrap();
+        let units = Assignment::new_with(3, true);
+        let mut wl = WatchedLiterals::new_skipping_satisfied(&cnf, &units);
+        assert_eq!(wl.watched_literals.len(), cnf.clauses.len());
+        assert_eq!(wl.watched_literals[1], None);
+        assert_eq!(wl.watched_literals[4], None);
+        assert!(wl.watched_literals[2].is_some());
+        let mut watched_clauses = wl.access_map.values().flatten();
+        assert!(watched_clauses.all(|&idx| idx == 2 || idx == 3));
+
+        // only the watched clauses with 2 are updated, the skipped ones stay untouched
+        let assignment = units.clone().with(2, false);
+        let result = wl.update(&cnf, &assignment, (2, false));
+        assert_eq!(
+            result,
+            UpdateResult::Satisfiable {
+                propagations: vec![((1, true), 2)]
+            }
+        );
+        assert_eq!(wl.watched_literals[1], None);
+        assert_eq!(wl.watched_literals[4], None);
+
+        wl.reset_skipping_satisfied(&cnf, &Assignment::new());
+        assert_invariants(&wl, &cnf);
+    }
+
+    #[test]
+    fn test_watchedliterals_reset() {
+        let cnf = parse_cnf_from_str("1 2 3\n-1 -2\n4 5 -6 7").unwrap();
+        let mut wl = WatchedLiterals::new(&cnf);
+
+        let other = parse_cnf_from_str("-3 4\n1\n2 -7\n1 2 3").unwrap();
+        wl.reset(&other);
+        assert_invariants(&wl, &other);
+        assert_eq!(wl.watched_literals.len(), other.clauses.len());
+
+        wl.reset(&cnf);
+        assert_invariants(&wl, &cnf);
+    }
+
+    #[test]
+    fn test_watchedliterals_random_updates() {
+        let mut rng = Rng::new(42);
+
+        for _ in 0..200 {
+            let mut cnf = Cnf::new();
+            for _ in 0..rng.below(12) {
+                let mut clause = Clause::new();
+                for _ in 0..rng.below(6) {
+                    // literals of variables already in the clause are skipped
+                    let var = rng.below(8) as Var + 1;
+                    if clause.get(var).is_none() {
+                        match rng.below(2) {
+                            0 => clause.add_negative(var),
+                            _ => clause.add_positive(var),
+                        }
+                    }
+                }
+                cnf.clauses.push(clause);
+            }
+
+            let mut wl = WatchedLiterals::new(&cnf);
+            let mut assignment = Assignment::new();
+            assert_invariants(&wl, &cnf);
+
+            let mut vars = (1..=8).collect::<Vec<Var>>();
+            rng.shuffle(&mut vars);
+            for var in vars {
+                let val = rng.below(2) == 0;
+                assignment.change(var, val);
+
+                match wl.update(&cnf, &assignment, (var, val)) {
+                    UpdateResult::Satisfiable { propagations } => {
+                        // every propagated literal is the last unassigned one of its clause
+                        for (lit, clause_idx) in propagations {
+                            let clause = &cnf.clauses[clause_idx];
+                            assert!(clause.literals().any(|l| l == lit));
+                            assert!(clause
+                                .literals()
+                                .all(|l| l == lit || assignment.get_lit(l) == Some(false)));
+                        }
+                    }
+                    UpdateResult::Unsatisfiable { clause_idx } => {
+                        let clause = &cnf.clauses[clause_idx];
+                        assert!(clause.literals().all(|l| assignment.get_lit(l) == Some(false)));
+                        break;
+                    }
+                }
+                assert_invariants(&wl, &cnf);
+            }
+        }
+    }
+
+    /// Assigns the literal and everything it implies, like a decision level of the search
+    ///
+    /// Returns the implied literals or the index of a falsified clause.
+    fn propagate_fully(
+        wl: &mut WatchedLiterals,
+        cnf: &Cnf,
+        assignment: &mut Assignment,
+        lit: LiteralTpl,
+    ) -> Result<Vec<LiteralTpl>, usize> {
+        assignment.change(lit.0, lit.1);
+        let mut queue = vec![lit];
+        let mut implied = Vec::new();
+        while let Some(lit) = queue.pop() {
+            match wl.update(cnf, assignment, lit) {
+                UpdateResult::Unsatisfiable { clause_idx } => return Err(clause_idx),
+                UpdateResult::Satisfiable { propagations } => {
+                    // a false literal is found unsatisfiable when its opposite is handled
+                    for (prop, _) in propagations {
+                        if assignment.get_lit(prop).is_none() {
+                            assignment.change(prop.0, prop.1);
+                            queue.push(prop);
+                            implied.push(prop);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(implied)
+    }
+
+    /// Checks that no propagation is missed: after propagating, a false watch is only kept
+    /// if the other watch satisfies the clause
+    fn assert_watches_complete(wl: &WatchedLiterals, cnf: &Cnf, assignment: &Assignment) {
+        for (clause_idx, watches) in wl.watched_literals.iter().enumerate() {
+            if let Some((lit0, lit1)) = *watches {
+                for &(watch, other) in [(lit0, lit1), (lit1, lit0)].iter() {
+                    if assignment.get_lit(watch) == Some(false) {
+                        assert_eq!(
+                            assignment.get_lit(other),
+                            Some(true),
+                            "clause {:?} under {:?}",
+                            cnf.clauses[clause_idx],
+                            assignment
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_watchedliterals_backtrack() {
+        // the watches are only moved, never restored, when the search backtracks
+        let cnf = parse_cnf_from_str("1 2 3 4").unwrap();
+        let mut wl = WatchedLiterals::new(&cnf);
+        let mut level1 = Assignment::new();
+        assert_eq!(
+            propagate_fully(&mut wl, &cnf, &mut level1, (1, false)),
+            Ok(vec![])
+        );
+
+        // watching 2 and 4 after deciding 3 = false
+        let mut level2 = level1.clone();
+        assert_eq!(
+            propagate_fully(&mut wl, &cnf, &mut level2, (3, false)),
+            Ok(vec![])
+        );
+        assert!(two_literal_eq(
+            wl.watched_literals[0].unwrap(),
+            (2, true),
+            (4, true)
+        ));
+
+        // back on level 1, 3 is unassigned again and has to be found as the last literal
+        let mut level2 = level1.clone();
+        assert_eq!(
+            propagate_fully(&mut wl, &cnf, &mut level2, (2, false)),
+            Ok(vec![])
+        );
+        assert_watches_complete(&wl, &cnf, &level2);
+        let mut level3 = level2.clone();
+        assert_eq!(
+            propagate_fully(&mut wl, &cnf, &mut level3, (4, false)),
+            Ok(vec![(3, true)])
+        );
+        assert_watches_complete(&wl, &cnf, &level3);
+
+        // back on level 0 with all watches moved, 1 is the last literal
+        let mut assignment = Assignment::new();
+        for &lit in [(4, false), (3, false)].iter() {
+            assert_eq!(
+                propagate_fully(&mut wl, &cnf, &mut assignment, lit),
+                Ok(vec![])
+            );
+        }
+        assert_eq!(
+            propagate_fully(&mut wl, &cnf, &mut assignment, (2, false)),
+            Ok(vec![(1, true)])
+        );
+        assert_watches_complete(&wl, &cnf, &assignment);
+    }
+
+    #[test]
+    fn test_watchedliterals_random_backtracking() {
+        let mut rng = Rng::new(789);
+
+        for _ in 0..200 {
+            // unit clauses are assigned before the search, so they are left out here
+            let num_clauses = rng.below(30) as usize;
+            let cnf = random_cnf(&mut rng, 8, num_clauses, 2..=4);
+            let mut wl = WatchedLiterals::new(&cnf);
+
+            // chronological backtracking like the search: the assignment before every
+            // decision, the decision and whether it has been flipped already
+            let mut levels: Vec<(Assignment, LiteralTpl, bool)> = Vec::new();
+            let mut assignment = Assignment::new();
+            let satisfiable = 'search: loop {
+                let mut decision = match (1..=8).find(|&var| assignment.get(var).is_none()) {
+                    Some(var) => (var, rng.below(2) == 0),
+                    None => break true,
+                };
+                levels.push((assignment.clone(), decision, false));
+                while let Err(clause_idx) =
+                    propagate_fully(&mut wl, &cnf, &mut assignment, decision)
+                {
+                    let clause = &cnf.clauses[clause_idx];
+                    assert!(clause
+                        .literals()
+                        .all(|l| assignment.get_lit(l) == Some(false)));
+                    decision = loop {
+                        match levels.pop() {
+                            Some((before, lit, false)) => {
+                                assignment = before.clone();
+                                levels.push((before, negate(lit), true));
+                                break negate(lit);
+                            }
+                            Some(_) => {}
+                            None => break 'search false,
+                        }
+                    };
+                }
+                assert_watches_complete(&wl, &cnf, &assignment);
+                assert_invariants(&wl, &cnf);
+            };
+
+            if satisfiable {
+                assert!(cnf.is_satisfied(&assignment));
+            }
+            assert_eq!(satisfiable, is_satisfiable(&cnf).0, "{:?}", cnf);
+        }
+    }
+
+    #[test]
+    fn test_watchedliteral_remove_clause() {
+        let cnf = parse_cnf_from_str("1 2\n-1\n2 3\n-3 4").unwrap();
+        let mut wl = WatchedLiterals::new(&cnf);
+        wl.remove_clause(0);
+
+        let mut cnf = cnf;
+        cnf.clauses.remove(0);
+        assert_invariants(&wl, &cnf);
+        assert_eq!(wl.watched_literals.len(), 3);
+        assert_eq!(wl.access_map[&(1, true)], Vec::<usize>::new());
+        assert_eq!(wl.access_map[&(2, true)], vec![1]);
+        assert_eq!(wl.access_map[&(4, true)], vec![2]);
+
+        // a clause without watches
+        wl.remove_clause(0);
+        cnf.clauses.remove(0);
+        assert_invariants(&wl, &cnf);
+        assert_eq!(wl.access_map[&(3, false)], vec![1]);
+    }
+
+    #[test]
+    fn test_watchedliteral_insert_clause() {
+        let mut cnf = parse_cnf_from_str("1 2\n-1\n2 3").unwrap();
+        let mut wl = WatchedLiterals::new(&cnf);
+        let inserted = parse_cnf_from_str("-2 4 -3").unwrap().clauses.remove(0);
+        wl.insert_clause(1, &inserted);
+        cnf.clauses.insert(1, inserted);
+        assert_invariants(&wl, &cnf);
+        assert_eq!(wl.access_map[&(2, true)], vec![0, 3]);
+        assert_eq!(wl.access_map[&(2, false)], vec![1]);
+
+        // a clause without watches
+        wl.insert_clause(0, &Clause::new());
+        cnf.clauses.insert(0, Clause::new());
+        assert_invariants(&wl, &cnf);
+        assert_eq!(wl.access_map[&(3, true)], vec![4]);
+    }
+
+    #[test]
+    fn test_watchedliteral_unit_clause() {
+        let mut cnf = parse_cnf_from_str("1 2 3\n-1 4").unwrap();
+        let mut wl = WatchedLiterals::new(&cnf);
+        let unit = Clause::from_literals(&[(3, false)]);
+        wl.add_clause(&unit);
+        cnf.clauses.push(unit);
+        assert_invariants(&wl, &cnf);
+
+        // the next update forces the literal of the unit clause
+        let mut assignment = Assignment::new_with(1, false);
+        assert_eq!(
+            wl.update(&cnf, &assignment, (1, false)),
+            UpdateResult::Satisfiable {
+                propagations: vec![((3, false), 2)]
+            }
+        );
+        assignment.change(3, false);
+        assert_eq!(
+            wl.update(&cnf, &assignment, (3, false)),
+            UpdateResult::Satisfiable {
+                propagations: vec![((2, true), 0)]
+            }
+        );
+
+        // once propagated, it still watches its literal
+        let assignment = Assignment::new_with(3, true);
+        assert_eq!(
+            wl.update(&cnf, &assignment, (3, true)),
+            UpdateResult::Unsatisfiable { clause_idx: 2 }
+        );
+
+        // a unit clause already false when it is added
+        let unit = Clause::from_literals(&[(4, false)]);
+        wl.insert_clause(0, &unit);
+        cnf.clauses.insert(0, unit);
+        assert_invariants(&wl, &cnf);
+        let assignment = Assignment::new_with(4, true).with(1, true);
+        assert_eq!(
+            wl.update(&cnf, &assignment, (1, true)),
+            UpdateResult::Unsatisfiable { clause_idx: 0 }
+        );
+    }
+
+    #[test]
+    fn test_watchedliteral_tautology() {
+        // both literals of 1 are watched, the false one is the second
+        let cnf = Cnf::new_with(vec![Clause::from_literals(&[
+            (1, false),
+            (1, true),
+            (2, true),
+        ])]);
+        let mut wl = WatchedLiterals::new(&cnf);
+        let assignment = Assignment::new_with(1, false);
+        assert_eq!(
+            wl.update(&cnf, &assignment, (1, false)),
+            UpdateResult::Satisfiable {
+                propagations: vec![]
+            }
+        );
+        assert_invariants(&wl, &cnf);
+        // the other watch satisfies the clause, so the false one is kept
+        assert!(two_literal_eq(
+            wl.watched_literals[0].unwrap(),
+            (1, false),
+            (1, true)
+        ));
+    }
+
+    #[test]
+    fn test_watchedliteral_unwatch_and_retain_clauses() {
+        let cnf = parse_cnf_from_str("1 2\n-1\n2 3\n-3 4").unwrap();
+        let mut wl = WatchedLiterals::new(&cnf);
+        wl.unwatch_clause(2);
+        assert_eq!(wl.watched_literals.len(), 4);
+        assert_eq!(wl.watched_literals[2], None);
+        assert_eq!(wl.access_map[&(2, true)], vec![0]);
+        assert_eq!(wl.access_map[&(3, true)], Vec::<usize>::new());
+
+        wl.retain_clauses(&[false, true, false, true]);
+        let cnf = parse_cnf_from_str("-1\n-3 4").unwrap();
+        assert_invariants(&wl, &cnf);
+        assert_eq!(wl.watched_literals.len(), 2);
+        assert_eq!(wl.access_map[&(1, true)], Vec::<usize>::new());
+        assert_eq!(wl.access_map[&(4, true)], vec![1]);
     }
 }
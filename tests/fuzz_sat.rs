@@ -1,44 +1,66 @@
 use satsolver::assignment::Assignment;
 use satsolver::cnf::Var;
 use satsolver::cnf::{Clause, Cnf};
-use satsolver::satsolve;
+use satsolver::rng::Rng;
+use satsolver::satsolve::{self, Solver, SolverConfig};
 
 #[test]
 //#[ignore]
 fn fuzzy_test_randomly() {
-    for _ in 0..10 {
-        test_fuzzy_instance();
+    for seed in 0..10 {
+        test_fuzzy_instance(seed);
     }
 }
 
-fn test_fuzzy_instance() {
-    let cnf = create_rand_cnf();
-    println!("Testing clause {:?}", cnf);
+#[test]
+fn fuzzy_test_reproducible() {
+    for seed in 0..10 {
+        let cnf = create_rand_cnf(&mut Rng::new(seed));
+        assert_eq!(cnf, create_rand_cnf(&mut Rng::new(seed)));
+
+        let config = SolverConfig {
+            trace: true,
+            shuffle_seed: Some(seed),
+            ..SolverConfig::default()
+        };
+        let mut solvers = [
+            Solver::with_config(cnf.clone(), config.clone()),
+            Solver::with_config(cnf, config),
+        ];
+        let results = [solvers[0].solve(), solvers[1].solve()];
+        assert_eq!(results[0], results[1], "seed {}", seed);
+        assert_eq!(solvers[0].trace(), solvers[1].trace(), "seed {}", seed);
+    }
+}
+
+fn test_fuzzy_instance(seed: u64) {
+    let cnf = create_rand_cnf(&mut Rng::new(seed));
+    println!("Testing clause {:?} from seed {}", cnf, seed);
     let (result, _stats) = satsolve::is_satisfiable(&cnf);
     let other_result = solve_by_testing_all_combinations(&cnf);
 
     match (result, other_result) {
         (true, Some(_)) | (false, None) => {}
-        (true, None) => assert!(false, "satsolve found a wrong solution"),
-        (false, Some(a)) => assert!(false, "satsolve did not find solution {:?}", a),
+        (true, None) => panic!("satsolve found a wrong solution"),
+        (false, Some(a)) => panic!("satsolve did not find solution {:?}", a),
     }
 }
 
-fn create_rand_cnf() -> Cnf {
+fn create_rand_cnf(rng: &mut Rng) -> Cnf {
     let mut cnf = Cnf::new();
 
-    for _ in 0..rand_int(0, 20) {
+    for _ in 0..rand_int(rng, 0, 20) {
         let mut clause = Clause::new();
 
-        for _ in 0..rand_int(0, 10) {
+        for _ in 0..rand_int(rng, 0, 10) {
             let var = loop {
-                let v = rand_int(1, 20) as u32;
+                let v = rand_int(rng, 1, 20);
                 if clause.literals().all(|lit| lit.0 != v) {
                     break v;
                 }
             };
 
-            if rand_int(0, 2) == 0 {
+            if rand_int(rng, 0, 2) == 0 {
                 clause.add_negative(var);
             } else {
                 clause.add_positive(var);
@@ -50,14 +72,8 @@ fn create_rand_cnf() -> Cnf {
     cnf
 }
 
-fn rand_int(min: u32, max_exclusive: u32) -> u32 {
-    // TODO this is so ugly
-    let str = format!("{:?}", std::time::Instant::now()); // format: Instant { **.**s }
-    let str = str.split('.').nth(1).unwrap();
-    let str = str.split('s').next().unwrap();
-    let num: u32 = str.parse().expect(&format!("Could not parse {}", str));
-
-    (num % (max_exclusive - min)) + min
+fn rand_int(rng: &mut Rng, min: u32, max_exclusive: u32) -> u32 {
+    rng.below(u64::from(max_exclusive - min)) as u32 + min
 }
 
 fn solve_by_testing_all_combinations(cnf: &Cnf) -> Option<Assignment> {
@@ -74,7 +90,7 @@ fn solve_by_testing_all_combinations(cnf: &Cnf) -> Option<Assignment> {
             return Some(a.clone());
         }
 
-        if stack.last().unwrap().1 == cnf.highest_var() {
+        if stack.last().unwrap().1 >= cnf.highest_var() {
             // backtrack
             loop {
                 let Decision(top_a, top_var, top_flipped) = stack.last_mut().unwrap();